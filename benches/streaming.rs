@@ -0,0 +1,214 @@
+//! Compares [`SVG::write_to`] with `String::try_from` on a large system.
+//!
+//! Only serialisation is streamed, the [`SVG`] is built in memory either way. Each approach is measured from building
+//! the [`SVG`] to serialising it, so peak heap includes the tree. Building the tree on its own is reported first.
+//!
+//! Run with `cargo bench --bench streaming -- [ROWS] [COLUMNS]`, defaults to a 128x128 system (16384 cores).
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use manycore_parser::ManycoreSystem;
+use manycore_svg::{BaseConfiguration, Configuration, ConfigurationSource, SVG};
+
+/// Keeps track of the heap in use and its peak since the last reset.
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+fn allocated(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Generates a system where every eighth core has a task, each task sending to the next one.
+fn system_xml(rows: usize, columns: usize) -> String {
+    let cores = rows * columns;
+    let tasks = cores.div_ceil(8);
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ManycoreSystem \
+        xmlns=\"https://www.york.ac.uk/physics-engineering-technology/ManycoreSystems\" \
+        rows=\"{rows}\" columns=\"{columns}\" routingAlgo=\"RowFirst\">\n<TaskGraph>\n"
+    );
+    for task in 0..tasks {
+        let _ = writeln!(xml, "<Task id=\"{task}\" computationCost=\"40\" />");
+    }
+    for task in 1..tasks {
+        let _ = writeln!(
+            xml,
+            "<Edge from=\"{}\" to=\"{task}\" communicationCost=\"30\" />",
+            task - 1
+        );
+    }
+    xml.push_str("</TaskGraph>\n<Cores>\n");
+    for core in 0..cores {
+        let allocated_task = if core % 8 == 0 {
+            format!(" allocatedTask=\"{}\"", core / 8)
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            xml,
+            "<Core id=\"{core}\" age=\"{}\" status=\"High\" actualFrequency=\"Low\" temperature=\"{}\"{allocated_task}>\n\
+            <Router age=\"30\" status=\"Normal\" temperature=\"30\" />\n<Channels>",
+            core % 400,
+            30 + core % 50
+        );
+        for direction in ["North", "West", "East", "South"] {
+            let _ = writeln!(
+                xml,
+                "<Channel direction=\"{direction}\" age=\"30\" actualComCost=\"4\" status=\"Normal\" bandwidth=\"400\" />"
+            );
+        }
+        xml.push_str("</Channels>\n</Core>\n");
+    }
+    xml.push_str("</Cores>\n</ManycoreSystem>\n");
+
+    xml
+}
+
+/// Runs `run`, returning how long it took, the peak heap it used on top of what was already allocated and its result.
+fn measure<T>(run: impl FnOnce() -> T) -> (Duration, usize, T) {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    let start = Instant::now();
+    let ret = run();
+    let elapsed = start.elapsed();
+
+    (
+        elapsed,
+        PEAK.load(Ordering::Relaxed).saturating_sub(baseline),
+        ret,
+    )
+}
+
+fn mebibytes(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn report(name: &str, (elapsed, peak, bytes): (Duration, usize, usize)) {
+    println!(
+        "{name:<14} {:>9.1} ms {:>9.1} MiB/s {:>9.1} MiB peak heap ({:.1} MiB written)",
+        elapsed.as_secs_f64() * 1000.0,
+        mebibytes(bytes) / elapsed.as_secs_f64(),
+        mebibytes(peak),
+        mebibytes(bytes)
+    );
+}
+
+/// Counts the bytes written, without keeping them.
+struct CountingSink(usize);
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {
+    // cargo bench passes --bench
+    let mut dimensions = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(|arg| arg.parse::<usize>().expect("Dimensions must be numbers"));
+    let rows = dimensions.next().unwrap_or(128);
+    let columns = dimensions.next().unwrap_or(rows);
+
+    let path = std::env::temp_dir().join(format!("manycore_svg_bench_{}.xml", std::process::id()));
+    fs::write(&path, system_xml(rows, columns)).expect("Could not write system");
+    let mut manycore = ManycoreSystem::parse_file(path.to_str().expect("Invalid temporary path"))
+        .expect("Could not parse system");
+    let _ = fs::remove_file(&path);
+
+    let configuration = || {
+        Configuration::layered(&[
+            ConfigurationSource::parse("preset:ageing"),
+            ConfigurationSource::parse("preset:routing-load"),
+        ])
+        .expect("Could not load presets")
+    };
+
+    let render = |manycore: &mut ManycoreSystem, mut configuration: Configuration| {
+        let mut svg = SVG::try_from(&*manycore).expect("Could not convert system");
+        svg.update_configurable_information(
+            manycore,
+            &mut configuration,
+            &BaseConfiguration::default(),
+        )
+        .expect("Could not render system");
+
+        svg
+    };
+
+    let conf = configuration();
+    let (elapsed, peak, _) = measure(|| render(&mut manycore, conf));
+    println!(
+        "{rows}x{columns} system ({} cores) rendered in {:.1} ms, {:.1} MiB peak heap",
+        rows * columns,
+        elapsed.as_secs_f64() * 1000.0,
+        mebibytes(peak)
+    );
+
+    let conf = configuration();
+    report(
+        "String",
+        measure(|| {
+            String::try_from(&render(&mut manycore, conf))
+                .expect("Could not serialise SVG")
+                .len()
+        }),
+    );
+
+    let conf = configuration();
+    report(
+        "write_to",
+        measure(|| {
+            let mut sink = CountingSink(0);
+            render(&mut manycore, conf)
+                .write_to(&mut sink)
+                .expect("Could not stream SVG");
+            sink.0
+        }),
+    );
+}
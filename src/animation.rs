@@ -0,0 +1,753 @@
+use std::collections::BTreeMap;
+
+use getset::Getters;
+use manycore_parser::{
+    Directions, ElementIDT, ManycoreSystem, RoutingType, WithID, BORDER_ROUTERS_KEY, ROUTING_KEY,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_bounds::{
+        load_percentages, resolve_element_bounds, resolve_load_bounds, BoundsResolution,
+    },
+    computed_attributes::validate_computed_attributes,
+    generation_error, get_connection_type, get_fill_colour, missing_channel, missing_connection,
+    missing_source_load, missing_source_loads, missing_task, no_processing_group,
+    style::{Style, DEFAULT_FILL, EDGE_DATA_CLASS_NAME},
+    Configuration, Connection, ConnectionType, ConnectionsParentGroup, CoordinateT, DirectionType,
+    ElementKind, FieldConfiguration, FontSizeT, Offsets, SVGError, SVGErrorKind, TasksGroup,
+    TextInformation, ViewBox, FREEFORM_CLIP_PATH_ID, SVG,
+};
+
+static CHANNEL_OVERLAY_STROKE_WIDTH: &'static str = "3";
+static CONTROL_WIDTH: CoordinateT = 90;
+static CONTROL_HEIGHT: CoordinateT = 40;
+static CONTROL_PADDING: CoordinateT = 10;
+static PLAY_ACTION: &'static str = "this.ownerSVGElement.unpauseAnimations()";
+static PAUSE_ACTION: &'static str = "this.ownerSVGElement.pauseAnimations()";
+
+/// Object representation of user-defined animation settings.
+/// * `frame_duration`: How long each snapshot is displayed for, in milliseconds.
+/// * `controls`: Whether to include play/pause controls in the animated [`SVG`].
+#[derive(Serialize, Deserialize, Getters, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct AnimationSettings {
+    frame_duration: u32,
+    controls: bool,
+}
+
+impl AnimationSettings {
+    /// Generates a new [`AnimationSettings`] instance from the given parameters.
+    pub fn new(frame_duration: u32, controls: bool) -> Self {
+        Self {
+            frame_duration,
+            controls,
+        }
+    }
+}
+
+/// Object representation of an SVG `<animate>` or `<animateTransform>` element.
+/// Values are changed discretely, one per frame.
+#[derive(Serialize)]
+struct Animate {
+    #[serde(rename = "@href", skip_serializing_if = "Option::is_none")]
+    href: Option<String>,
+    #[serde(rename = "@attributeName")]
+    attribute_name: &'static str,
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    transform_type: Option<&'static str>,
+    #[serde(rename = "@values")]
+    values: String,
+    #[serde(rename = "@keyTimes")]
+    key_times: String,
+    #[serde(rename = "@dur")]
+    dur: String,
+    #[serde(rename = "@calcMode")]
+    calc_mode: &'static str,
+    #[serde(rename = "@repeatCount")]
+    repeat_count: &'static str,
+}
+
+impl Animate {
+    /// Generates a new [`Animate`] instance from the given parameters.
+    fn new(
+        href: Option<String>,
+        attribute_name: &'static str,
+        transform_type: Option<&'static str>,
+        values: Vec<String>,
+        dur: &String,
+    ) -> Self {
+        // Each frame starts at an even fraction of the duration
+        let frames = values.len();
+        let key_times = (0..frames)
+            .map(|frame| (frame as f64 / frames as f64).to_string())
+            .collect::<Vec<String>>()
+            .join(";");
+
+        Self {
+            href: href.map(|id| format!("#{id}")),
+            attribute_name,
+            transform_type,
+            values: values.join(";"),
+            key_times,
+            dur: dur.clone(),
+            calc_mode: "discrete",
+            repeat_count: "indefinite",
+        }
+    }
+}
+
+/// Object representation of an SVG `<path>` drawn on top of a channel connection to display its load colour.
+#[derive(Serialize)]
+struct ChannelOverlay {
+    #[serde(rename = "@d")]
+    d: String,
+    #[serde(rename = "@fill")]
+    fill: &'static str,
+    #[serde(rename = "@stroke")]
+    stroke: &'static str,
+    #[serde(rename = "@stroke-width")]
+    stroke_width: &'static str,
+    #[serde(rename = "@class", skip_serializing_if = "Option::is_none")]
+    class: Option<&'static str>,
+    #[serde(rename = "@clip-path")]
+    clip_path: String,
+    animate: Animate,
+}
+
+/// Object representation of an SVG `<rect>` used as animation control button.
+#[derive(Serialize)]
+struct ControlRect {
+    #[serde(rename = "@x")]
+    x: CoordinateT,
+    #[serde(rename = "@y")]
+    y: CoordinateT,
+    #[serde(rename = "@width")]
+    width: CoordinateT,
+    #[serde(rename = "@height")]
+    height: CoordinateT,
+    #[serde(rename = "@rx")]
+    rx: &'static str,
+    #[serde(rename = "@fill")]
+    fill: &'static str,
+    #[serde(rename = "@stroke")]
+    stroke: &'static str,
+}
+
+/// Object representation of an SVG `<g>` that acts as an animation control button.
+#[derive(Serialize)]
+struct AnimationControl {
+    #[serde(rename = "@onclick")]
+    onclick: &'static str,
+    #[serde(rename = "@cursor")]
+    cursor: &'static str,
+    rect: ControlRect,
+    text: TextInformation,
+}
+
+impl AnimationControl {
+    /// Generates a new [`AnimationControl`] instance at the given coordinates.
+    fn new(
+        x: CoordinateT,
+        y: CoordinateT,
+        label: &'static str,
+        onclick: &'static str,
+        font_size: FontSizeT,
+    ) -> Self {
+        Self {
+            onclick,
+            cursor: "pointer",
+            rect: ControlRect {
+                x,
+                y,
+                width: CONTROL_WIDTH,
+                height: CONTROL_HEIGHT,
+                rx: "10",
+                fill: DEFAULT_FILL,
+                stroke: "black",
+            },
+            text: TextInformation::new(
+                x.saturating_add(CONTROL_WIDTH.saturating_div(2)),
+                y.saturating_add(CONTROL_HEIGHT.saturating_div(2)),
+                font_size,
+                "middle",
+                "central",
+                None,
+                None,
+                label.to_string(),
+            ),
+        }
+    }
+}
+
+/// Object representation of the SVG `<g>` that wraps all animation elements of an animated [`SVG`].
+#[derive(Serialize, Default)]
+pub(crate) struct AnimationGroup {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "path")]
+    channel_overlays: Vec<ChannelOverlay>,
+    animate: Vec<Animate>,
+    #[serde(rename = "animateTransform")]
+    animate_transforms: Vec<Animate>,
+    #[serde(rename = "g")]
+    controls: Vec<AnimationControl>,
+}
+
+/// Utility to retrieve an SVG connection and whether it is an edge connection.
+fn get_connection<'a>(
+    connections_group: &'a ConnectionsParentGroup,
+    direction_type: &DirectionType,
+    core_id: &ElementIDT,
+) -> Result<(&'a Connection, bool), SVGError> {
+    match get_connection_type(connections_group, direction_type, core_id)? {
+        ConnectionType::Connection(idx) => Ok((
+            connections_group
+                .connections()
+                .path()
+                .get(*idx)
+                .ok_or(missing_connection(idx))?,
+            false,
+        )),
+        ConnectionType::EdgeConnection(idx) => {
+            let edge_connections = match direction_type {
+                DirectionType::Out(_) => connections_group.edge_connections().sink(),
+                DirectionType::Source(_) => connections_group.edge_connections().source(),
+            };
+
+            Ok((
+                edge_connections.get(*idx).ok_or(missing_connection(idx))?,
+                true,
+            ))
+        }
+    }
+}
+
+/// Utility to generate an error when snapshots do not share the same dimensions.
+fn mismatched_snapshot(index: usize) -> SVGError {
+    SVGError::new(SVGErrorKind::ManycoreMismatch(format!(
+        "Snapshot {index} does not have the same dimensions as the first snapshot."
+    )))
+    .with_code("mismatched_snapshot")
+}
+
+impl SVG {
+    /// Generates an animated [`SVG`] that cycles through the provided [`ManycoreSystem`] snapshots.
+    ///
+    /// The layout is derived from the first snapshot. Each frame then displays, according to the provided [`Configuration`]:
+    /// * Core and router fills (`Fill` attributes and fill overrides). The base document is filled like a static render
+    ///   of the first snapshot, only fills that change are animated.
+    /// * Channel load colours, if routing was requested.
+    /// * Task positions. Tasks that are not allocated in a frame are hidden.
+    ///
+    /// Snapshots are mutable because routing stores channel loads in the system.
+    pub fn animate(
+        snapshots: &mut [ManycoreSystem],
+        configuration: &Configuration,
+        animation_settings: &AnimationSettings,
+    ) -> Result<SVG, SVGError> {
+        let frames = snapshots.len();
+        let first = snapshots.first().ok_or(
+            SVGError::new(SVGErrorKind::GenerationError(
+                "At least one snapshot is required to generate an animation.".to_string(),
+            ))
+            .with_code("no_snapshots"),
+        )?;
+
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            if snapshot.rows() != first.rows() || snapshot.columns() != first.columns() {
+                return Err(mismatched_snapshot(i));
+            }
+
+            validate_computed_attributes(configuration, snapshot)?;
+        }
+
+        // Bounds are derived from every frame, so that colours are comparable between frames
+        let mut configuration = configuration.duplicate()?;
+        resolve_element_bounds(
+            &mut configuration,
+            &snapshots
+                .iter()
+                .flat_map(|snapshot| snapshot.cores().list().iter())
+                .collect::<Vec<_>>(),
+            BoundsResolution::Configured,
+        );
+
+        // Every frame is routed first, for the same reason
+        let mut links_with_load = Vec::with_capacity(frames);
+        if let Some(FieldConfiguration::Routing {
+            configuration: routing_configuration,
+        }) = configuration.channel_config_mut().get_mut(ROUTING_KEY)
+        {
+            let mut percentages = Vec::new();
+            for snapshot in snapshots.iter_mut() {
+                let links = snapshot.route(routing_configuration.algorithm())?;
+                percentages.extend(load_percentages(snapshot, &links));
+                links_with_load.push(links);
+            }
+
+            resolve_load_bounds(routing_configuration, percentages);
+        }
+        let configuration = &configuration;
+
+        let first = &snapshots[0];
+        let mut ret = SVG::try_from(first)?;
+        let mut animation_group = AnimationGroup::default();
+        animation_group.id = ret.namespace.id("animation");
+        let dur = format!(
+            "{}ms",
+            u64::from(*animation_settings.frame_duration()).saturating_mul(u64::try_from(frames)?)
+        );
+
+        // Border routers, just like a static render
+        let show_border_routers = matches!(
+            configuration.channel_config().get(BORDER_ROUTERS_KEY),
+            Some(FieldConfiguration::Boolean { value: true })
+        );
+        if show_border_routers {
+            ret.style = Style::base(&ret.namespace);
+            let ViewBox {
+                x,
+                y,
+                width,
+                height,
+            } = ret.borders_view_box;
+            ret.view_box.swap(x, y, width, height);
+        }
+
+        let routing_configuration = match configuration.channel_config().get(ROUTING_KEY) {
+            Some(FieldConfiguration::Routing { configuration }) => Some(configuration),
+            _ => None,
+        };
+
+        // Colours are stored per frame, indexed by element.
+        let mut core_fills: Vec<Vec<String>> = Vec::with_capacity(first.cores().list().len());
+        let mut router_fills: Vec<Vec<String>> = Vec::with_capacity(first.cores().list().len());
+        // (core index, is source, direction) -> per-frame load colour, if routed.
+        let mut channel_colours: BTreeMap<(usize, bool, Directions), Vec<Option<String>>> =
+            BTreeMap::new();
+        // Task ID -> per-frame core index, if allocated.
+        let mut task_positions: BTreeMap<u16, Vec<Option<usize>>> = BTreeMap::new();
+
+        for (f, snapshot) in snapshots.iter().enumerate() {
+            let links_with_load = links_with_load.get(f);
+
+            for (i, core) in snapshot.cores().list().iter().enumerate() {
+                // Fills
+                let core_fill = get_fill_colour(
+                    configuration.core_config(),
+                    configuration.computed_attributes(),
+                    configuration.core_fills(),
+                    core,
+                )
+                .map_or(DEFAULT_FILL.to_string(), |fill| fill.clone());
+                let router_fill = get_fill_colour(
+                    configuration.router_config(),
+                    configuration.computed_attributes(),
+                    configuration.router_fills(),
+                    core.router(),
+                )
+                .map_or(DEFAULT_FILL.to_string(), |fill| fill.clone());
+
+                if f == 0 {
+                    core_fills.push(Vec::with_capacity(frames));
+                    router_fills.push(Vec::with_capacity(frames));
+                }
+                core_fills
+                    .get_mut(i)
+                    .ok_or_else(|| mismatched_snapshot(f))?
+                    .push(core_fill);
+                router_fills
+                    .get_mut(i)
+                    .ok_or_else(|| mismatched_snapshot(f))?
+                    .push(router_fill);
+
+                // Tasks
+                if let Some(task_id) = core.allocated_task() {
+                    task_positions.entry(*task_id).or_insert(vec![None; frames])[f] = Some(i);
+                }
+
+                // Channel loads
+                if let (Some(links_with_load), Some(routing_configuration)) =
+                    (links_with_load, routing_configuration)
+                {
+                    if let Some(routed_channels) = links_with_load.get(core.id()) {
+                        for (target, directions) in routed_channels {
+                            for direction in directions {
+                                let channel = core
+                                    .channels()
+                                    .channel()
+                                    .get(direction)
+                                    .ok_or(missing_channel(core.id(), direction))?;
+
+                                let (is_source, load) = match target {
+                                    RoutingType::OutputChannel => (false, channel.current_load()),
+                                    RoutingType::SourceChannel => (
+                                        true,
+                                        core.source_loads()
+                                            .as_ref()
+                                            .ok_or(missing_source_loads(core.id()))?
+                                            .get(direction)
+                                            .ok_or(missing_source_load(core.id(), direction))?,
+                                    ),
+                                };
+
+                                let (_, fill) = TextInformation::calculate_load_fill_and_percentage(
+                                    load,
+                                    channel.bandwidth(),
+                                    routing_configuration,
+                                );
+
+                                channel_colours
+                                    .entry((i, is_source, *direction))
+                                    .or_insert(vec![None; frames])[f] = fill.cloned();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Fills of the first frame are applied like in a static render, so elements whose fill never changes keep it
+        let mut class_updates = BTreeMap::new();
+        for (i, (core_frames, router_frames)) in core_fills.iter().zip(&router_fills).enumerate() {
+            let mut fill_class = |frames: &[String]| {
+                frames
+                    .first()
+                    .filter(|fill| fill.as_str() != DEFAULT_FILL)
+                    .map(|fill| ret.style.fill_class(fill, &ret.namespace))
+            };
+            let core_class = fill_class(core_frames);
+            let router_class = fill_class(router_frames);
+
+            ret.root
+                .processing_group
+                .g_mut()
+                .get_mut(i)
+                .ok_or(no_processing_group(i))?
+                .set_fill_classes(core_class, router_class, &mut class_updates);
+        }
+
+        let processing_groups = ret.root.processing_group.g();
+
+        // Fill animations, only for elements whose fill changes.
+        for (i, (core_frames, router_frames)) in core_fills
+            .into_iter()
+            .zip(router_fills.into_iter())
+            .enumerate()
+        {
+            let processing_group = processing_groups.get(i).ok_or(no_processing_group(i))?;
+
+            if core_frames.iter().any(|fill| *fill != core_frames[0]) {
+                animation_group.animate.push(Animate::new(
+                    Some(processing_group.core().id().clone()),
+                    "fill",
+                    None,
+                    core_frames,
+                    &dur,
+                ));
+            }
+
+            if router_frames.iter().any(|fill| *fill != router_frames[0]) {
+                animation_group.animate.push(Animate::new(
+                    Some(processing_group.router().id().clone()),
+                    "fill",
+                    None,
+                    router_frames,
+                    &dur,
+                ));
+            }
+        }
+
+        // Channel load overlays
+        for ((i, is_source, direction), colours) in channel_colours {
+            let core = first.cores().list().get(i).ok_or(no_processing_group(i))?;
+            let direction_type = if is_source {
+                DirectionType::Source(direction)
+            } else {
+                DirectionType::Out(direction)
+            };
+
+            let (connection, edge) =
+                get_connection(&ret.root.connections_group, &direction_type, core.id())?;
+
+            animation_group.channel_overlays.push(ChannelOverlay {
+                d: connection.d().clone(),
+                fill: "none",
+                stroke: "none",
+                stroke_width: CHANNEL_OVERLAY_STROKE_WIDTH,
+                class: if edge {
+                    Some(EDGE_DATA_CLASS_NAME)
+                } else {
+                    None
+                },
+                clip_path: ret.namespace.url(FREEFORM_CLIP_PATH_ID),
+                animate: Animate::new(
+                    None,
+                    "stroke",
+                    None,
+                    colours
+                        .into_iter()
+                        .map(|colour| colour.unwrap_or("none".to_string()))
+                        .collect(),
+                    &dur,
+                ),
+            });
+        }
+
+        // Task animations. Each task is anchored to the first core it is allocated to.
+        let mut offsets = Offsets::default();
+        for (task_id, positions) in task_positions {
+            let Some((anchor_frame, anchor)) = positions
+                .iter()
+                .enumerate()
+                .find_map(|(f, position)| position.map(|i| (f, i)))
+            else {
+                continue;
+            };
+            let anchor_group = processing_groups
+                .get(anchor)
+                .ok_or_else(|| no_processing_group(anchor))?;
+            let (anchor_x, anchor_y) = anchor_group.core().move_coordinates();
+
+            // Tasks first allocated after the first frame are not in the base render.
+            if ret.root.tasks_group.get(&task_id).is_none() {
+                let anchor_snapshot = &snapshots[anchor_frame];
+                let anchor_core = anchor_snapshot
+                    .cores()
+                    .list()
+                    .get(anchor)
+                    .ok_or_else(|| no_processing_group(anchor))?;
+                let allocated_task = anchor_snapshot
+                    .task_graph()
+                    .tasks()
+                    .get(&task_id)
+                    .ok_or_else(|| missing_task(anchor_core.id(), &task_id))?;
+                let (row, column) = anchor_group.coordinates();
+
+                ret.root.tasks_group.add_task(
+                    row,
+                    column,
+                    allocated_task,
+                    &ret.top_left,
+                    &ret.processed_base_configuration,
+                )?;
+            }
+
+            let task_offsets = ret
+                .root
+                .tasks_group
+                .get(&task_id)
+                .map(Offsets::from_task)
+                .ok_or_else(|| {
+                    generation_error(format!("Could not find Task {task_id} in TasksGroup."))
+                        .with_code("missing_svg_task")
+                        .with_element(ElementKind::Task, task_id)
+                })?;
+
+            let mut translations = Vec::with_capacity(frames);
+            let mut visibility = Vec::with_capacity(frames);
+            for position in positions.iter() {
+                match position {
+                    Some(i) => {
+                        let (core_x, core_y) = processing_groups
+                            .get(*i)
+                            .ok_or_else(|| no_processing_group(*i))?
+                            .core()
+                            .move_coordinates();
+                        let delta_x = core_x.saturating_sub(*anchor_x);
+                        let delta_y = core_y.saturating_sub(*anchor_y);
+
+                        offsets.update(task_offsets.translate(delta_x, delta_y));
+                        translations.push(format!("{delta_x} {delta_y}"));
+                        visibility.push("visible".to_string());
+                    }
+                    None => {
+                        translations.push("0 0".to_string());
+                        visibility.push("hidden".to_string());
+                    }
+                }
+            }
+
+            let task_svg_id = TasksGroup::make_task_id(&task_id, &ret.namespace);
+            if translations.iter().any(|t| *t != translations[0]) {
+                animation_group.animate_transforms.push(Animate::new(
+                    Some(task_svg_id.clone()),
+                    "transform",
+                    Some("translate"),
+                    translations,
+                    &dur,
+                ));
+            }
+            if visibility.iter().any(|v| *v != visibility[0]) {
+                animation_group.animate.push(Animate::new(
+                    Some(task_svg_id),
+                    "visibility",
+                    None,
+                    visibility,
+                    &dur,
+                ));
+            }
+        }
+        ret.root.tasks_group.set_ids(&ret.namespace);
+
+        // Make room for moved tasks
+        ret.view_box.fit_offsets(&offsets);
+
+        // Controls go above everything else
+        if *animation_settings.controls() {
+            let controls_height = CONTROL_HEIGHT.saturating_add(CONTROL_PADDING.saturating_mul(2));
+            ret.view_box.extend_top(controls_height);
+
+            let x = ret.view_box.x().saturating_add(CONTROL_PADDING);
+            let y = ret.view_box.y().saturating_add(CONTROL_PADDING);
+            let font_size = *ret.processed_base_configuration.attribute_font_size();
+
+            animation_group.controls.push(AnimationControl::new(
+                x,
+                y,
+                "Play",
+                PLAY_ACTION,
+                font_size,
+            ));
+            animation_group.controls.push(AnimationControl::new(
+                x.saturating_add(CONTROL_WIDTH)
+                    .saturating_add(CONTROL_PADDING),
+                y,
+                "Pause",
+                PAUSE_ACTION,
+                font_size,
+            ));
+        }
+
+        ret.root.animation_group = Some(animation_group);
+
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::{PAUSE_ACTION, PLAY_ACTION};
+    use crate::{
+        test_utils::read_configuration, AnimationSettings, BaseConfiguration, Configuration,
+        FieldConfiguration, SVG,
+    };
+
+    #[test]
+    fn can_animate_snapshots() {
+        let manycore = |path: &str| {
+            ManycoreSystem::parse_file(path)
+                .unwrap_or_else(|_| panic!("Could not read input test file \"{path}\""))
+        };
+        let mut snapshots = vec![
+            manycore("tests/VisualiserOutput1.xml"),
+            manycore("tests/VisualiserOutput1_next.xml"),
+        ];
+
+        let configuration = read_configuration("tests/conf2.json");
+        let age_colour = |age: f64| match configuration.core_config().get("@age") {
+            Some(FieldConfiguration::Fill { colour_settings }) => colour_settings.colour(age),
+            other => panic!("Unexpected core age configuration {other:?}"),
+        };
+
+        let svg = SVG::animate(
+            &mut snapshots,
+            &configuration,
+            &AnimationSettings::new(500, true),
+        )
+        .expect("Could not generate animated SVG.");
+
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        assert!(res.contains("<g id=\"animation\">"));
+        // Core 0 ages from 238 to 38, its fill is the only one that changes
+        assert_eq!(res.matches("attributeName=\"fill\"").count(), 1);
+        assert!(res.contains(&format!(
+            "<animate href=\"#c0\" attributeName=\"fill\" values=\"{};{}\" keyTimes=\"0;0.5\" dur=\"1000ms\" calcMode=\"discrete\" repeatCount=\"indefinite\"/>",
+            age_colour(238.0),
+            age_colour(38.0)
+        )));
+        // Task 3 moves from core 1 to core 0, one core to the left
+        assert!(res.contains(
+            "<animateTransform href=\"#t3\" attributeName=\"transform\" type=\"translate\" values=\"0 0;-414 0\" keyTimes=\"0;0.5\" dur=\"1000ms\" calcMode=\"discrete\" repeatCount=\"indefinite\"/>"
+        ));
+        assert_eq!(res.matches("<animateTransform").count(), 1);
+
+        // Fills that never change are applied like in a static render of the first frame
+        let mut first = manycore("tests/VisualiserOutput1.xml");
+        let mut first_svg = SVG::try_from(&first).expect("Could not convert Manycore to SVG.");
+        first_svg
+            .update_configurable_information(
+                &mut first,
+                &mut read_configuration("tests/conf2.json"),
+                &BaseConfiguration::default(),
+            )
+            .expect("Could not update SVG.");
+        assert_eq!(
+            quick_xml::se::to_string_with_root("g", &svg.root.processing_group)
+                .expect("Could not serialise processing group"),
+            quick_xml::se::to_string_with_root("g", &first_svg.root.processing_group)
+                .expect("Could not serialise processing group")
+        );
+
+        let controls = &svg
+            .root
+            .animation_group
+            .as_ref()
+            .expect("Missing animation group")
+            .controls;
+        assert_eq!(controls.len(), 2);
+        assert_eq!(controls[0].onclick, PLAY_ACTION);
+        assert_eq!(controls[1].onclick, PAUSE_ACTION);
+        assert!(controls[0].rect.x < controls[1].rect.x);
+        assert!(res.contains(&format!("<g onclick=\"{PLAY_ACTION}\" cursor=\"pointer\">")));
+        assert!(res.contains(&format!(
+            "<g onclick=\"{PAUSE_ACTION}\" cursor=\"pointer\">"
+        )));
+    }
+
+    #[test]
+    fn resolves_auto_bounds_across_frames() {
+        let manycore = |path: &str| {
+            ManycoreSystem::parse_file(path)
+                .unwrap_or_else(|_| panic!("Could not read input test file \"{path}\""))
+        };
+        let mut snapshots = vec![
+            manycore("tests/VisualiserOutput1.xml"),
+            manycore("tests/VisualiserOutput1_next.xml"),
+        ];
+        let configuration: Configuration = serde_json::from_str(
+            r##"{
+                "coreConfig": {
+                    "@age": {
+                        "type": "Fill",
+                        "autoBounds": "EqualWidth",
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let svg = SVG::animate(
+            &mut snapshots,
+            &configuration,
+            &AnimationSettings::new(500, false),
+        )
+        .expect("Could not generate animated SVG.");
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        // Ages range from 10 to 478 across both frames, bounds are [127, 244, 361, 478]
+        assert!(
+            res.contains("<animate href=\"#c0\" attributeName=\"fill\" values=\"#eab308;#22c55e\"")
+        );
+    }
+}
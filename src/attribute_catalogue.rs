@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use getset::Getters;
+use manycore_parser::{ManycoreSystem, WithID, WithXMLAttributes, ID_KEY};
+use serde::Serialize;
+
+use crate::{
+    parse_colour_value,
+    validation::{ATTRIBUTE_TYPES, CHANNEL_ATTRIBUTE_TYPES},
+    SVG,
+};
+
+#[cfg(doc)]
+use crate::FieldConfiguration;
+
+/// Type of the values of an attribute.
+/// * [`Numeric`][`ValueType::Numeric`]: Every value is a number. Can be coloured.
+/// * [`Categorical`][`ValueType::Categorical`]: At least one value is not a number. Can only be displayed as text.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Numeric,
+    Categorical,
+}
+
+/// Summary of the values of an attribute across all elements of a kind.
+/// * `value_type`: [`ValueType`] of the values.
+/// * `count`: Number of elements that have the attribute.
+/// * `min`, `max`: Observed range, for numeric attributes.
+/// * `values`: Distinct values, sorted, for categorical attributes.
+/// * `field_types`: [`FieldConfiguration`] variants that can be used for the attribute.
+#[derive(Serialize, Getters, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct AttributeSummary {
+    value_type: ValueType,
+    count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    values: Vec<String>,
+    field_types: Vec<&'static str>,
+}
+
+impl AttributeSummary {
+    /// Summarises `values`. `numeric_types` are the field types available to numeric values.
+    /// Values are numeric if they can be coloured, see [`parse_colour_value`].
+    fn new(values: &[&str], numeric_types: &[&'static str]) -> Self {
+        let numbers: Vec<f64> = values
+            .iter()
+            .filter_map(|value| parse_colour_value(value))
+            .collect();
+
+        if numbers.len() == values.len() {
+            Self {
+                value_type: ValueType::Numeric,
+                count: values.len(),
+                min: numbers.iter().copied().reduce(f64::min),
+                max: numbers.iter().copied().reduce(f64::max),
+                values: Vec::new(),
+                field_types: numeric_types.to_vec(),
+            }
+        } else {
+            let distinct: BTreeSet<&str> = values.iter().copied().collect();
+
+            Self {
+                value_type: ValueType::Categorical,
+                count: values.len(),
+                min: None,
+                max: None,
+                values: distinct.into_iter().map(str::to_string).collect(),
+                field_types: vec!["Text"],
+            }
+        }
+    }
+}
+
+/// Attributes available on the elements of a [`ManycoreSystem`], keyed by attribute, e.g. `@age`.
+/// Core and router IDs are listed under `@id`.
+#[derive(Serialize, Getters, Debug, Clone, PartialEq)]
+#[getset(get = "pub")]
+pub struct AttributeCatalogue {
+    core: BTreeMap<String, AttributeSummary>,
+    router: BTreeMap<String, AttributeSummary>,
+    channel: BTreeMap<String, AttributeSummary>,
+}
+
+/// Summarises the attributes of `targets`.
+fn catalogue<T: WithXMLAttributes>(
+    targets: &[&T],
+    numeric_types: &[&'static str],
+) -> BTreeMap<String, AttributeSummary> {
+    let mut values: BTreeMap<&String, Vec<&str>> = BTreeMap::new();
+
+    for target in targets {
+        if let Some(attributes) = target.other_attributes() {
+            for (key, value) in attributes {
+                values.entry(key).or_default().push(value.as_str());
+            }
+        }
+    }
+
+    values
+        .into_iter()
+        .map(|(key, values)| (key.clone(), AttributeSummary::new(&values, numeric_types)))
+        .collect()
+}
+
+/// Summarises the IDs of `targets`. IDs are only displayed as text.
+fn id_summary(ids: Vec<String>) -> AttributeSummary {
+    let values: Vec<&str> = ids.iter().map(String::as_str).collect();
+
+    AttributeSummary::new(&values, &["Text"])
+}
+
+impl SVG {
+    /// Lists the attributes available on the cores, routers and channels of a [`ManycoreSystem`], with their value
+    /// type, observed values and the [`FieldConfiguration`] variants that can be used for them.
+    pub fn attribute_catalogue(manycore: &ManycoreSystem) -> AttributeCatalogue {
+        let cores: Vec<_> = manycore.cores().list().iter().collect();
+        let routers: Vec<_> = cores.iter().map(|core| core.router()).collect();
+        let channels: Vec<_> = cores
+            .iter()
+            .flat_map(|core| core.channels().channel().values())
+            .collect();
+
+        let mut core = catalogue(&cores, &ATTRIBUTE_TYPES);
+        core.insert(
+            ID_KEY.to_string(),
+            id_summary(cores.iter().map(|core| core.id().to_string()).collect()),
+        );
+
+        let mut router = catalogue(&routers, &ATTRIBUTE_TYPES);
+        router.insert(
+            ID_KEY.to_string(),
+            id_summary(
+                routers
+                    .iter()
+                    .map(|router| router.id().to_string())
+                    .collect(),
+            ),
+        );
+
+        AttributeCatalogue {
+            core,
+            router,
+            channel: catalogue(&channels, &CHANNEL_ATTRIBUTE_TYPES),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::{ManycoreSystem, ID_KEY};
+
+    use super::{AttributeSummary, ValueType};
+    use crate::{validation::ATTRIBUTE_TYPES, SVG};
+
+    #[test]
+    fn lists_available_attributes() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+        let catalogue = SVG::attribute_catalogue(&manycore);
+
+        let age = catalogue.core().get("@age").expect("No core age");
+        assert_eq!(*age.value_type(), ValueType::Numeric);
+        assert_eq!(*age.count(), manycore.cores().list().len());
+        assert!(age
+            .min()
+            .is_some_and(|min| age.max().is_some_and(|max| min <= max)));
+        assert_eq!(*age.field_types(), vec!["Text", "ColouredText", "Fill"]);
+
+        let status = catalogue.core().get("@status").expect("No core status");
+        assert_eq!(*status.value_type(), ValueType::Categorical);
+        assert!(status.values().contains(&"High".to_string()));
+        assert_eq!(*status.field_types(), vec!["Text"]);
+
+        assert!(catalogue.router().contains_key(ID_KEY));
+        assert_eq!(
+            *catalogue
+                .channel()
+                .get("@age")
+                .expect("No channel age")
+                .field_types(),
+            vec!["Text", "ColouredText"]
+        );
+    }
+
+    #[test]
+    fn classifies_values_like_rendering() {
+        let signed = AttributeSummary::new(&["-2.5", "10"], &ATTRIBUTE_TYPES);
+        assert_eq!(*signed.value_type(), ValueType::Numeric);
+        assert_eq!(*signed.min(), Some(-2.5));
+
+        // Padded values cannot be coloured
+        let padded = AttributeSummary::new(&[" 5", "10"], &ATTRIBUTE_TYPES);
+        assert_eq!(*padded.value_type(), ValueType::Categorical);
+    }
+}
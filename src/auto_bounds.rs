@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+
+use getset::Getters;
+use manycore_parser::{Core, ManycoreSystem, RoutingMap, RoutingType, WithID, WithXMLAttributes};
+use serde::Serialize;
+
+use crate::{
+    computed_attributes::attribute_value, parse_colour_value, AutoBounds, ColourSettings,
+    ComputedAttribute, Configuration, FieldConfiguration, RoutingConfiguration, ScaleTransform,
+    TextInformation,
+};
+
+/// Bounds resolved for every field configured with [`AutoBounds`], keyed by attribute.
+#[derive(Serialize, Getters, Clone, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ResolvedBounds {
+    core: BTreeMap<String, [f64; 4]>,
+    router: BTreeMap<String, [f64; 4]>,
+    channel: BTreeMap<String, [f64; 4]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routing: Option<[f64; 4]>,
+}
+
+/// Which fields have their bounds resolved, and how.
+#[derive(Clone, Copy)]
+pub(crate) enum BoundsResolution {
+    /// Fields configured with [`AutoBounds`], from the values of the system being rendered.
+    Configured,
+    /// Every colour mapped field, from the values of a whole batch of systems. Fields configured with [`AutoBounds`]
+    /// keep their method, the others use [`AutoBounds::EqualWidth`]. The resolved bounds are fixed, so that they are
+    /// not derived again from each system of the batch.
+    Batch,
+}
+
+impl BoundsResolution {
+    /// Method used to resolve the bounds of a field, if any.
+    fn method(self, colour_settings: &ColourSettings) -> Option<AutoBounds> {
+        match self {
+            BoundsResolution::Configured => *colour_settings.auto_bounds(),
+            BoundsResolution::Batch => Some(
+                colour_settings
+                    .auto_bounds()
+                    .unwrap_or(AutoBounds::EqualWidth),
+            ),
+        }
+    }
+}
+
+/// Four equally wide bins between `min` and `max`.
+fn equal_width_bounds(min: f64, max: f64) -> [f64; 4] {
+    [1.0, 2.0, 3.0, 4.0].map(|i| min + (max - min) * i / 4.0)
+}
+
+/// Transforms values into the scale their bounds are expressed in.
+fn scaled(scale: &ScaleTransform, values: Vec<f64>) -> Vec<f64> {
+    values.into_iter().map(|value| scale.apply(value)).collect()
+}
+
+/// Derives bounds from `values` with the given method. Returns `None` if there are no values.
+fn resolve(method: AutoBounds, values: &mut [f64]) -> Option<[f64; 4]> {
+    if values.is_empty() {
+        return None;
+    }
+
+    match method {
+        AutoBounds::Quantiles => {
+            values.sort_unstable_by(f64::total_cmp);
+            let n = values.len();
+
+            // Nearest-rank quartiles
+            Some([1, 2, 3, 4].map(|i| values[(i * n).div_ceil(4) - 1]))
+        }
+        AutoBounds::EqualWidth => {
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+            Some(equal_width_bounds(min, max))
+        }
+        AutoBounds::StandardDeviations => {
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / n;
+            let deviation = variance.sqrt();
+
+            Some([-1.0, 0.0, 1.0, 2.0].map(|k| mean + k * deviation))
+        }
+    }
+}
+
+/// Numeric values of attribute `key` on every target.
+fn attribute_values<T: WithXMLAttributes>(
+    targets: &[&T],
+    key: &String,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+) -> Vec<f64> {
+    targets
+        .iter()
+        .filter_map(|target| attribute_value(*target, key, computed_attributes))
+        .filter_map(|value| parse_colour_value(&value))
+        .collect()
+}
+
+/// Resolves the bounds of every field in `config` selected by `resolution`.
+/// `values` provides the values of each attribute key, before scaling.
+pub(crate) fn resolve_fields(
+    config: &mut BTreeMap<String, FieldConfiguration>,
+    resolution: BoundsResolution,
+    resolved: &mut BTreeMap<String, [f64; 4]>,
+    values: impl Fn(&String) -> Vec<f64>,
+) {
+    for (key, field_configuration) in config {
+        if let Some(colour_settings) = field_configuration.colour_settings_mut() {
+            if let Some(method) = resolution.method(colour_settings) {
+                let mut values = scaled(colour_settings.scale(), values(key));
+
+                if let Some(bounds) = resolve(method, &mut values) {
+                    match resolution {
+                        BoundsResolution::Configured => colour_settings.set_bounds(bounds),
+                        BoundsResolution::Batch => colour_settings.fix_bounds(bounds),
+                    }
+                    resolved.insert(key.clone(), bounds);
+                }
+            }
+        }
+    }
+}
+
+/// Collects the load percentage of every routed channel, as displayed on the [`SVG`](crate::SVG).
+pub(crate) fn load_percentages(
+    manycore: &ManycoreSystem,
+    links_with_load: &RoutingMap,
+) -> Vec<f64> {
+    let mut ret = Vec::new();
+
+    for core in manycore.cores().list() {
+        if let Some(routed_channels) = links_with_load.get(core.id()) {
+            for (target, directions) in routed_channels {
+                for direction in directions {
+                    let Some(channel) = core.channels().channel().get(direction) else {
+                        continue;
+                    };
+
+                    let load = match target {
+                        RoutingType::OutputChannel => Some(channel.current_load()),
+                        RoutingType::SourceChannel => core
+                            .source_loads()
+                            .as_ref()
+                            .and_then(|source_loads| source_loads.get(direction)),
+                    };
+
+                    if let Some(percentage) = load.and_then(|load| {
+                        TextInformation::load_percentage(load, channel.bandwidth())
+                    }) {
+                        ret.push(percentage.into());
+                    }
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+/// Resolves the bounds of the core, router and channel fields selected by `resolution`, from the values of `cores`.
+pub(crate) fn resolve_element_bounds(
+    configuration: &mut Configuration,
+    cores: &[&Core],
+    resolution: BoundsResolution,
+) -> ResolvedBounds {
+    let mut ret = ResolvedBounds::default();
+    // Field configurations are borrowed mutably below
+    let computed_attributes = configuration.computed_attributes().clone();
+
+    let routers: Vec<_> = cores.iter().map(|core| core.router()).collect();
+    let channels: Vec<_> = cores
+        .iter()
+        .flat_map(|core| core.channels().channel().values())
+        .collect();
+
+    resolve_fields(
+        configuration.core_config_mut(),
+        resolution,
+        &mut ret.core,
+        |key| attribute_values(cores, key, &computed_attributes),
+    );
+    resolve_fields(
+        configuration.router_config_mut(),
+        resolution,
+        &mut ret.router,
+        |key| attribute_values(&routers, key, &computed_attributes),
+    );
+    resolve_fields(
+        configuration.channel_config_mut(),
+        resolution,
+        &mut ret.channel,
+        |key| attribute_values(&channels, key, &computed_attributes),
+    );
+
+    ret
+}
+
+/// Replaces the bounds of every [`ColourSettings`] configured with [`AutoBounds`] with bounds derived from the
+/// [`ManycoreSystem`]'s values. Routing load bounds are derived from the load percentages of routed channels.
+pub(crate) fn resolve_auto_bounds(
+    manycore: &ManycoreSystem,
+    configuration: &mut Configuration,
+    routing_configuration: Option<&mut RoutingConfiguration>,
+    links_with_load: Option<&RoutingMap>,
+) -> ResolvedBounds {
+    let mut ret = resolve_element_bounds(
+        configuration,
+        &manycore.cores().list().iter().collect::<Vec<_>>(),
+        BoundsResolution::Configured,
+    );
+
+    if let (Some(routing_configuration), Some(links_with_load)) =
+        (routing_configuration, links_with_load)
+    {
+        ret.routing = resolve_load_bounds(
+            routing_configuration,
+            load_percentages(manycore, links_with_load),
+        );
+    }
+
+    ret
+}
+
+/// Replaces the load bounds of `routing_configuration`, if configured with [`AutoBounds`], with bounds derived from
+/// the given load percentages.
+pub(crate) fn resolve_load_bounds(
+    routing_configuration: &mut RoutingConfiguration,
+    percentages: Vec<f64>,
+) -> Option<[f64; 4]> {
+    let load_colours = routing_configuration.load_colours_mut();
+    let method = (*load_colours.auto_bounds())?;
+
+    let mut values = scaled(load_colours.scale(), percentages);
+    let bounds = resolve(method, &mut values)?;
+    load_colours.set_bounds(bounds);
+
+    Some(bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::resolve;
+    use crate::{get_attribute_value, AutoBounds, BaseConfiguration, Configuration, SVG};
+
+    #[test]
+    fn resolves_bounds_from_statistics() {
+        assert_eq!(
+            resolve(
+                AutoBounds::Quantiles,
+                &mut [8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0]
+            ),
+            Some([2.0, 4.0, 6.0, 8.0])
+        );
+        assert_eq!(
+            resolve(AutoBounds::EqualWidth, &mut [0.0, 10.0, 100.0]),
+            Some([25.0, 50.0, 75.0, 100.0])
+        );
+        assert_eq!(
+            resolve(
+                AutoBounds::StandardDeviations,
+                &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]
+            ),
+            Some([3.0, 5.0, 7.0, 9.0])
+        );
+        // Fractional bounds keep the precision of transformed values
+        assert_eq!(
+            resolve(AutoBounds::EqualWidth, &mut [1.0, 2.0]),
+            Some([1.25, 1.5, 1.75, 2.0])
+        );
+        assert_eq!(resolve(AutoBounds::Quantiles, &mut []), None);
+    }
+
+    #[test]
+    fn reports_resolved_bounds() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration: Configuration = serde_json::from_str(
+            r##"{
+                "coreConfig": {
+                    "@age": {
+                        "type": "Fill",
+                        "autoBounds": "EqualWidth",
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        let update = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut configuration,
+                &BaseConfiguration::default(),
+            )
+            .expect("Could not update SVG.");
+
+        let key = "@age".to_string();
+        let max_age = manycore
+            .cores()
+            .list()
+            .iter()
+            .filter_map(|core| get_attribute_value(core, &key))
+            .filter_map(|age| age.parse::<u64>().ok())
+            .max()
+            .expect("No core ages");
+
+        let bounds = update
+            .resolved_bounds
+            .core()
+            .get(&key)
+            .expect("Bounds were not resolved");
+        assert_eq!(bounds[3], max_age as f64);
+        assert!(update.resolved_bounds.router().is_empty());
+    }
+}
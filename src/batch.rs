@@ -0,0 +1,400 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use manycore_parser::ManycoreSystem;
+
+use crate::{
+    auto_bounds::{resolve_element_bounds, BoundsResolution},
+    namespace::IdNamespace,
+    watch::{read_system, write_svg_atomically},
+    BaseConfiguration, Configuration, SVGError, SVGErrorKind, SVG,
+};
+
+/// Placeholders replaced in [`BatchSettings`] output templates.
+static TEMPLATE_PLACEHOLDERS: [&str; 3] = ["{stem}", "{name}", "{index}"];
+
+/// Settings for [`render_batch`].
+/// * `output_template`: Output path of each render. `{stem}`, `{name}` and `{index}` are replaced with the input's file
+///   stem, file name and position in the batch, e.g. `out/{stem}.svg`. Batches of more than one input must use at
+///   least one of them, or every render would be written to the same file.
+/// * `normalise_bounds`: Whether to derive the bounds of every `Fill` and `ColouredText` field from the values found
+///   across the whole batch, so that renders are comparable. Fields configured with
+///   [`AutoBounds`](crate::AutoBounds) keep their method, the others are spread evenly between the smallest and
+///   largest value.
+/// * `jobs`: Number of files rendered in parallel. `0` uses the available parallelism.
+pub struct BatchSettings {
+    output_template: String,
+    normalise_bounds: bool,
+    jobs: usize,
+}
+
+impl BatchSettings {
+    /// Generates a new [`BatchSettings`] instance from the given parameters.
+    pub fn new(output_template: String, normalise_bounds: bool, jobs: usize) -> Self {
+        Self {
+            output_template,
+            normalise_bounds,
+            jobs,
+        }
+    }
+}
+
+/// An input that could not be rendered.
+pub struct BatchFailure {
+    input: PathBuf,
+    error: SVGError,
+}
+
+impl BatchFailure {
+    /// Returns the path of the input that failed.
+    pub fn input(&self) -> &Path {
+        &self.input
+    }
+
+    /// Returns the reason the input failed.
+    pub fn error(&self) -> &SVGError {
+        &self.error
+    }
+}
+
+/// Outcome of [`render_batch`].
+pub struct BatchSummary {
+    rendered: Vec<PathBuf>,
+    failures: Vec<BatchFailure>,
+}
+
+impl BatchSummary {
+    /// Returns the paths of the written renders, in input order.
+    pub fn rendered(&self) -> &[PathBuf] {
+        &self.rendered
+    }
+
+    /// Returns the inputs that could not be rendered, in input order.
+    pub fn failures(&self) -> &[BatchFailure] {
+        &self.failures
+    }
+
+    /// Counts failures by [`SVGErrorKind`], keyed by [`SVGErrorKind::code`].
+    pub fn failures_by_kind(&self) -> BTreeMap<&'static str, usize> {
+        let mut ret = BTreeMap::new();
+
+        for failure in &self.failures {
+            *ret.entry(failure.error.kind().code()).or_insert(0) += 1;
+        }
+
+        ret
+    }
+}
+
+/// Matches a file name against a pattern where `*` matches any sequence of characters and `?` any single character.
+fn matches_pattern(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_pattern(&pattern[1..], name)
+                || (!name.is_empty() && matches_pattern(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_pattern(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches_pattern(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Expands a batch input into a sorted list of files.
+/// `input` can be a directory, in which case all of its `.xml` files are used, a pattern whose file name contains
+/// `*` or `?` wildcards, e.g. `runs/*.xml`, or a single file.
+pub fn expand_inputs(input: &str) -> Result<Vec<PathBuf>, SVGError> {
+    let path = Path::new(input);
+
+    let (directory, pattern) = if path.is_dir() {
+        (path, "*.xml")
+    } else {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.contains(['*', '?']) => (
+                path.parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .unwrap_or(Path::new(".")),
+                name,
+            ),
+            _ => return Ok(vec![path.to_path_buf()]),
+        }
+    };
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut ret = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+
+        if entry.file_type()?.is_file() && matches_pattern(&pattern, &name) {
+            ret.push(entry.path());
+        }
+    }
+    ret.sort();
+
+    Ok(ret)
+}
+
+/// Generates the output path of the `index`-th input.
+fn output_path(template: &str, input: &Path, index: usize) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = input
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    PathBuf::from(
+        template
+            .replace("{stem}", &stem)
+            .replace("{name}", &name)
+            .replace("{index}", &index.to_string()),
+    )
+}
+
+/// Applies `f` to every item using up to `jobs` threads. Results are in input order.
+fn parallel_map<T: Send, R: Send>(items: Vec<T>, jobs: usize, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+    let jobs = jobs.clamp(1, items.len().max(1));
+
+    let mut buckets: Vec<Vec<(usize, T)>> = (0..jobs).map(|_| Vec::new()).collect();
+    for (i, item) in items.into_iter().enumerate() {
+        buckets[i % jobs].push((i, item));
+    }
+
+    let f = &f;
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = buckets
+            .into_iter()
+            .map(|bucket| {
+                scope.spawn(move || {
+                    bucket
+                        .into_iter()
+                        .map(|(i, item)| (i, f(item)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Derives the bounds of every colour mapped field from the values found across all `systems`.
+pub(crate) fn normalise_bounds(configuration: &mut Configuration, systems: &[&ManycoreSystem]) {
+    let cores: Vec<_> = systems
+        .iter()
+        .flat_map(|manycore| manycore.cores().list().iter())
+        .collect();
+
+    resolve_element_bounds(configuration, &cores, BoundsResolution::Batch);
+}
+
+/// Renders a single [`ManycoreSystem`], read from `input`, and writes it to `output`.
+fn render_one(
+    manycore: &mut ManycoreSystem,
+    input: &Path,
+    configuration: &str,
+    base_configuration: &BaseConfiguration,
+    output: &Path,
+) -> Result<(), SVGError> {
+    // Updates consume parts of the configuration, every render needs its own copy
+    let mut configuration: Configuration = serde_json::from_str(configuration)?;
+
+    let mut svg = SVG::try_from_manycore_with_base_config(
+        manycore,
+        base_configuration,
+        IdNamespace::default(),
+    )?;
+    svg.set_source(input)?;
+    svg.update_configurable_information(manycore, &mut configuration, base_configuration)?;
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    write_svg_atomically(output, &svg)
+}
+
+/// Renders every input with the same [`Configuration`], writing outputs according to [`BatchSettings`].
+/// Inputs that fail don't stop the batch, they are reported in the returned [`BatchSummary`].
+pub fn render_batch(
+    inputs: &[PathBuf],
+    configuration: &Configuration,
+    base_configuration: &BaseConfiguration,
+    settings: &BatchSettings,
+) -> Result<BatchSummary, SVGError> {
+    if inputs.len() > 1
+        && !TEMPLATE_PLACEHOLDERS
+            .iter()
+            .any(|placeholder| settings.output_template.contains(placeholder))
+    {
+        return Err(SVGError::new(SVGErrorKind::DataConversionError(format!(
+            "Output template \"{}\" must contain one of {} to render more than one input",
+            settings.output_template,
+            TEMPLATE_PLACEHOLDERS.join(", ")
+        )))
+        .with_code("ambiguous_output_template"));
+    }
+
+    let jobs = match settings.jobs {
+        0 => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        jobs => jobs,
+    };
+
+    let systems = parallel_map(inputs.iter().collect(), jobs, |input| read_system(input));
+
+    let configuration = if settings.normalise_bounds {
+        let mut normalised = configuration.duplicate()?;
+        let parsed: Vec<&ManycoreSystem> = systems.iter().filter_map(|s| s.as_ref().ok()).collect();
+        normalise_bounds(&mut normalised, &parsed);
+
+        serde_json::to_string(&normalised)?
+    } else {
+        serde_json::to_string(configuration)?
+    };
+
+    let results = parallel_map(
+        inputs.iter().zip(systems).enumerate().collect(),
+        jobs,
+        |(i, (input, system))| {
+            let output = output_path(&settings.output_template, input, i);
+            let result = system.and_then(|mut manycore| {
+                render_one(
+                    &mut manycore,
+                    input,
+                    &configuration,
+                    base_configuration,
+                    &output,
+                )
+            });
+
+            (input, output, result)
+        },
+    );
+
+    let mut rendered = Vec::new();
+    let mut failures = Vec::new();
+    for (input, output, result) in results {
+        match result {
+            Ok(()) => rendered.push(output),
+            Err(error) => failures.push(BatchFailure {
+                input: input.clone(),
+                error,
+            }),
+        }
+    }
+
+    Ok(BatchSummary { rendered, failures })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use manycore_parser::ManycoreSystem;
+
+    use super::{expand_inputs, normalise_bounds, render_batch, BatchSettings};
+    use crate::{
+        get_attribute_value, test_utils::read_configuration, BaseConfiguration, FieldConfiguration,
+    };
+
+    #[test]
+    fn renders_batch_and_reports_failures() {
+        let dir = std::env::temp_dir().join(format!("manycore_svg_batch_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("Could not create test directory");
+
+        fs::copy("tests/VisualiserOutput1.xml", dir.join("a.xml")).expect("Could not copy system");
+        fs::copy("tests/VisualiserOutput1.xml", dir.join("b.xml")).expect("Could not copy system");
+        fs::write(dir.join("c.xml"), "not a system").expect("Could not write broken system");
+        fs::write(dir.join("notes.txt"), "").expect("Could not write unrelated file");
+
+        let inputs = expand_inputs(dir.join("*.xml").to_str().expect("Invalid test path"))
+            .expect("Could not expand inputs");
+        assert_eq!(inputs.len(), 3);
+
+        let template = dir.join("out").join("{stem}.svg");
+        let summary = render_batch(
+            &inputs,
+            &read_configuration("tests/conf3.json"),
+            &BaseConfiguration::default(),
+            &BatchSettings::new(
+                template.to_str().expect("Invalid test path").to_string(),
+                true,
+                2,
+            ),
+        )
+        .expect("Could not render batch");
+
+        assert_eq!(summary.rendered().len(), 2);
+        assert!(dir.join("out").join("a.svg").exists());
+        assert!(dir.join("out").join("b.svg").exists());
+        assert_eq!(summary.failures().len(), 1);
+        assert_eq!(summary.failures()[0].input(), dir.join("c.xml"));
+        assert_eq!(summary.failures_by_kind().get("ManycoreError"), Some(&1));
+
+        // Every render would overwrite the previous one
+        let error = render_batch(
+            &inputs,
+            &read_configuration("tests/conf3.json"),
+            &BaseConfiguration::default(),
+            &BatchSettings::new(
+                dir.join("out.svg")
+                    .to_str()
+                    .expect("Invalid test path")
+                    .to_string(),
+                false,
+                2,
+            ),
+        );
+        assert!(matches!(
+            error.map_err(|error| error.kind().code()),
+            Err("DataConversionError")
+        ));
+
+        fs::remove_dir_all(&dir).expect("Could not clean up test directory");
+    }
+
+    #[test]
+    fn normalises_bounds_to_batch_range() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration = read_configuration("tests/conf3.json");
+
+        normalise_bounds(&mut configuration, &[&manycore]);
+
+        let key = "@age".to_string();
+        let max_age = manycore
+            .cores()
+            .list()
+            .iter()
+            .filter_map(|core| get_attribute_value(core, &key))
+            .filter_map(|age| age.parse::<u64>().ok())
+            .max()
+            .expect("No core ages");
+
+        match configuration.core_config().get(&key) {
+            Some(FieldConfiguration::Fill { colour_settings }) => {
+                let bounds = colour_settings.bounds();
+                assert_eq!(bounds[3], max_age as f64);
+                assert!(bounds.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+            _ => panic!("Expected a Fill configuration for \"{key}\""),
+        }
+    }
+}
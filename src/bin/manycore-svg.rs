@@ -0,0 +1,139 @@
+use std::{path::Path, process::ExitCode};
+
+use manycore_svg::{
+    base_configuration_schema, configuration_schema, expand_inputs, read_configuration_file,
+    render_batch, BaseConfiguration, BatchSettings, Configuration, ConfigurationSource,
+};
+
+static USAGE: &str = "Usage:
+    manycore-svg batch <INPUT> --configuration <SOURCE>... [--base-configuration <FILE>]
+                       [--output <TEMPLATE>] [--jobs <N>] [--normalise-bounds]
+    manycore-svg schema [--base]
+
+<INPUT> is a directory, a pattern such as \"runs/*.xml\" or a single file.
+<SOURCE> is a configuration file (JSON, or TOML and YAML if enabled) or preset:<NAME>, with <NAME> one of thermal, ageing or routing-load.
+--configuration can be repeated, later sources override earlier ones.
+<TEMPLATE> defaults to \"{stem}.svg\". {stem}, {name} and {index} are replaced for each input.
+schema prints the JSON Schema of configuration files, or of base configuration files with --base.";
+
+/// Arguments of the `batch` subcommand.
+struct BatchArguments {
+    input: String,
+    configuration: Vec<ConfigurationSource>,
+    base_configuration: Option<String>,
+    output: String,
+    jobs: usize,
+    normalise_bounds: bool,
+}
+
+fn parse_batch_arguments(mut args: impl Iterator<Item = String>) -> Result<BatchArguments, String> {
+    let mut input = None;
+    let mut configuration = Vec::new();
+    let mut base_configuration = None;
+    let mut output = "{stem}.svg".to_string();
+    let mut jobs = 0;
+    let mut normalise_bounds = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--configuration" => configuration.push(ConfigurationSource::parse(&value()?)),
+            "--base-configuration" => base_configuration = Some(value()?),
+            "--output" => output = value()?,
+            "--jobs" => {
+                jobs = value()?
+                    .parse()
+                    .map_err(|_| "--jobs expects a number".to_string())?
+            }
+            "--normalise-bounds" => normalise_bounds = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}")),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("Unexpected argument {arg}")),
+        }
+    }
+
+    if configuration.is_empty() {
+        return Err("Missing --configuration".to_string());
+    }
+
+    Ok(BatchArguments {
+        input: input.ok_or("Missing <INPUT>")?,
+        configuration,
+        base_configuration,
+        output,
+        jobs,
+        normalise_bounds,
+    })
+}
+
+fn batch(arguments: BatchArguments) -> Result<bool, String> {
+    let configuration =
+        Configuration::layered(&arguments.configuration).map_err(|error| error.to_string())?;
+    let base_configuration: BaseConfiguration = match &arguments.base_configuration {
+        Some(path) => read_configuration_file(Path::new(path))
+            .map_err(|error| format!("Could not read \"{path}\": {error}"))?,
+        None => BaseConfiguration::default(),
+    };
+
+    let inputs = expand_inputs(&arguments.input).map_err(|error| error.to_string())?;
+    let summary = render_batch(
+        &inputs,
+        &configuration,
+        &base_configuration,
+        &BatchSettings::new(arguments.output, arguments.normalise_bounds, arguments.jobs),
+    )
+    .map_err(|error| error.to_string())?;
+
+    println!(
+        "Rendered {} of {} files.",
+        summary.rendered().len(),
+        inputs.len()
+    );
+
+    if !summary.failures().is_empty() {
+        for failure in summary.failures() {
+            eprintln!("{}: {}", failure.input().display(), failure.error());
+        }
+        for (kind, count) in summary.failures_by_kind() {
+            eprintln!("{kind}: {count}");
+        }
+    }
+
+    Ok(summary.failures().is_empty())
+}
+
+fn schema(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let schema = match args.next().as_deref() {
+        None => configuration_schema(),
+        Some("--base") => base_configuration_schema(),
+        Some(arg) => return Err(format!("Unexpected argument {arg}")),
+    };
+
+    let schema = serde_json::to_string_pretty(&schema).map_err(|error| error.to_string())?;
+    println!("{schema}");
+
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let result = match args.next().as_deref() {
+        Some("batch") => parse_batch_arguments(args).and_then(batch),
+        Some("schema") => schema(args),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
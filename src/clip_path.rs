@@ -1,13 +1,10 @@
-use const_format::concatcp;
 use manycore_parser::ElementIDT;
 use serde::Serialize;
 
-use crate::{CoordinateT, ROUTER_OFFSET, SIDE_LENGTH};
+use crate::{namespace::IdNamespace, CoordinateT, ROUTER_OFFSET, SIDE_LENGTH};
 
 pub(crate) const FREEFORM_CLIP_PATH_ID: &'static str = "crop";
 
-pub(crate) static USE_FREEFORM_CLIP_PATH: &'static str = concatcp!("url(#", FREEFORM_CLIP_PATH_ID, ")");
-
 /// Object representation of an SVG `<polygon>`.
 #[derive(Serialize)]
 struct Polygon {
@@ -26,8 +23,13 @@ pub struct ClipPath {
 impl ClipPath {
     /// Creates a new [`ClipPath`] instance given the `points` of a polygon (as [`String`], i.e. already formatted as to be expected in an SVG).
     pub fn new(polygon_points: String) -> Self {
+        Self::freeform(polygon_points, &IdNamespace::default())
+    }
+
+    /// Creates a new FreeForm [`ClipPath`] instance whose id lives in the given [`IdNamespace`].
+    pub(crate) fn freeform(polygon_points: String, namespace: &IdNamespace) -> Self {
         Self {
-            id: FREEFORM_CLIP_PATH_ID.to_string(),
+            id: namespace.id(FREEFORM_CLIP_PATH_ID),
             polygon: Polygon {
                 points: polygon_points,
             },
@@ -45,12 +47,17 @@ impl ClipPath {
     }
 
     /// Creates a new [`ClipPath`] instance from a core's position and id. Used to clip information layer.
-    pub(crate) fn for_core(id: ElementIDT, x: CoordinateT, y: CoordinateT) -> Self {
+    pub(crate) fn for_core(
+        id: ElementIDT,
+        x: CoordinateT,
+        y: CoordinateT,
+        namespace: &IdNamespace,
+    ) -> Self {
         let full_y = y.saturating_add(SIDE_LENGTH);
         let full_x = x.saturating_add(SIDE_LENGTH);
 
         Self {
-            id: ClipPath::make_core_id(&id),
+            id: namespace.id(&ClipPath::make_core_id(&id)),
             polygon: Polygon {
                 points: format!(
                     "{x} {y}, {x} {full_y}, {full_x} {full_y}, {full_x} {}, {} {y}",
@@ -62,13 +69,18 @@ impl ClipPath {
     }
 
     /// Creates a new [`ClipPath`] instance from a router's position and id. Used to clip information layer.
-    pub(crate) fn for_router(id: ElementIDT, x: CoordinateT, y: CoordinateT) -> Self {
+    pub(crate) fn for_router(
+        id: ElementIDT,
+        x: CoordinateT,
+        y: CoordinateT,
+        namespace: &IdNamespace,
+    ) -> Self {
         let min_y = y.saturating_sub(ROUTER_OFFSET);
         let full_x = x.saturating_add(SIDE_LENGTH);
         let full_y = min_y.saturating_add(SIDE_LENGTH);
 
         Self {
-            id: ClipPath::make_router_id(&id),
+            id: namespace.id(&ClipPath::make_router_id(&id)),
             polygon: Polygon {
                 points: format!(
                     "{x} {y}, {x} {min_y}, {full_x} {min_y}, {full_x} {full_y}, {} {full_y}",
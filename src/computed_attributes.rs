@@ -0,0 +1,414 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use manycore_parser::{ManycoreSystem, WithXMLAttributes};
+use serde::{Deserialize, Serialize};
+
+use crate::{get_attribute_value, Configuration, SVGError, SVGErrorKind};
+
+/// Arithmetic operators supported in expressions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// A parsed computed attribute expression.
+#[derive(Clone, PartialEq, Debug)]
+enum Expression {
+    Number(f64),
+    /// Key of an attribute of the element the expression is evaluated on, e.g. `@temperature`.
+    Attribute(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+impl Expression {
+    /// Evaluates the expression against `target`'s attributes.
+    /// Returns `Ok(None)` if a referenced attribute is missing or the expression divides by zero.
+    fn evaluate<T: WithXMLAttributes>(&self, target: &T) -> Result<Option<f64>, String> {
+        match self {
+            Expression::Number(value) => Ok(Some(*value)),
+            Expression::Attribute(key) => match get_attribute_value(target, key) {
+                Some(value) => value
+                    .trim()
+                    .parse::<f64>()
+                    .map(Some)
+                    .map_err(|_| format!("{key} has non numeric value \"{value}\"")),
+                None => Ok(None),
+            },
+            Expression::Negate(expression) => Ok(expression.evaluate(target)?.map(|value| -value)),
+            Expression::Binary(left, operator, right) => {
+                let (Some(left), Some(right)) = (left.evaluate(target)?, right.evaluate(target)?)
+                else {
+                    return Ok(None);
+                };
+
+                Ok(match operator {
+                    Operator::Add => Some(left + right),
+                    Operator::Subtract => Some(left - right),
+                    Operator::Multiply => Some(left * right),
+                    Operator::Divide if right == 0.0 => None,
+                    Operator::Divide => Some(left / right),
+                })
+            }
+        }
+    }
+}
+
+/// Recursive descent parser for expressions.
+struct Parser<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(source: &'a str) -> Result<Expression, String> {
+        let mut parser = Parser {
+            source: source.as_bytes(),
+            position: 0,
+        };
+
+        let expression = parser.expression()?;
+
+        match parser.peek() {
+            None => Ok(expression),
+            Some(_) => Err(parser.unexpected()),
+        }
+    }
+
+    /// Next non whitespace character, if any.
+    fn peek(&mut self) -> Option<u8> {
+        while self
+            .source
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        self.source.get(self.position).copied()
+    }
+
+    fn unexpected(&mut self) -> String {
+        match self.peek() {
+            Some(c) => format!(
+                "unexpected '{}' at position {}",
+                char::from(c),
+                self.position + 1
+            ),
+            None => "unexpected end of expression".to_string(),
+        }
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut ret = self.term()?;
+
+        loop {
+            let operator = match self.peek() {
+                Some(b'+') => Operator::Add,
+                Some(b'-') => Operator::Subtract,
+                _ => return Ok(ret),
+            };
+            self.position += 1;
+
+            ret = Expression::Binary(Box::new(ret), operator, Box::new(self.term()?));
+        }
+    }
+
+    /// `factor (('*' | '/') factor)*`
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut ret = self.factor()?;
+
+        loop {
+            let operator = match self.peek() {
+                Some(b'*') => Operator::Multiply,
+                Some(b'/') => Operator::Divide,
+                _ => return Ok(ret),
+            };
+            self.position += 1;
+
+            ret = Expression::Binary(Box::new(ret), operator, Box::new(self.factor()?));
+        }
+    }
+
+    /// `'-' factor | '(' expression ')' | number | attribute`
+    fn factor(&mut self) -> Result<Expression, String> {
+        match self.peek() {
+            Some(b'-') => {
+                self.position += 1;
+
+                Ok(Expression::Negate(Box::new(self.factor()?)))
+            }
+            Some(b'(') => {
+                self.position += 1;
+                let ret = self.expression()?;
+
+                match self.peek() {
+                    Some(b')') => {
+                        self.position += 1;
+                        Ok(ret)
+                    }
+                    _ => Err(self.unexpected()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == b'.');
+
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| format!("invalid number \"{number}\""))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' || c == b'@' => {
+                // Attribute keys always start with '@', it's optional in expressions
+                if c == b'@' {
+                    self.position += 1;
+                }
+
+                let name = self.take_while(|c| {
+                    c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || c == b':'
+                });
+
+                if name.is_empty() {
+                    Err(self.unexpected())
+                } else {
+                    Ok(Expression::Attribute(format!("@{name}")))
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.position;
+        while self
+            .source
+            .get(self.position)
+            .is_some_and(|c| predicate(*c))
+        {
+            self.position += 1;
+        }
+
+        // Only ASCII characters are taken, the slice is valid UTF-8
+        std::str::from_utf8(&self.source[start..self.position]).unwrap_or_default()
+    }
+}
+
+/// An attribute computed from an arithmetic expression over an element's attributes.
+///
+/// Expressions support numbers, attribute names, `+`, `-`, `*`, `/` and parentheses, e.g. `@temperature - @ambient`.
+/// The leading `@` of attribute names can be omitted, e.g. `age / 1000`.
+/// Elements missing a referenced attribute have no value for the computed attribute, just like elements missing a
+/// regular attribute. The same applies to divisions by zero.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(from = "String", into = "String")]
+pub struct ComputedAttribute {
+    source: String,
+    expression: Result<Expression, String>,
+}
+
+impl From<String> for ComputedAttribute {
+    fn from(source: String) -> Self {
+        // Parse errors are reported when the configuration is used, as an SVGError
+        let expression = Parser::parse(&source);
+
+        Self { source, expression }
+    }
+}
+
+impl From<ComputedAttribute> for String {
+    fn from(computed_attribute: ComputedAttribute) -> Self {
+        computed_attribute.source
+    }
+}
+
+impl ComputedAttribute {
+    /// The expression, as provided by the user.
+    pub fn source(&self) -> &String {
+        &self.source
+    }
+
+    /// Reason the expression could not be parsed, if it could not.
+    pub(crate) fn parse_error(&self) -> Option<&String> {
+        self.expression.as_ref().err()
+    }
+
+    fn error(&self, key: &String, code: &'static str, reason: &String) -> SVGError {
+        SVGError::new(SVGErrorKind::ExpressionError(format!(
+            "Computed attribute {key} (\"{}\"): {reason}",
+            self.source
+        )))
+        .with_code(code)
+        .with_key(key)
+    }
+
+    /// Evaluates the computed attribute `key` on `target`.
+    fn evaluate<T: WithXMLAttributes>(
+        &self,
+        key: &String,
+        target: &T,
+    ) -> Result<Option<f64>, SVGError> {
+        let expression = self
+            .expression
+            .as_ref()
+            .map_err(|reason| self.error(key, "invalid_expression", reason))?;
+
+        expression
+            .evaluate(target)
+            .map_err(|reason| self.error(key, "expression_evaluation_failed", &reason))
+    }
+}
+
+/// Formats a computed value. Whole numbers have no decimals, others are rounded to two decimals.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+/// Retrieves the value of an attribute from an element, computing it if it's a computed attribute.
+/// Computed attributes take precedence over the element's own attributes.
+pub(crate) fn attribute_value<'a, T: WithXMLAttributes>(
+    target: &'a T,
+    key: &String,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+) -> Option<Cow<'a, String>> {
+    match computed_attributes.get(key) {
+        Some(computed_attribute) => computed_attribute
+            .evaluate(key, target)
+            .ok()
+            .flatten()
+            .map(|value| Cow::Owned(format_value(value))),
+        None => get_attribute_value(target, key).map(Cow::Borrowed),
+    }
+}
+
+/// Checks that every computed attribute parses and evaluates on the elements it's requested for.
+pub(crate) fn validate_computed_attributes(
+    configuration: &Configuration,
+    manycore: &ManycoreSystem,
+) -> Result<(), SVGError> {
+    for (key, computed_attribute) in configuration.computed_attributes() {
+        // Parse errors must be reported even if no element is affected
+        if let Err(reason) = &computed_attribute.expression {
+            return Err(computed_attribute.error(key, "invalid_expression", reason));
+        }
+
+        for core in manycore.cores().list() {
+            if configuration.core_config().contains_key(key) {
+                computed_attribute.evaluate(key, core)?;
+            }
+
+            if configuration.router_config().contains_key(key) {
+                computed_attribute.evaluate(key, core.router())?;
+            }
+
+            if configuration.channel_config().contains_key(key) {
+                for channel in core.channels().channel().values() {
+                    computed_attribute.evaluate(key, channel)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::{format_value, Parser};
+    use crate::{BaseConfiguration, Configuration, SVGErrorKind, SVG};
+
+    #[test]
+    fn parses_expressions() {
+        assert!(Parser::parse("(@temperature - ambient) * 2 / -4").is_ok());
+        assert!(Parser::parse("age / 1000").is_ok());
+        assert_eq!(
+            Parser::parse("@age +").unwrap_err(),
+            "unexpected end of expression"
+        );
+        assert_eq!(
+            Parser::parse("@age $ 2").unwrap_err(),
+            "unexpected '$' at position 6"
+        );
+
+        assert_eq!(format_value(1500.0), "1500");
+        assert_eq!(format_value(1.5), "1.5");
+        assert_eq!(format_value(-0.126), "-0.13");
+    }
+
+    #[test]
+    fn renders_computed_attributes() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration: Configuration = serde_json::from_str(
+            r##"{
+                "computedAttributes": {
+                    "@ageThousands": "@age / 1000"
+                },
+                "coreConfig": {
+                    "@ageThousands": {
+                        "type": "Text",
+                        "display": "Age (k)"
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        let update = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut configuration,
+                &BaseConfiguration::default(),
+            )
+            .expect("Could not update SVG.");
+
+        assert!(update.information_group.contains("Age (k): "));
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration: Configuration = serde_json::from_str(
+            r##"{
+                "computedAttributes": {
+                    "@broken": "@age * (2"
+                },
+                "coreConfig": {},
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        let error = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut configuration,
+                &BaseConfiguration::default(),
+            )
+            .expect_err("Invalid expression was accepted.");
+
+        assert!(matches!(error.kind(), SVGErrorKind::ExpressionError(_)));
+    }
+}
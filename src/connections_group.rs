@@ -5,9 +5,10 @@ use manycore_parser::{Core, Directions, EdgePosition, ElementIDT, WithID};
 use serde::Serialize;
 
 use crate::{
-    sinks_sources_layer::SINKS_SOURCES_CONNECTION_LENGTH, style::EDGE_DATA_CLASS_NAME,
-    CommonAttributes, CoordinateT, Router, TopLeft, HALF_ROUTER_OFFSET, MARKER_HEIGHT,
-    MARKER_REFERENCE, ROUTER_OFFSET, SIDE_LENGTH, USE_FREEFORM_CLIP_PATH,
+    namespace::IdNamespace, sinks_sources_layer::SINKS_SOURCES_CONNECTION_LENGTH,
+    style::EDGE_DATA_CLASS_NAME, CommonAttributes, CoordinateT, Router, TopLeft,
+    FREEFORM_CLIP_PATH_ID, HALF_ROUTER_OFFSET, MARKER_HEIGHT, MARKER_ID, ROUTER_OFFSET,
+    SIDE_LENGTH,
 };
 
 pub(crate) const EDGE_CONNECTIONS_ID: &'static str = "edgeConnetions";
@@ -23,11 +24,12 @@ pub(crate) static CONNECTION_LENGTH: CoordinateT = ROUTER_OFFSET.saturating_mul(
 #[derive(Serialize, Getters, Debug)]
 pub(crate) struct Connection {
     #[serde(rename = "@d")]
+    #[getset(get = "pub")]
     d: String,
     #[serde(flatten)]
     attributes: CommonAttributes,
     #[serde(rename = "@marker-end")]
-    marker_end: &'static str,
+    marker_end: String,
     #[serde(skip)]
     #[getset(get = "pub")]
     x: CoordinateT,
@@ -35,7 +37,7 @@ pub(crate) struct Connection {
     #[getset(get = "pub")]
     y: CoordinateT,
     #[serde(rename = "@clip-path")]
-    clip_path: &'static str,
+    clip_path: String,
 }
 
 /// Helper struct used when calculating connection paths.
@@ -235,14 +237,14 @@ impl Connection {
     }
 
     /// Creates a new [`Connection`] instance given a [`ConnectionPath`]. Remaining parameters are default.
-    fn new(connection_path: ConnectionPath) -> Self {
+    fn new(connection_path: ConnectionPath, namespace: &IdNamespace) -> Self {
         Self {
             d: connection_path.path,
             attributes: CommonAttributes::with_no_class(),
-            marker_end: MARKER_REFERENCE,
+            marker_end: namespace.url(MARKER_ID),
             x: connection_path.x,
             y: connection_path.y,
-            clip_path: USE_FREEFORM_CLIP_PATH,
+            clip_path: namespace.url(FREEFORM_CLIP_PATH_ID),
         }
     }
 }
@@ -258,8 +260,10 @@ pub(crate) struct Connections {
 #[derive(Serialize, Getters)]
 pub struct EdgeConnections {
     #[serde(rename = "@id")]
-    id: &'static str,
+    #[getset(get = "pub")]
+    id: String,
     #[serde(rename = "@class")]
+    #[getset(get = "pub")]
     class: &'static str,
     #[serde(rename = "path")]
     #[getset(get = "pub")]
@@ -269,10 +273,11 @@ pub struct EdgeConnections {
     sink: Vec<Connection>,
 }
 
-impl Default for EdgeConnections {
-    fn default() -> Self {
+impl EdgeConnections {
+    /// Generates a new, empty, [`EdgeConnections`] instance whose id lives in the given [`IdNamespace`].
+    fn new(namespace: &IdNamespace) -> Self {
         Self {
-            id: EDGE_CONNECTIONS_ID,
+            id: namespace.id(EDGE_CONNECTIONS_ID),
             class: EDGE_DATA_CLASS_NAME,
             source: Vec::new(),
             sink: Vec::new(),
@@ -303,7 +308,7 @@ impl Display for DirectionType {
 }
 
 /// Object representation of the SVG group that contains channel connections.
-#[derive(Serialize, Getters, Default)]
+#[derive(Serialize, Getters)]
 #[getset(get = "pub")]
 pub(crate) struct ConnectionsParentGroup {
     #[serde(rename = "g")]
@@ -313,11 +318,29 @@ pub(crate) struct ConnectionsParentGroup {
     /// A double map to quickly retrieve a core's (router) connections in the SVG.
     #[serde(skip)]
     core_connections_map: HashMap<ElementIDT, HashMap<DirectionType, ConnectionType>>,
+    #[serde(skip)]
+    #[getset(skip)]
+    namespace: IdNamespace,
 }
 
 impl ConnectionsParentGroup {
+    /// Generates a new, empty, [`ConnectionsParentGroup`] instance whose ids live in the given [`IdNamespace`].
+    pub(crate) fn new(namespace: &IdNamespace) -> Self {
+        Self {
+            connections: Connections::default(),
+            edge_connections: EdgeConnections::new(namespace),
+            core_connections_map: HashMap::new(),
+            namespace: namespace.clone(),
+        }
+    }
+
     /// Inserts an SVG core connection in the core_connections_map.
-    fn insert_in_map(&mut self, core_id: &ElementIDT, direction: DirectionType, element: ConnectionType) {
+    fn insert_in_map(
+        &mut self,
+        core_id: &ElementIDT,
+        direction: DirectionType,
+        element: ConnectionType,
+    ) {
         self.core_connections_map
             .entry(*core_id)
             // Each core has 4 connections, so we preallocate 4 slots in the inner map.
@@ -338,8 +361,12 @@ impl ConnectionsParentGroup {
         let current_source_size = self.edge_connections.source.len();
         let current_sink_size = self.edge_connections.sink.len();
 
-        self.edge_connections.source.push(Connection::new(input));
-        self.edge_connections.sink.push(Connection::new(output));
+        self.edge_connections
+            .source
+            .push(Connection::new(input, &self.namespace));
+        self.edge_connections
+            .sink
+            .push(Connection::new(output, &self.namespace));
 
         // When we insert in map, we store direction and the index of the element in its
         // respective vector so we can grab it quickly in case we need to display its load,
@@ -368,7 +395,9 @@ impl ConnectionsParentGroup {
         let path = Connection::get_inner_path(direction, &r, &c, top_left);
         let current_size = self.connections.path.len();
 
-        self.connections.path.push(Connection::new(path));
+        self.connections
+            .path
+            .push(Connection::new(path, &self.namespace));
 
         // When we insert in map, we store direction and the index of the element in its
         // respective vector so we can grab it quickly in case we need to display its load,
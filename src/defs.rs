@@ -1,29 +1,70 @@
-use std::ops::Mul;
+use getset::{Getters, MutGetters};
+use serde::{ser::SerializeSeq, Serialize, Serializer};
 
-use getset::MutGetters;
-use serde::Serialize;
-
-use crate::{ClipPath, Marker};
+use crate::{namespace::IdNamespace, ClipPath, Marker, ProcessingParentGroup};
 
 /// Object representation of SVG `<defs>`.
-/// Includes a default [`Marker`] and the required [`ClipPath`]s for core and router information layer.
-#[derive(Serialize, MutGetters)]
+/// Includes a default [`Marker`] and the FreeForm [`ClipPath`], if any. The [`ClipPath`]s for core and router
+/// information layer are not kept: they are generated from the [`ProcessingParentGroup`] while serialising, see
+/// [`Defs::content`].
+#[derive(Getters, MutGetters)]
 pub(crate) struct Defs {
+    #[getset(get = "pub")]
     marker: Marker,
-    #[serde(rename = "clipPath")]
-    #[getset(get_mut = "pub")]
+    #[getset(get = "pub", get_mut = "pub")]
     clip_paths: Vec<ClipPath>,
 }
 
+/// The [`ClipPath`]s of a [`Defs`]: those of every core and router, then the FreeForm ones.
+pub(crate) struct ClipPaths<'a> {
+    processing_group: &'a ProcessingParentGroup,
+    freeform: &'a [ClipPath],
+    namespace: &'a IdNamespace,
+}
+
+/// Object representation of SVG `<defs>` as serialised, see [`Defs::content`].
+#[derive(Serialize)]
+pub(crate) struct DefsContent<'a> {
+    marker: &'a Marker,
+    #[serde(rename = "clipPath")]
+    clip_paths: ClipPaths<'a>,
+}
+
+impl Serialize for ClipPaths<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for clip_path in self.processing_group.clip_paths(self.namespace) {
+            seq.serialize_element(&clip_path)?;
+        }
+        for clip_path in self.freeform {
+            seq.serialize_element(clip_path)?;
+        }
+        seq.end()
+    }
+}
+
 impl Defs {
-    /// Creates a new [`Defs`] instance with the required capacity for [`ClipPath`]s.
-    pub(crate) fn new(number_of_cores: &usize) -> Self {
+    /// Creates a new [`Defs`] instance, without any FreeForm [`ClipPath`].
+    pub(crate) fn new(namespace: &IdNamespace) -> Self {
         Self {
-            marker: Default::default(),
-            // We need capacity for twice the number of cores to fit
-            // both cores and routers' clip paths.
-            // We add one to potentially store freeform clip path.
-            clip_paths: Vec::with_capacity(number_of_cores.mul(2).saturating_add(1)),
+            marker: Marker::new(namespace),
+            clip_paths: Vec::new(),
+        }
+    }
+
+    /// The [`Defs`] to serialise, with a [`ClipPath`] for every core and router of `processing_group`.
+    pub(crate) fn content<'a>(
+        &'a self,
+        processing_group: &'a ProcessingParentGroup,
+        namespace: &'a IdNamespace,
+    ) -> DefsContent<'a> {
+        DefsContent {
+            marker: &self.marker,
+            clip_paths: ClipPaths {
+                processing_group,
+                freeform: &self.clip_paths,
+                namespace,
+            },
         }
     }
 }
@@ -0,0 +1,346 @@
+use std::collections::BTreeMap;
+
+use manycore_parser::{ManycoreSystem, WithXMLAttributes};
+use serde::Serialize;
+
+use crate::{
+    auto_bounds::{resolve_fields, BoundsResolution},
+    computed_attributes::{attribute_value, validate_computed_attributes},
+    information_layer::delta_magnitude,
+    namespace::IdNamespace,
+    no_processing_group, ComputedAttribute, Configuration, CoordinateT, InformationLayer,
+    ProcessingGroup, SVGError, SVGErrorKind, FREEFORM_CLIP_PATH_ID, HALF_SIDE_LENGTH, MARKER_ID,
+    SVG,
+};
+
+static TASK_MOVE_STROKE: &'static str = "#1c71d8";
+static TASK_MOVE_STROKE_WIDTH: &'static str = "3";
+
+/// Object representation of an SVG `<path>` that connects a task's old core to its new core.
+#[derive(Serialize)]
+struct TaskMove {
+    #[serde(rename = "@d")]
+    d: String,
+    #[serde(rename = "@fill")]
+    fill: &'static str,
+    #[serde(rename = "@stroke")]
+    stroke: &'static str,
+    #[serde(rename = "@stroke-width")]
+    stroke_width: &'static str,
+    #[serde(rename = "@marker-end")]
+    marker_end: String,
+}
+
+impl TaskMove {
+    /// Generates a new [`TaskMove`] arrow between the centres of the given [`ProcessingGroup`]s' cores.
+    fn new(from: &ProcessingGroup, to: &ProcessingGroup, namespace: &IdNamespace) -> Self {
+        let (from_x, from_y) = Self::core_centre(from);
+        let (to_x, to_y) = Self::core_centre(to);
+
+        Self {
+            d: format!("M{},{} L{},{}", from_x, from_y, to_x, to_y),
+            fill: "none",
+            stroke: TASK_MOVE_STROKE,
+            stroke_width: TASK_MOVE_STROKE_WIDTH,
+            marker_end: namespace.url(MARKER_ID),
+        }
+    }
+
+    /// Calculates the centre coordinates of a [`ProcessingGroup`]'s core.
+    fn core_centre(processing_group: &ProcessingGroup) -> (CoordinateT, CoordinateT) {
+        let (core_x, core_y) = processing_group.core().move_coordinates();
+
+        (
+            core_x.saturating_add(HALF_SIDE_LENGTH),
+            core_y.saturating_add(HALF_SIDE_LENGTH),
+        )
+    }
+}
+
+/// Object representation of the SVG `<g>` that wraps all [`TaskMove`]s of a diff render.
+#[derive(Serialize)]
+pub(crate) struct TaskMovesGroup {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@clip-path")]
+    clip_path: String,
+    #[serde(rename = "path")]
+    moves: Vec<TaskMove>,
+}
+
+impl TaskMovesGroup {
+    /// Generates a new [`TaskMovesGroup`] instance from the given [`TaskMove`]s.
+    fn new(moves: Vec<TaskMove>, namespace: &IdNamespace) -> Self {
+        Self {
+            id: namespace.id("taskMoves"),
+            clip_path: namespace.url(FREEFORM_CLIP_PATH_ID),
+            moves,
+        }
+    }
+}
+
+/// Maps each allocated task to the index of the core it is allocated to.
+fn task_allocations(manycore: &ManycoreSystem) -> BTreeMap<u16, usize> {
+    let mut allocations = BTreeMap::new();
+
+    for (i, core) in manycore.cores().list().iter().enumerate() {
+        if let Some(task_id) = core.allocated_task() {
+            allocations.insert(*task_id, i);
+        }
+    }
+
+    allocations
+}
+
+/// Magnitude of the change of attribute `key` between every pair of old and new targets, where numeric.
+fn deltas<T: WithXMLAttributes>(
+    pairs: &[(&T, &T)],
+    key: &String,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+) -> Vec<f64> {
+    pairs
+        .iter()
+        .filter_map(|(old, new)| {
+            delta_magnitude(
+                attribute_value(*old, key, computed_attributes).as_deref(),
+                attribute_value(*new, key, computed_attributes).as_deref(),
+            )
+        })
+        .collect()
+}
+
+/// Resolves the bounds of core and router fields configured with [`AutoBounds`](crate::AutoBounds) from the
+/// changes between two snapshots, as that's what a diff is coloured by.
+fn resolve_delta_bounds(
+    configuration: &mut Configuration,
+    old: &ManycoreSystem,
+    new: &ManycoreSystem,
+) {
+    // Field configurations are borrowed mutably below
+    let computed_attributes = configuration.computed_attributes().clone();
+    let cores: Vec<_> = old
+        .cores()
+        .list()
+        .iter()
+        .zip(new.cores().list().iter())
+        .collect();
+    let routers: Vec<_> = cores
+        .iter()
+        .map(|(old, new)| (old.router(), new.router()))
+        .collect();
+
+    // Diffs don't report resolved bounds
+    resolve_fields(
+        configuration.core_config_mut(),
+        BoundsResolution::Configured,
+        &mut BTreeMap::new(),
+        |key| deltas(&cores, key, &computed_attributes),
+    );
+    resolve_fields(
+        configuration.router_config_mut(),
+        BoundsResolution::Configured,
+        &mut BTreeMap::new(),
+        |key| deltas(&routers, key, &computed_attributes),
+    );
+}
+
+impl SVG {
+    /// Generates a diff [`SVG`] between two snapshots of a [`ManycoreSystem`] with the same dimensions.
+    ///
+    /// The render is based on the `new` snapshot. For each attribute in the provided [`Configuration`]:
+    /// * `Text` and `ColouredText` attributes whose value changed are displayed as `old → new` labels.
+    /// `ColouredText` labels are coloured according to the delta magnitude.
+    /// * `Fill` attributes fill cores and routers according to the delta magnitude.
+    ///
+    /// Bounds configured with [`AutoBounds`](crate::AutoBounds) are derived from the delta magnitudes.
+    ///
+    /// Tasks allocated to a different core in the `new` snapshot are connected to their old core by an arrow.
+    pub fn diff(
+        old: &ManycoreSystem,
+        new: &ManycoreSystem,
+        configuration: &Configuration,
+    ) -> Result<SVG, SVGError> {
+        if old.rows() != new.rows() || old.columns() != new.columns() {
+            return Err(SVGError::new(SVGErrorKind::ManycoreMismatch(format!(
+                "Cannot diff a {}x{} system against a {}x{} system.",
+                old.rows(),
+                old.columns(),
+                new.rows(),
+                new.columns()
+            )))
+            .with_code("mismatched_dimensions"));
+        }
+
+        validate_computed_attributes(configuration, old)?;
+        validate_computed_attributes(configuration, new)?;
+
+        let mut configuration = configuration.duplicate()?;
+        resolve_delta_bounds(&mut configuration, old, new);
+        let configuration = &configuration;
+
+        let mut ret = SVG::try_from(new)?;
+
+        let mut fill_classes = BTreeMap::new();
+        for (i, (old_core, new_core)) in old
+            .cores()
+            .list()
+            .iter()
+            .zip(new.cores().list().iter())
+            .enumerate()
+        {
+            let processing_group = ret
+                .root
+                .processing_group
+                .g()
+                .get(i)
+                .ok_or(no_processing_group(i))?;
+
+            let information_layer = InformationLayer::diff(
+                ret.rows,
+                configuration,
+                old_core,
+                new_core,
+                processing_group,
+                &ret.namespace,
+                &ret.processed_base_configuration,
+            )?;
+
+            ret.apply_fill_classes(i, &information_layer, &mut fill_classes)?;
+
+            ret.root
+                .information_group
+                .groups_mut()
+                .push(information_layer);
+        }
+
+        // Draw an arrow for every task that moved
+        let old_allocations = task_allocations(old);
+        let mut moves = Vec::new();
+        for (task_id, new_index) in task_allocations(new) {
+            if let Some(old_index) = old_allocations.get(&task_id) {
+                if *old_index != new_index {
+                    let processing_groups = ret.root.processing_group.g();
+                    let from = processing_groups
+                        .get(*old_index)
+                        .ok_or(no_processing_group(*old_index))?;
+                    let to = processing_groups
+                        .get(new_index)
+                        .ok_or(no_processing_group(new_index))?;
+
+                    moves.push(TaskMove::new(from, to, &ret.namespace));
+                }
+            }
+        }
+        ret.root.task_moves_group = Some(TaskMovesGroup::new(moves, &ret.namespace));
+
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use crate::{test_utils::read_configuration, Configuration, FieldConfiguration, SVG};
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+        let configuration = read_configuration("tests/conf2.json");
+
+        let svg =
+            SVG::diff(&manycore, &manycore, &configuration).expect("Could not generate diff SVG.");
+
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        assert!(!res.contains("→"));
+        assert!(res.contains("<g id=\"taskMoves\" clip-path=\"url(#crop)\"/>"));
+    }
+
+    #[test]
+    fn labels_and_fills_changes() {
+        let old = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let new = ManycoreSystem::parse_file("tests/VisualiserOutput1_next.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1_next.xml\"");
+
+        let configuration = read_configuration("tests/conf2.json");
+        let colour_settings = |key: &str| match configuration.core_config().get(key) {
+            Some(
+                FieldConfiguration::Fill { colour_settings }
+                | FieldConfiguration::ColouredText {
+                    colour_settings, ..
+                },
+            ) => colour_settings,
+            other => panic!("Unexpected {key} configuration {other:?}"),
+        };
+
+        let svg = SVG::diff(&old, &new, &configuration).expect("Could not generate diff SVG.");
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        // Core 0 aged from 238 to 38 and heated from 45 to 60, nothing else changed
+        assert_eq!(res.matches("→").count(), 1);
+        let label = res
+            .find("Temp: 45 → 60")
+            .expect("Missing temperature label");
+        let text_start = res[..label].rfind("<text").expect("Label is not a text");
+        assert!(res[text_start..label].contains(&format!(
+            "fill=\"{}\"",
+            colour_settings("@temperature").colour(15.0)
+        )));
+
+        let layers = svg.root.information_group.groups();
+        assert_eq!(
+            layers[0].core_fill(),
+            Some(colour_settings("@age").colour(200.0))
+        );
+        for layer in &layers[1..] {
+            assert_eq!(layer.core_fill(), Some(colour_settings("@age").colour(0.0)));
+        }
+
+        // Task 3 moved from core 1 to core 0, the arrow joins the centres of the two cores
+        let task_moves = svg
+            .root
+            .task_moves_group
+            .as_ref()
+            .expect("Missing task moves group");
+        assert_eq!(task_moves.moves.len(), 1);
+        assert_eq!(task_moves.moves[0].d, "M-37,-376 L-451,-376");
+    }
+
+    #[test]
+    fn resolves_auto_bounds_from_deltas() {
+        let old = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        // VisualiserOutput1_next with the age of core 1 raised from 394 to 494
+        let new = ManycoreSystem::parse_file("tests/VisualiserOutput1_next_aged.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1_next_aged.xml\"");
+
+        let configuration: Configuration = serde_json::from_str(
+            r##"{
+                "coreConfig": {
+                    "@age": {
+                        "type": "Fill",
+                        "autoBounds": "EqualWidth",
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let svg = SVG::diff(&old, &new, &configuration).expect("Could not generate diff SVG.");
+
+        // Core 0 aged by 200 and core 1 by 100, bounds are [50, 100, 150, 200]
+        let layers = svg.root.information_group.groups();
+        assert_eq!(layers[0].core_fill(), Some(&"#dc2626".to_string()));
+        assert_eq!(layers[1].core_fill(), Some(&"#eab308".to_string()));
+        assert_eq!(layers[2].core_fill(), Some(&"#22c55e".to_string()));
+    }
+}
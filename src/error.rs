@@ -1,8 +1,11 @@
-use std::{error::Error, fmt::Display, num::TryFromIntError};
+use std::{error::Error, fmt::Display, io, num::TryFromIntError};
 
+use getset::Getters;
 use manycore_parser::ManycoreError;
 use quick_xml::DeError;
+use serde::{ser::SerializeStruct, Serialize};
 
+use crate::ElementKind;
 #[cfg(doc)]
 use crate::SVG;
 
@@ -23,18 +26,153 @@ pub enum SVGErrorKind {
     DataConversionError(String),
     /// A generic [`SVG`] generation error.
     GenerationError(String),
+    /// Reading or writing a file failed.
+    IoError(String),
+    /// A computed attribute expression could not be parsed or evaluated.
+    ExpressionError(String),
+    /// A configuration could not be upgraded to the current format version.
+    MigrationError(String),
+    /// A configuration source could not be used, e.g. an unknown preset.
+    ConfigurationError(String),
+}
+
+impl SVGErrorKind {
+    /// Stable code identifying the kind of error, e.g. `ConnectionError`. Codes never change between releases.
+    /// See [`SVGError::code`] for a code identifying the error itself.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SVGErrorKind::ConnectionError(_) => "ConnectionError",
+            SVGErrorKind::ManycoreMismatch(_) => "ManycoreMismatch",
+            SVGErrorKind::ManycoreError(_) => "ManycoreError",
+            SVGErrorKind::SerialisationError(_) => "SerialisationError",
+            SVGErrorKind::DataConversionError(_) => "DataConversionError",
+            SVGErrorKind::GenerationError(_) => "GenerationError",
+            SVGErrorKind::IoError(_) => "IoError",
+            SVGErrorKind::ExpressionError(_) => "ExpressionError",
+            SVGErrorKind::MigrationError(_) => "MigrationError",
+            SVGErrorKind::ConfigurationError(_) => "ConfigurationError",
+        }
+    }
+
+    /// User friendly explanation of the error.
+    pub fn reason(&self) -> &String {
+        match self {
+            SVGErrorKind::ConnectionError(reason)
+            | SVGErrorKind::ManycoreMismatch(reason)
+            | SVGErrorKind::ManycoreError(reason)
+            | SVGErrorKind::SerialisationError(reason)
+            | SVGErrorKind::DataConversionError(reason)
+            | SVGErrorKind::GenerationError(reason)
+            | SVGErrorKind::IoError(reason)
+            | SVGErrorKind::ExpressionError(reason)
+            | SVGErrorKind::MigrationError(reason)
+            | SVGErrorKind::ConfigurationError(reason) => reason,
+        }
+    }
+}
+
+/// What an [`SVGError`] refers to. Every field is optional, only what is known is provided.
+/// * `element`: [`ElementKind`] of the offending element.
+/// * `id`: ID of the offending element. Channels are identified by their core's ID.
+/// * `direction`: Direction of the offending channel or connection.
+/// * `key`: Configuration key that was being processed.
+/// * `line`, `column`: Position of the problem in the file being parsed, starting from 1.
+#[derive(Serialize, Getters, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ErrorContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    element: Option<ElementKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
 }
 
 /// A generic error container used to keep results consistent within the library.
+///
+/// Serialises to an object with the error's stable `code`, the code of its `kind`, its `reason`, its [`ErrorContext`]
+/// and the description of the underlying error, if any, as `source`.
 #[derive(Debug)]
 pub struct SVGError {
     error_kind: SVGErrorKind,
+    code: &'static str,
+    context: ErrorContext,
+    source: Option<Box<dyn Error + Send + Sync>>,
 }
 
 impl SVGError {
     /// Instantiates a new [`SVGError`] instance.
     pub(crate) fn new(error_kind: SVGErrorKind) -> Self {
-        Self { error_kind }
+        Self {
+            code: error_kind.code(),
+            error_kind,
+            context: ErrorContext::default(),
+            source: None,
+        }
+    }
+
+    /// Instantiates a new [`SVGError`] caused by another error.
+    fn caused_by(error_kind: SVGErrorKind, source: impl Error + Send + Sync + 'static) -> Self {
+        Self {
+            source: Some(Box::new(source)),
+            ..Self::new(error_kind)
+        }
+    }
+
+    /// Sets the stable code identifying the error, e.g. `missing_channel`.
+    pub(crate) fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// Attaches the offending element to the error.
+    pub(crate) fn with_element(mut self, element: ElementKind, id: impl Display) -> Self {
+        self.context.element = Some(element);
+        self.context.id = Some(id.to_string());
+        self
+    }
+
+    /// Attaches the offending direction to the error.
+    pub(crate) fn with_direction(mut self, direction: impl Display) -> Self {
+        self.context.direction = Some(direction.to_string());
+        self
+    }
+
+    /// Attaches the offending configuration key to the error.
+    pub(crate) fn with_key(mut self, key: &str) -> Self {
+        self.context.key = Some(key.to_string());
+        self
+    }
+
+    /// Attaches the position of the problem in the parsed file to the error.
+    pub(crate) fn with_location(mut self, line: usize, column: usize) -> Self {
+        self.context.line = Some(line);
+        self.context.column = Some(column);
+        self
+    }
+
+    /// Returns the [`SVGErrorKind`] of this error.
+    pub fn kind(&self) -> &SVGErrorKind {
+        &self.error_kind
+    }
+
+    /// Returns the stable code identifying this error, e.g. `missing_channel`. Unlike [`SVGErrorKind::code`], every
+    /// place an error is raised from has its own code. Codes never change between releases and can be used to look
+    /// up localised messages.
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// Returns the [`ErrorContext`] of this error.
+    pub fn context(&self) -> &ErrorContext {
+        &self.context
     }
 }
 
@@ -51,6 +189,12 @@ impl Display for SVGError {
                 write!(f, "Data Conversion Error: {reason}")
             }
             SVGErrorKind::GenerationError(reason) => write!(f, "Generation Error: {reason}"),
+            SVGErrorKind::IoError(reason) => write!(f, "IO Error: {reason}"),
+            SVGErrorKind::ExpressionError(reason) => write!(f, "Expression Error: {reason}"),
+            SVGErrorKind::MigrationError(reason) => write!(f, "Migration Error: {reason}"),
+            SVGErrorKind::ConfigurationError(reason) => {
+                write!(f, "Configuration Error: {reason}")
+            }
         }
     }
 }
@@ -60,28 +204,112 @@ pub(crate) fn generation_error(reason: String) -> SVGError {
     SVGError::new(SVGErrorKind::GenerationError(reason))
 }
 
-impl Error for SVGError {}
+impl Error for SVGError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
+
+impl Serialize for SVGError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("SVGError", 5)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("kind", self.error_kind.code())?;
+        state.serialize_field("reason", self.error_kind.reason())?;
+        state.serialize_field("context", &self.context)?;
+        state.serialize_field(
+            "source",
+            &self.source.as_ref().map(|source| source.to_string()),
+        )?;
+        state.end()
+    }
+}
 
 impl From<ManycoreError> for SVGError {
     fn from(error: ManycoreError) -> Self {
-        Self {
-            error_kind: SVGErrorKind::ManycoreError(format!("{error}")),
-        }
+        Self::caused_by(SVGErrorKind::ManycoreError(error.to_string()), error)
+            .with_code("manycore_error")
     }
 }
 
 impl From<DeError> for SVGError {
     fn from(error: DeError) -> Self {
-        Self {
-            error_kind: SVGErrorKind::SerialisationError(error.to_string()),
+        Self::caused_by(SVGErrorKind::SerialisationError(error.to_string()), error)
+            .with_code("invalid_xml")
+    }
+}
+
+impl From<serde_json::Error> for SVGError {
+    fn from(error: serde_json::Error) -> Self {
+        // Line 0 means the error did not come from parsing text
+        let location = (error.line() > 0).then(|| (error.line(), error.column()));
+        let ret = Self::caused_by(SVGErrorKind::SerialisationError(error.to_string()), error)
+            .with_code("invalid_json");
+
+        match location {
+            Some((line, column)) => ret.with_location(line, column),
+            None => ret,
         }
     }
 }
 
+impl From<io::Error> for SVGError {
+    fn from(error: io::Error) -> Self {
+        Self::caused_by(SVGErrorKind::IoError(error.to_string()), error).with_code("io_error")
+    }
+}
+
 impl From<TryFromIntError> for SVGError {
     fn from(error: TryFromIntError) -> Self {
-        Self {
-            error_kind: SVGErrorKind::DataConversionError(error.to_string()),
-        }
+        Self::caused_by(SVGErrorKind::DataConversionError(error.to_string()), error)
+            .with_code("integer_out_of_range")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io};
+
+    use manycore_parser::Directions;
+
+    use super::SVGError;
+    use crate::{missing_channel, ElementKind};
+
+    #[test]
+    fn provides_error_context() {
+        let error = missing_channel(&5, &Directions::North);
+
+        assert_eq!(error.code(), "missing_channel");
+        assert_eq!(error.kind().code(), "ManycoreMismatch");
+        assert_eq!(*error.context().element(), Some(ElementKind::Channel));
+        assert_eq!(error.context().id().as_deref(), Some("5"));
+        assert_eq!(
+            *error.context().direction(),
+            Some(Directions::North.to_string())
+        );
+
+        let serialised = serde_json::to_value(&error).expect("Could not serialise error");
+        assert_eq!(serialised["code"], "missing_channel");
+        assert_eq!(serialised["kind"], "ManycoreMismatch");
+        assert_eq!(serialised["context"]["element"], "Channel");
+        assert_eq!(serialised["context"]["id"], "5");
+        assert!(serialised["source"].is_null());
+    }
+
+    #[test]
+    fn chains_error_sources() {
+        let error = SVGError::from(io::Error::new(io::ErrorKind::NotFound, "missing file"));
+
+        assert_eq!(error.code(), "io_error");
+        assert_eq!(error.kind().code(), "IoError");
+        assert_eq!(
+            error.source().map(|source| source.to_string()),
+            Some("missing file".to_string())
+        );
     }
 }
@@ -0,0 +1,459 @@
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use manycore_parser::{
+    Core, ManycoreSystem, RoutingMap, RoutingType, WithID, BORDER_ROUTERS_KEY, TASK_COST_KEY,
+};
+use serde::Serialize;
+
+use crate::{
+    auto_bounds::resolve_auto_bounds,
+    computed_attributes::{attribute_value, validate_computed_attributes},
+    no_processing_group, BaseConfiguration, Configuration, InformationLayer, Offsets,
+    ResolvedBounds, SVGError, SVGErrorKind, UpdateResult, ViewBox, Warning, SVG,
+};
+
+/// Fingerprints of the inputs an [`SVG`]'s information layers were last generated from.
+/// Used to work out which [`InformationLayer`]s need regenerating on the next update.
+pub(crate) struct RenderState {
+    /// Fingerprint of the inputs shared by every core.
+    system: u64,
+    /// Fingerprint of each core's own inputs, indexed like the cores list.
+    cores: Vec<u64>,
+    /// Offsets required to display each core's information layer.
+    offsets: Vec<Offsets>,
+    /// The viewBox before being fit to any offset.
+    view_box: ViewBox,
+    /// Automatically derived bounds. Every layer is regenerated when these change.
+    resolved_bounds: ResolvedBounds,
+}
+
+impl RenderState {
+    /// Generates a new [`RenderState`] instance from the given parameters.
+    pub(crate) fn new(
+        system: u64,
+        cores: Vec<u64>,
+        offsets: Vec<Offsets>,
+        view_box: ViewBox,
+        resolved_bounds: ResolvedBounds,
+    ) -> Self {
+        Self {
+            system,
+            cores,
+            offsets,
+            view_box,
+            resolved_bounds,
+        }
+    }
+}
+
+/// Replacement for a single [`InformationLayer`], identified by the index of its core.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InformationLayerPatch {
+    index: usize,
+    content: String,
+}
+
+/// Result of an incremental update where only some [`InformationLayer`]s changed.
+/// Fields are kept private as no modification of this is ever expected. It's just a convenient wrapper for serialisation.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalUpdate {
+    /// Only provided if new fill classes were added.
+    style: Option<String>,
+    /// Class of every core and router whose fill changed since the previous update, keyed by element ID.
+    fill_classes: BTreeMap<String, String>,
+    information_layers: Vec<InformationLayerPatch>,
+    view_box: String,
+    /// The SVG `<metadata>` block. Only provided if it changed, e.g. because the [`Configuration`] was recorded.
+    metadata: Option<String>,
+    resolved_bounds: ResolvedBounds,
+    /// Warnings raised while generating the regenerated layers. Unchanged layers are not checked again.
+    warnings: Vec<Warning>,
+}
+
+/// Result of [`SVG::update_incrementally`].
+/// A full [`UpdateResult`] is provided whenever a change affects the whole [`SVG`].
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IncrementalUpdateResult {
+    Full(UpdateResult),
+    Patch(IncrementalUpdate),
+}
+
+/// Fingerprints the inputs that affect every core: the requested fields, the task allocations and the task graph.
+/// Edges and their communication costs decide the routing loads of every channel.
+/// Must be called before any key is removed from the [`Configuration`].
+pub(crate) fn system_fingerprint(
+    manycore: &ManycoreSystem,
+    configuration: &Configuration,
+) -> Result<u64, SVGError> {
+    let mut hasher = DefaultHasher::new();
+
+    serde_json::to_string(configuration.core_config())?.hash(&mut hasher);
+    serde_json::to_string(configuration.router_config())?.hash(&mut hasher);
+    serde_json::to_string(configuration.channel_config())?.hash(&mut hasher);
+    serde_json::to_string(configuration.computed_attributes())?.hash(&mut hasher);
+    // Fill overrides are per element, they only decide whether information is rendered at all.
+    configuration.core_fills().is_empty().hash(&mut hasher);
+    configuration.router_fills().is_empty().hash(&mut hasher);
+
+    for core in manycore.cores().list() {
+        core.allocated_task().hash(&mut hasher);
+    }
+
+    // Sort tasks and edges, map iteration order is not guaranteed
+    let task_graph = manycore.task_graph();
+    let mut tasks: Vec<_> = task_graph
+        .tasks()
+        .iter()
+        .map(|(task_id, task)| (*task_id, *task.computation_cost()))
+        .collect();
+    tasks.sort();
+    tasks.hash(&mut hasher);
+
+    let mut edges: Vec<_> = task_graph
+        .edges()
+        .iter()
+        .map(|edge| (*edge.from(), *edge.to(), *edge.communication_cost()))
+        .collect();
+    edges.sort();
+    edges.hash(&mut hasher);
+
+    Ok(hasher.finish())
+}
+
+/// Fingerprints the inputs a single core's [`InformationLayer`] is generated from.
+pub(crate) fn core_fingerprint(
+    core: &Core,
+    configuration: &Configuration,
+    links_with_load: Option<&RoutingMap>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for key in configuration.core_config().keys() {
+        attribute_value(core, key, configuration.computed_attributes()).hash(&mut hasher);
+    }
+    configuration.core_fills().get(core.id()).hash(&mut hasher);
+
+    for key in configuration.router_config().keys() {
+        attribute_value(core.router(), key, configuration.computed_attributes()).hash(&mut hasher);
+    }
+    configuration
+        .router_fills()
+        .get(core.router().id())
+        .hash(&mut hasher);
+
+    // Sort everything by direction, map iteration order is not guaranteed
+    let mut channels: Vec<_> = core.channels().channel().iter().collect();
+    channels.sort_by_key(|(direction, _)| **direction);
+    for (direction, channel) in channels {
+        direction.hash(&mut hasher);
+        channel.current_load().hash(&mut hasher);
+        channel.bandwidth().hash(&mut hasher);
+
+        for key in configuration.channel_config().keys() {
+            attribute_value(channel, key, configuration.computed_attributes()).hash(&mut hasher);
+        }
+    }
+
+    if let Some(source_loads) = core.source_loads() {
+        let mut source_loads: Vec<_> = source_loads.iter().collect();
+        source_loads.sort();
+        source_loads.hash(&mut hasher);
+    }
+
+    if let Some(routed_channels) = links_with_load.and_then(|map| map.get(core.id())) {
+        let mut routed_channels: Vec<(bool, Vec<_>)> = routed_channels
+            .iter()
+            .map(|(target, directions)| {
+                let mut directions: Vec<_> = directions.iter().copied().collect();
+                directions.sort();
+
+                (matches!(target, RoutingType::SourceChannel), directions)
+            })
+            .collect();
+        routed_channels.sort();
+        routed_channels.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// Error thrown when the stored [`RenderState`] does not match the [`ManycoreSystem`] being updated.
+fn stale_render_state(index: usize) -> SVGError {
+    SVGError::new(SVGErrorKind::GenerationError(format!(
+        "No previous render state for core at index {index}. The SVG was generated from a different system."
+    )))
+    .with_code("stale_render_state")
+}
+
+impl SVG {
+    /// Updates the [`SVG`] by regenerating only the [`InformationLayer`]s whose inputs changed since the previous update.
+    /// Falls back to [`SVG::update_configurable_information`] when a change affects the whole [`SVG`], e.g. a different
+    /// set of requested fields, a new [`BaseConfiguration`] or new task allocations.
+    pub fn update_incrementally(
+        &mut self,
+        manycore: &mut ManycoreSystem,
+        configuration: &mut Configuration,
+        base_configuration: &BaseConfiguration,
+    ) -> Result<IncrementalUpdateResult, SVGError> {
+        validate_computed_attributes(configuration, manycore)?;
+
+        let system = system_fingerprint(manycore, configuration)?;
+
+        let mut state = match self.render_state.take() {
+            Some(state)
+                if state.system == system && *base_configuration == self.base_configuration =>
+            {
+                state
+            }
+            _ => {
+                return Ok(IncrementalUpdateResult::Full(
+                    self.update_configurable_information(
+                        manycore,
+                        configuration,
+                        base_configuration,
+                    )?,
+                ));
+            }
+        };
+
+        let mut warnings = Vec::new();
+
+        let old_metadata = self.metadata.update_string()?;
+        self.metadata.provenance_mut().record(configuration)?;
+
+        // These were consumed by the previous update and are unchanged.
+        let (links_with_load, mut routing_configuration) =
+            SVG::take_routing(manycore, configuration, &mut warnings)?;
+        self.metadata
+            .provenance_mut()
+            .record_routing(routing_configuration.as_ref());
+        configuration
+            .channel_config_mut()
+            .remove(BORDER_ROUTERS_KEY);
+        configuration.core_config_mut().remove(TASK_COST_KEY);
+
+        let resolved_bounds = resolve_auto_bounds(
+            manycore,
+            configuration,
+            routing_configuration.as_mut(),
+            links_with_load.as_ref(),
+        );
+        // New bounds can change the colour of any element
+        let bounds_changed = resolved_bounds != state.resolved_bounds;
+
+        let style_length = self.style.css().len();
+        let mut fill_classes = BTreeMap::new();
+        let mut information_layers = Vec::new();
+
+        // Without information layers there's nothing to patch
+        if !self.root.information_group.groups().is_empty() {
+            for (i, core) in manycore.cores().list().iter().enumerate() {
+                let fingerprint = core_fingerprint(core, configuration, links_with_load.as_ref());
+                let previous = state.cores.get_mut(i).ok_or(stale_render_state(i))?;
+
+                if *previous == fingerprint && !bounds_changed {
+                    continue;
+                }
+
+                let processing_group = self
+                    .root
+                    .processing_group
+                    .g()
+                    .get(i)
+                    .ok_or(no_processing_group(i))?;
+
+                let mut offsets = Offsets::default();
+                let information_layer = InformationLayer::new(
+                    self.rows,
+                    configuration,
+                    core,
+                    links_with_load.as_ref(),
+                    processing_group,
+                    &self.root.connections_group,
+                    routing_configuration.as_ref(),
+                    &mut offsets,
+                    &self.namespace,
+                    &self.processed_base_configuration,
+                    &mut warnings,
+                )?;
+
+                self.apply_fill_classes(i, &information_layer, &mut fill_classes)?;
+
+                let layer = self
+                    .root
+                    .information_group
+                    .groups_mut()
+                    .get_mut(i)
+                    .ok_or(no_processing_group(i))?;
+                *layer = information_layer;
+
+                information_layers.push(InformationLayerPatch {
+                    index: i,
+                    content: quick_xml::se::to_string(&*layer)?,
+                });
+
+                *previous = fingerprint;
+                if let Some(previous_offsets) = state.offsets.get_mut(i) {
+                    *previous_offsets = offsets;
+                }
+            }
+        }
+
+        // Refit the viewBox to the offsets of every layer, some might have shrunk
+        let mut offsets = Offsets::default();
+        state
+            .offsets
+            .iter()
+            .for_each(|layer_offsets| offsets.update(*layer_offsets));
+        self.root.tasks_group.into_iter().for_each(|t| {
+            offsets.update(Offsets::from_task(t));
+        });
+
+        self.view_box.restore_from(&state.view_box);
+        self.view_box.fit_offsets(&offsets);
+
+        state.resolved_bounds = resolved_bounds.clone();
+        self.render_state = Some(state);
+
+        let metadata = self.metadata.update_string()?;
+
+        Ok(IncrementalUpdateResult::Patch(IncrementalUpdate {
+            style: if self.style.css().len() != style_length {
+                Some(self.style.css().clone())
+            } else {
+                None
+            },
+            fill_classes,
+            information_layers,
+            view_box: String::from(&self.view_box),
+            metadata: if metadata != old_metadata {
+                metadata
+            } else {
+                None
+            },
+            resolved_bounds,
+            warnings,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::{system_fingerprint, IncrementalUpdateResult};
+    use crate::{test_utils::read_configuration, BaseConfiguration, MetadataContent, SVG};
+
+    #[test]
+    fn regenerates_only_changed_layers() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let base_configuration = BaseConfiguration::default();
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        // The render time would change the metadata between updates
+        svg.set_metadata_content(MetadataContent::Reproducible);
+
+        // No previous state, everything is generated
+        let first = svg
+            .update_incrementally(
+                &mut manycore,
+                &mut read_configuration("tests/conf7.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert!(matches!(first, IncrementalUpdateResult::Full(_)));
+
+        // Nothing changed
+        let second = svg
+            .update_incrementally(
+                &mut manycore,
+                &mut read_configuration("tests/conf7.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        match second {
+            IncrementalUpdateResult::Patch(patch) => {
+                assert!(patch.information_layers.is_empty());
+                assert!(patch.fill_classes.is_empty());
+                assert!(patch.style.is_none());
+                assert!(patch.metadata.is_none());
+            }
+            IncrementalUpdateResult::Full(_) => panic!("Expected a patch."),
+        }
+
+        // A single core changed fill
+        let mut configuration = read_configuration("tests/conf7.json");
+        configuration
+            .core_fills_mut()
+            .insert(4, "#000000".to_string());
+        let third = svg
+            .update_incrementally(&mut manycore, &mut configuration, &base_configuration)
+            .expect("Could not update SVG.");
+        match third {
+            IncrementalUpdateResult::Patch(patch) => {
+                assert_eq!(patch.information_layers.len(), 1);
+                assert_eq!(patch.information_layers[0].index, 4);
+                assert_eq!(patch.fill_classes.len(), 1);
+                assert!(patch.fill_classes.contains_key("c4"));
+                assert!(patch.style.is_some_and(|style| style.contains("#000000")));
+                // The new configuration is recorded in the document's provenance
+                assert!(patch
+                    .metadata
+                    .is_some_and(|metadata| metadata.contains("#000000")));
+            }
+            IncrementalUpdateResult::Full(_) => panic!("Expected a patch."),
+        }
+
+        // Different fields require a full update
+        let fourth = svg
+            .update_incrementally(
+                &mut manycore,
+                &mut read_configuration("tests/conf3.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert!(matches!(fourth, IncrementalUpdateResult::Full(_)));
+    }
+
+    #[test]
+    fn task_graph_changes_require_full_update() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        // Same system, the edge from task 3 to task 4 costs 10 instead of 100
+        let mut cheaper_edge = ManycoreSystem::parse_file(
+            "tests/VisualiserOutput1_cheaper_edge.xml",
+        )
+        .expect("Could not read input test file \"tests/VisualiserOutput1_cheaper_edge.xml\"");
+
+        let configuration = read_configuration("tests/conf4.json");
+        assert_ne!(
+            system_fingerprint(&manycore, &configuration).expect("Could not fingerprint"),
+            system_fingerprint(&cheaper_edge, &configuration).expect("Could not fingerprint")
+        );
+
+        // Routing loads changed, channel layers must be regenerated
+        let base_configuration = BaseConfiguration::default();
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        svg.update_incrementally(
+            &mut manycore,
+            &mut read_configuration("tests/conf4.json"),
+            &base_configuration,
+        )
+        .expect("Could not update SVG.");
+        let update = svg
+            .update_incrementally(
+                &mut cheaper_edge,
+                &mut read_configuration("tests/conf4.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert!(matches!(update, IncrementalUpdateResult::Full(_)));
+    }
+}
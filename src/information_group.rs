@@ -1,24 +1,25 @@
-use getset::MutGetters;
+use getset::{Getters, MutGetters};
 use quick_xml::DeError;
 use serde::Serialize;
 
-use crate::{partial_update::PartialUpdate, InformationLayer};
+use crate::{namespace::IdNamespace, partial_update::PartialUpdate, InformationLayer};
 
-#[derive(Serialize, MutGetters)]
+#[derive(Serialize, Getters, MutGetters)]
 pub(crate) struct InformationGroup {
     #[serde(rename = "g", skip_serializing_if = "Vec::is_empty")]
-    #[getset(get_mut = "pub")]
+    #[getset(get = "pub", get_mut = "pub")]
     groups: Vec<InformationLayer>,
     #[serde(rename = "@id")]
-    id: &'static str,
+    #[getset(get = "pub")]
+    id: String,
 }
 
 impl InformationGroup {
     /// Creates a new [`InformationGroup`] with capacity for each [`ProcessingGroup`].
-    pub(crate) fn new(number_of_cores: &usize) -> Self {
+    pub(crate) fn new(number_of_cores: &usize, namespace: &IdNamespace) -> Self {
         Self {
             groups: Vec::with_capacity(*number_of_cores),
-            id: "information",
+            id: namespace.id("information"),
         }
     }
 }
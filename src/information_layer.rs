@@ -1,31 +1,37 @@
-use manycore_parser::{RoutingMap, SystemDimensionsT, WithID};
+use manycore_parser::{ElementIDT, RoutingMap, SystemDimensionsT, WithID};
+use quick_xml::DeError;
 use serde::Serialize;
 
 use crate::{
+    namespace::IdNamespace,
+    warnings::{ElementKind, Warning},
     ClipPath, Configuration, ConnectionsParentGroup, CoordinateT, Offsets,
-    ProcessedBaseConfiguration, ProcessingGroup, RoutingConfiguration, SVGError, ROUTER_OFFSET,
-    USE_FREEFORM_CLIP_PATH,
+    ProcessedBaseConfiguration, ProcessingGroup, RoutingConfiguration, SVGError,
+    FREEFORM_CLIP_PATH_ID, ROUTER_OFFSET,
 };
 
 static OFFSET_FROM_BORDER: CoordinateT = 1;
 
 /// Core or Router information SVG `<g>` wrapper.
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, Clone)]
 struct ProcessingInformation {
     #[serde(rename = "@clip-path")]
     clip_path: String,
     #[serde(rename = "text")]
     information: Vec<TextInformation>,
+    /// Fill colour of the element, if any. Applied through a [`Style`](crate::style::Style) class.
+    #[serde(skip)]
+    fill: Option<String>,
 }
 
 /// Object representation for an SVG `<g>` that wraps user configurable information for each core-derived group.
 /// core-derived groups include channels and routers as all the calculations to generate those groups rely on
 /// information provided by the [`manycore_parser::Core`] object.
-#[derive(Serialize, Default)]
+#[derive(Serialize, Default, Clone)]
 #[serde(rename = "g")]
 pub(crate) struct InformationLayer {
     #[serde(rename = "@clip-path")]
-    clip_path: &'static str,
+    clip_path: String,
     #[serde(rename = "g")]
     core_group: ProcessingInformation,
     #[serde(rename = "g")]
@@ -37,30 +43,34 @@ pub(crate) struct InformationLayer {
 }
 
 mod utils;
-use utils::*;
+pub(crate) use utils::*;
 mod text_information;
 pub(crate) use text_information::*;
 mod coordinates;
 use coordinates::make_coordinates;
 mod channel_data;
 use channel_data::*;
+mod diff;
+pub(crate) use diff::delta_magnitude;
 
 impl InformationLayer {
     /// Generates a new [`InformationLayer`] instance.
+    /// Anything that could not be rendered is reported in `warnings`.
     pub(crate) fn new(
         rows: SystemDimensionsT,
         configuration: &mut Configuration,
         core: &manycore_parser::Core,
         links_with_load: Option<&RoutingMap>,
-        css: &mut String,
         processing_group: &ProcessingGroup,
         connections_group: &ConnectionsParentGroup,
         routing_configuration: Option<&RoutingConfiguration>,
         offsets: &mut Offsets,
+        namespace: &IdNamespace,
         processed_base_configuration: &ProcessedBaseConfiguration,
+        warnings: &mut Vec<Warning>,
     ) -> Result<Self, SVGError> {
         let mut ret = InformationLayer::default();
-        ret.clip_path = USE_FREEFORM_CLIP_PATH;
+        ret.clip_path = namespace.url(FREEFORM_CLIP_PATH_ID);
 
         let (r, c) = processing_group.coordinates();
         let (core_x, core_y) = processing_group.core().move_coordinates();
@@ -82,15 +92,17 @@ impl InformationLayer {
             *core_x,
             *core_y,
             configuration.core_config(),
+            configuration.computed_attributes(),
             configuration.core_fills(),
             core,
             &mut ret.core_group,
             "start",
-            css,
             processed_base_configuration,
+            ElementKind::Core,
+            warnings,
         )?;
         // Clip path id
-        ret.core_group.clip_path = format!("url(#{})", ClipPath::make_core_id(core.id()));
+        ret.core_group.clip_path = namespace.url(&ClipPath::make_core_id(core.id()));
 
         // Router
         let (router_x, router_y) = processing_group.router().move_coordinates();
@@ -98,16 +110,17 @@ impl InformationLayer {
             *router_x,
             router_y - ROUTER_OFFSET,
             configuration.router_config(),
+            configuration.computed_attributes(),
             configuration.router_fills(),
             core.router(),
             &mut ret.router_group,
             "start",
-            css,
             processed_base_configuration,
+            ElementKind::Router,
+            warnings,
         )?;
         // Clip path id
-        ret.router_group.clip_path =
-            format!("url(#{})", ClipPath::make_router_id(core.router().id()));
+        ret.router_group.clip_path = namespace.url(&ClipPath::make_router_id(core.router().id()));
 
         // Channels
         generate_channel_data(
@@ -119,8 +132,92 @@ impl InformationLayer {
             offsets,
             &mut ret,
             processed_base_configuration,
+            warnings,
         )?;
 
+        ret.set_text_ids(core.id(), namespace);
+
         Ok(ret)
     }
+
+    /// Every label of the layer, in document order.
+    fn texts(&self) -> impl Iterator<Item = &TextInformation> {
+        self.core_group
+            .information
+            .iter()
+            .chain(self.router_group.information.iter())
+            .chain(self.coordinates.iter())
+            .chain(self.links_load.iter())
+    }
+
+    /// Identifies every label by its element and position: `c{id}-label{n}` for core labels, `r{id}-label{n}` for
+    /// router labels and `info{id}-label{n}` for coordinates and channel labels.
+    fn set_text_ids(&mut self, core_id: &ElementIDT, namespace: &IdNamespace) {
+        for (prefix, texts) in [
+            (
+                "c",
+                self.core_group.information.iter_mut().collect::<Vec<_>>(),
+            ),
+            ("r", self.router_group.information.iter_mut().collect()),
+            (
+                "info",
+                self.coordinates
+                    .iter_mut()
+                    .chain(self.links_load.iter_mut())
+                    .collect(),
+            ),
+        ] {
+            for (n, text) in texts.into_iter().enumerate() {
+                text.set_id(Some(namespace.id(&format!("{prefix}{core_id}-label{n}"))));
+            }
+        }
+    }
+
+    /// If `new` differs from this layer only in the content of its labels, returns the ID and new text of every
+    /// label that changed.
+    pub(crate) fn text_changes(
+        &self,
+        new: &InformationLayer,
+    ) -> Result<Option<Vec<(String, String)>>, DeError> {
+        if self.texts().count() != new.texts().count() {
+            return Ok(None);
+        }
+
+        // Put the old text back into a copy of the new layer, anything left over is not a text change
+        let mut restored = new.clone();
+        let mut changes = Vec::new();
+        for (text, old) in restored
+            .core_group
+            .information
+            .iter_mut()
+            .chain(restored.router_group.information.iter_mut())
+            .chain(restored.coordinates.iter_mut())
+            .chain(restored.links_load.iter_mut())
+            .zip(self.texts())
+        {
+            if text.value() != old.value() {
+                match text.id() {
+                    Some(id) => changes.push((id.clone(), text.value().clone())),
+                    None => return Ok(None),
+                }
+                text.set_value(old.value().clone());
+            }
+        }
+
+        if quick_xml::se::to_string(&restored)? == quick_xml::se::to_string(self)? {
+            Ok(Some(changes))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the fill colour requested for the core, if any.
+    pub(crate) fn core_fill(&self) -> Option<&String> {
+        self.core_group.fill.as_ref()
+    }
+
+    /// Returns the fill colour requested for the router, if any.
+    pub(crate) fn router_fill(&self) -> Option<&String> {
+        self.router_group.fill.as_ref()
+    }
 }
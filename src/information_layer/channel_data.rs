@@ -1,18 +1,20 @@
-use std::collections::{btree_map::Iter, BTreeSet};
+use std::collections::{btree_map::Iter, BTreeMap, BTreeSet};
 
 use manycore_parser::{
     Channel, Core, Directions, RoutingMap, RoutingType, WithID, WithXMLAttributes,
 };
 
 use crate::{
-    Configuration, ConnectionType, ConnectionsParentGroup, CoordinateT, DirectionType,
-    FieldConfiguration, InformationLayer, Offsets, ProcessedBaseConfiguration,
+    computed_attributes::attribute_value,
+    warnings::{missing_attribute, non_numeric_value, unsupported_field, Warning},
+    ComputedAttribute, Configuration, ConnectionType, ConnectionsParentGroup, CoordinateT,
+    DirectionType, FieldConfiguration, InformationLayer, Offsets, ProcessedBaseConfiguration,
     RoutingConfiguration, SVGError, TextInformation,
 };
 
 use super::{
     get_connection_type, missing_channel, missing_connection, missing_source_load,
-    missing_source_loads,
+    missing_source_loads, parse_colour_value,
 };
 
 /// Utility to retrieve an SVG connection's coordinates and whether it is an edge or an inner connection.
@@ -58,27 +60,61 @@ fn channel_info_details<'a>(
     }
 }
 
+/// Reports a channel attribute value that cannot be rendered as configured.
+fn check_channel_value(
+    core: &Core,
+    direction: &Directions,
+    key: &str,
+    field_configuration: &FieldConfiguration,
+    value: &str,
+    warnings: &mut Vec<Warning>,
+) {
+    let reason = match field_configuration {
+        FieldConfiguration::Text { .. } => None,
+        FieldConfiguration::ColouredText { .. } => parse_colour_value(value)
+            .is_none()
+            .then(|| non_numeric_value(value)),
+        unsupported => Some(unsupported_field(unsupported)),
+    };
+
+    if let Some(reason) = reason {
+        warnings.push(Warning::channel(core.id(), direction, key, reason));
+    }
+}
+
 /// Generates an SVG channel secondary [`TextInformation`], if requested and present.
 fn get_secondary_channel_attribute(
     x: &CoordinateT,
     y: &CoordinateT,
     edge: bool,
     configuration_iterator: &mut Iter<'_, String, FieldConfiguration>,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+    core: &Core,
     channel: &Channel,
     direction: &Directions,
     prrocessed_base_configuration: &ProcessedBaseConfiguration,
+    warnings: &mut Vec<Warning>,
 ) -> Option<TextInformation> {
     if !edge {
-        if let (Some((key, field_configuration)), Some(channel_attributes)) =
+        if let (Some((key, field_configuration)), Some(_)) =
             (configuration_iterator.next(), channel.other_attributes())
         {
-            return match channel_attributes.get(key) {
+            return match attribute_value(channel, key, computed_attributes) {
                 Some(attribute_value) => {
+                    check_channel_value(
+                        core,
+                        direction,
+                        key,
+                        field_configuration,
+                        &attribute_value,
+                        warnings,
+                    );
+
                     let link_secondary_text = TextInformation::link_secondary(
                         direction,
                         x,
                         y,
-                        attribute_value,
+                        &attribute_value,
                         field_configuration,
                         prrocessed_base_configuration,
                     );
@@ -87,7 +123,14 @@ fn get_secondary_channel_attribute(
                 }
                 None => {
                     // Not all attributes must be present on every channel I suppose.
-                    // Do nothing if this channel does not have the requested one.
+                    // Let the user know this channel does not have the requested one.
+                    warnings.push(Warning::channel(
+                        core.id(),
+                        direction,
+                        key,
+                        missing_attribute(),
+                    ));
+
                     None
                 }
             };
@@ -106,6 +149,7 @@ pub(crate) fn generate_channel_data(
     offsets: &mut Offsets,
     ret: &mut InformationLayer,
     processed_base_configuration: &ProcessedBaseConfiguration,
+    warnings: &mut Vec<Warning>,
 ) -> Result<(), SVGError> {
     // We use this set to keep track of directions we can add information to.
     let mut remaining_directions: BTreeSet<&Directions> =
@@ -185,9 +229,12 @@ pub(crate) fn generate_channel_data(
                         y,
                         edge,
                         &mut configuration.channel_config().iter(),
+                        configuration.computed_attributes(),
+                        core,
                         channel,
                         direction,
                         processed_base_configuration,
+                        warnings,
                     ) {
                         // This channel data might need the viewBox extended to be fully displayed.
                         offsets.update(Offsets::try_from_channel(&link_secondary_text, direction)?);
@@ -223,16 +270,25 @@ pub(crate) fn generate_channel_data(
             &RoutingType::OutputChannel,
         )?;
 
-        if let Some(channel_attributes) = channel.other_attributes() {
+        if channel.other_attributes().is_some() {
             // First element
             if let Some((key, field_configuration)) = iter.next() {
-                match channel_attributes.get(key) {
+                match attribute_value(channel, key, configuration.computed_attributes()) {
                     Some(attribute_value) => {
+                        check_channel_value(
+                            core,
+                            direction,
+                            key,
+                            field_configuration,
+                            &attribute_value,
+                            warnings,
+                        );
+
                         let link_text = TextInformation::link_primary(
                             direction,
                             x,
                             y,
-                            attribute_value,
+                            &attribute_value,
                             // Edge is always false, we are not walking over any edge direction.
                             // Set difference iterator removes them.
                             false,
@@ -245,7 +301,13 @@ pub(crate) fn generate_channel_data(
                     }
                     None => {
                         // Not all attributes must be present on every channel I suppose.
-                        // Do nothing if this channel does not have the requested one.
+                        // Let the user know this channel does not have the requested one.
+                        warnings.push(Warning::channel(
+                            core.id(),
+                            direction,
+                            key,
+                            missing_attribute(),
+                        ));
                     }
                 }
             }
@@ -259,9 +321,12 @@ pub(crate) fn generate_channel_data(
             // Set difference iterator removes them.
             false,
             &mut iter,
+            configuration.computed_attributes(),
+            core,
             channel,
             direction,
             processed_base_configuration,
+            warnings,
         ) {
             // This channel data might need the viewBox extended to be fully displayed.
             offsets.update(Offsets::try_from_channel(&link_secondary_text, direction)?);
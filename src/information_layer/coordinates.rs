@@ -32,7 +32,9 @@ pub(crate) fn make_coordinates(
             fc => Err(generation_error(format!(
                 "Unsupported configuration for coordinates: {}",
                 fc.type_str()
-            ))),
+            ))
+            .with_code("unsupported_coordinates_configuration")
+            .with_key(COORDINATES_KEY)),
         }?;
 
         ret.coordinates = Some(TextInformation::new(
@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+
+use manycore_parser::{
+    ElementIDT, SystemDimensionsT, WithID, WithXMLAttributes, COORDINATES_KEY, ID_KEY,
+};
+
+use super::{make_coordinates, ProcessingInformation, TextInformation, OFFSET_FROM_BORDER};
+use crate::{
+    computed_attributes::attribute_value, namespace::IdNamespace, parse_colour_value, ClipPath,
+    ComputedAttribute, Configuration, CoordinateT, FieldConfiguration, InformationLayer,
+    ProcessedBaseConfiguration, ProcessingGroup, SVGError, FREEFORM_CLIP_PATH_ID, ROUTER_OFFSET,
+};
+
+/// Placeholder displayed in a diff label when one of the two snapshots does not have the attribute.
+static MISSING_VALUE: &'static str = "-";
+
+/// Calculates the magnitude of the change between two attribute values, if both are numeric.
+pub(crate) fn delta_magnitude(
+    old_value: Option<&String>,
+    new_value: Option<&String>,
+) -> Option<f64> {
+    Some((parse_colour_value(new_value?)? - parse_colour_value(old_value?)?).abs())
+}
+
+/// Generates the `old → new` text of a diff label.
+fn diff_label(display: &String, old_value: Option<&String>, new_value: Option<&String>) -> String {
+    format!(
+        "{}: {} → {}",
+        display,
+        old_value.map_or(MISSING_VALUE, |v| v.as_str()),
+        new_value.map_or(MISSING_VALUE, |v| v.as_str())
+    )
+}
+
+/// Generates [`InformationLayer`] diff content for a [`WithID`] element.
+/// Only changed attributes are labelled, while `Fill` attributes colour the element by delta magnitude.
+fn generate_diff_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
+    mut base_x: CoordinateT,
+    mut base_y: CoordinateT,
+    configuration: &BTreeMap<String, FieldConfiguration>,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+    fill_override: &BTreeMap<ElementIDT, String>,
+    old_target: &T,
+    new_target: &T,
+    group: &mut ProcessingInformation,
+    processed_base_configuration: &ProcessedBaseConfiguration,
+) {
+    // Start by adding some padding between text and element border
+    base_x = base_x.saturating_add(OFFSET_FROM_BORDER);
+
+    for (key, field_configuration) in configuration {
+        if key == ID_KEY || key == COORDINATES_KEY {
+            // IDs never change between snapshots and coordinates are handled at layer level.
+            continue;
+        }
+
+        let old_value = attribute_value(old_target, key, computed_attributes);
+        let new_value = attribute_value(new_target, key, computed_attributes);
+        let (old_value, new_value) = (old_value.as_deref(), new_value.as_deref());
+        let delta = delta_magnitude(old_value, new_value);
+
+        match field_configuration {
+            FieldConfiguration::Text {
+                display, colour, ..
+            } => {
+                if old_value != new_value {
+                    group.information.push(TextInformation::new(
+                        base_x,
+                        base_y,
+                        *processed_base_configuration.attribute_font_size(),
+                        "start",
+                        "text-before-edge",
+                        colour.as_ref(),
+                        None,
+                        diff_label(display, old_value, new_value),
+                    ));
+
+                    // Increase y for next element, if any
+                    base_y = base_y.saturating_add(
+                        *processed_base_configuration.attribute_font_size_coordinate(),
+                    );
+                }
+            }
+            FieldConfiguration::ColouredText {
+                display,
+                colour_settings,
+                ..
+            } => {
+                if old_value != new_value {
+                    // Text colour reflects the delta magnitude, not the value itself.
+                    let fill = delta.map(|delta| colour_settings.colour(delta));
+
+                    group.information.push(TextInformation::new(
+                        base_x,
+                        base_y,
+                        *processed_base_configuration.attribute_font_size(),
+                        "start",
+                        "text-before-edge",
+                        fill,
+                        None,
+                        diff_label(display, old_value, new_value),
+                    ));
+
+                    // Increase y for next element, if any
+                    base_y = base_y.saturating_add(
+                        *processed_base_configuration.attribute_font_size_coordinate(),
+                    );
+                }
+            }
+            FieldConfiguration::Fill { colour_settings } => {
+                // Do not compute if user requested override
+                if let (None, Some(delta)) = (fill_override.get(new_target.id()), delta) {
+                    group.fill = Some(colour_settings.colour(delta).clone());
+                }
+            }
+            _ => {
+                // Remaining variants are not meaningful in a diff
+            }
+        }
+    }
+
+    // Did the user request to override fill colour?
+    if let Some(fill) = fill_override.get(new_target.id()) {
+        group.fill = Some(fill.clone());
+    }
+}
+
+impl InformationLayer {
+    /// Generates a new [`InformationLayer`] instance describing the changes between two snapshots of the same core.
+    pub(crate) fn diff(
+        rows: SystemDimensionsT,
+        configuration: &Configuration,
+        old_core: &manycore_parser::Core,
+        new_core: &manycore_parser::Core,
+        processing_group: &ProcessingGroup,
+        namespace: &IdNamespace,
+        processed_base_configuration: &ProcessedBaseConfiguration,
+    ) -> Result<Self, SVGError> {
+        let mut ret = InformationLayer::default();
+        ret.clip_path = namespace.url(FREEFORM_CLIP_PATH_ID);
+
+        let (r, c) = processing_group.coordinates();
+        let (core_x, core_y) = processing_group.core().move_coordinates();
+
+        // Coordinates are stored in the core config but apply to whole group
+        make_coordinates(
+            configuration.core_config(),
+            core_x,
+            core_y,
+            rows,
+            r,
+            c,
+            &mut ret,
+            processed_base_configuration,
+        )?;
+
+        // Core
+        generate_diff_with_id(
+            *core_x,
+            *core_y,
+            configuration.core_config(),
+            configuration.computed_attributes(),
+            configuration.core_fills(),
+            old_core,
+            new_core,
+            &mut ret.core_group,
+            processed_base_configuration,
+        );
+        // Clip path id
+        ret.core_group.clip_path = namespace.url(&ClipPath::make_core_id(new_core.id()));
+
+        // Router
+        let (router_x, router_y) = processing_group.router().move_coordinates();
+        generate_diff_with_id(
+            *router_x,
+            router_y - ROUTER_OFFSET,
+            configuration.router_config(),
+            configuration.computed_attributes(),
+            configuration.router_fills(),
+            old_core.router(),
+            new_core.router(),
+            &mut ret.router_group,
+            processed_base_configuration,
+        );
+        // Clip path id
+        ret.router_group.clip_path =
+            namespace.url(&ClipPath::make_router_id(new_core.router().id()));
+
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delta_magnitude, diff_label};
+
+    #[test]
+    fn measures_deltas() {
+        let value = |value: &str| value.to_string();
+
+        assert_eq!(
+            delta_magnitude(Some(&value("238")), Some(&value("38"))),
+            Some(200.0)
+        );
+        assert_eq!(
+            delta_magnitude(Some(&value("45")), Some(&value("60"))),
+            Some(15.0)
+        );
+        assert_eq!(
+            delta_magnitude(Some(&value("30")), Some(&value("30"))),
+            Some(0.0)
+        );
+        assert_eq!(
+            delta_magnitude(Some(&value("High")), Some(&value("Low"))),
+            None
+        );
+        assert_eq!(
+            delta_magnitude(Some(&value("-2.5")), Some(&value("10"))),
+            Some(12.5)
+        );
+        assert_eq!(delta_magnitude(None, Some(&value("30"))), None);
+
+        assert_eq!(
+            diff_label(&value("Temp"), None, Some(&value("60"))),
+            "Temp: - → 60"
+        );
+    }
+}
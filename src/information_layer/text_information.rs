@@ -36,7 +36,7 @@ pub(crate) static MAXIMUM_ATTRIBUTE_FONT_SIZE: FontSizeT = 24.0;
 static ROBOTO_RATIO: FontSizeT = 1.665;
 
 /// Wrapper around font size
-#[derive(Getters)]
+#[derive(Getters, Clone)]
 pub(crate) struct FontSize {
     #[getset(get = "pub")]
     px: FontSizeT,
@@ -54,8 +54,12 @@ impl Serialize for FontSize {
 }
 
 /// Object representation of an SVG `<text>` element.
-#[derive(Serialize, Getters, Setters)]
+#[derive(Serialize, Getters, Setters, Clone)]
 pub(crate) struct TextInformation {
+    /// Only information layer labels have an ID, so that their text can be updated in place.
+    #[serde(rename = "@id", skip_serializing_if = "Option::is_none")]
+    #[getset(get = "pub", set = "pub")]
+    id: Option<String>,
     #[serde(rename = "@x")]
     #[getset(get = "pub", set = "pub")]
     x: CoordinateT,
@@ -78,6 +82,7 @@ pub(crate) struct TextInformation {
     #[serde(rename = "@class", skip_serializing_if = "Option::is_none")]
     class: Option<&'static str>,
     #[serde(rename = "$text")]
+    #[getset(get = "pub", set = "pub")]
     value: String,
 }
 
@@ -116,6 +121,7 @@ impl TextInformation {
         value: String,
     ) -> Self {
         Self {
+            id: None,
             x,
             y,
             font_size: FontSize { px: font_size },
@@ -204,23 +210,26 @@ impl TextInformation {
         }
     }
 
+    /// Calculates a channel's load as a percentage of its bandwidth.
+    /// We can only calculate load percentage if the bandwidth is above 0.
+    pub(crate) fn load_percentage(load: &u16, bandwidth: &u16) -> Option<u16> {
+        if *bandwidth > 0 {
+            Some(((FontSizeT::from(*load) / FontSizeT::from(*bandwidth)) * 100.0).round() as u16)
+        } else {
+            None
+        }
+    }
+
     /// Calculates the fill and load percentage of a channel.
-    fn calculate_load_fill_and_percentage<'a>(
+    pub(crate) fn calculate_load_fill_and_percentage<'a>(
         load: &u16,
         bandwidth: &u16,
         routing_configuration: &'a RoutingConfiguration,
     ) -> (Option<u16>, Option<&'a String>) {
-        if *bandwidth > 0 {
-            // We can only calculaye load percentage if the bandwidth is above 0.
-            let percentage =
-                ((FontSizeT::from(*load) / FontSizeT::from(*bandwidth)) * 100.0).round() as u16;
-
-            let fill_idx = utils::binary_search_left_insertion_point(
-                routing_configuration.load_colours().bounds(),
-                percentage.into(),
-            );
-
-            let fill = &routing_configuration.load_colours().colours()[fill_idx];
+        if let Some(percentage) = TextInformation::load_percentage(load, bandwidth) {
+            let fill = routing_configuration
+                .load_colours()
+                .colour(percentage.into());
             return (Some(percentage), Some(fill));
         } else {
             // If we can't calculate a load percentage, the channel is overloaded so we pick the last colour.
@@ -238,23 +247,29 @@ impl TextInformation {
         percentage: Option<u16>,
         routing_configuration: &RoutingConfiguration,
     ) -> String {
+        let format = routing_configuration.format();
+
         // Does the user want fraction or percentage?
         match routing_configuration.load_configuration() {
             LoadConfiguration::Percentage => match percentage {
-                Some(value) => format!("{}: {}%", routing_configuration.display(), value),
+                Some(value) => format!(
+                    "{}: {}%",
+                    routing_configuration.display(),
+                    format.apply(&value.to_string())
+                ),
                 // We can't give them a percentage for a channel with no bandwidth -> default to fraction.
                 None => format!(
                     "{}: {}/{}",
                     routing_configuration.display(),
-                    load,
-                    bandwidth
+                    format.apply(&load.to_string()),
+                    format.apply(&bandwidth.to_string())
                 ),
             },
             LoadConfiguration::Fraction => format!(
                 "{}: {}/{}",
                 routing_configuration.display(),
-                load,
-                bandwidth
+                format.apply(&load.to_string()),
+                format.apply(&bandwidth.to_string())
             ),
         }
     }
@@ -368,17 +383,19 @@ impl TextInformation {
             FieldConfiguration::ColouredText {
                 display: value,
                 colour_settings,
+                format,
             } => (
-                utils::get_attribute_colour(
-                    colour_settings.bounds(),
-                    colour_settings.colours(),
-                    data,
-                ),
-                format!("{}: {}", value, data),
+                utils::get_attribute_colour(colour_settings, data),
+                format!("{}: {}", value, format.apply(data)),
+            ),
+            FieldConfiguration::Text {
+                display,
+                colour,
+                format,
+            } => (
+                colour.as_ref(),
+                format!("{}: {}", display, format.apply(data)),
             ),
-            FieldConfiguration::Text { display, colour } => {
-                (colour.as_ref(), format!("{}: {}", display, data))
-            }
             _ => (None, "".into()), // Unsupported
         };
 
@@ -411,17 +428,19 @@ impl TextInformation {
             FieldConfiguration::ColouredText {
                 display: value,
                 colour_settings,
+                format,
             } => (
-                utils::get_attribute_colour(
-                    colour_settings.bounds(),
-                    colour_settings.colours(),
-                    data,
-                ),
-                format!("{}: {}", value, data),
+                utils::get_attribute_colour(colour_settings, data),
+                format!("{}: {}", value, format.apply(data)),
+            ),
+            FieldConfiguration::Text {
+                display,
+                colour,
+                format,
+            } => (
+                colour.as_ref(),
+                format!("{}: {}", display, format.apply(data)),
             ),
-            FieldConfiguration::Text { display, colour } => {
-                (colour.as_ref(), format!("{}: {}", display, data))
-            }
             _ => (None, "".into()), // Any other variant shouldn't be used.
         };
 
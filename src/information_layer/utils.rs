@@ -7,12 +7,18 @@ use manycore_parser::{Directions, ElementIDT, WithID, WithXMLAttributes, COORDIN
 
 use super::{ProcessingInformation, TextInformation, OFFSET_FROM_BORDER};
 use crate::{
-    generation_error, ConnectionType, ConnectionsParentGroup, CoordinateT, DirectionType,
-    FieldConfiguration, ProcessedBaseConfiguration, SVGError, SVGErrorKind,
+    computed_attributes::attribute_value,
+    generation_error,
+    warnings::{non_numeric_value, unsupported_field, ElementKind, Warning},
+    ColourSettings, ComputedAttribute, ConnectionType, ConnectionsParentGroup, CoordinateT,
+    DirectionType, FieldConfiguration, ProcessedBaseConfiguration, SVGError, SVGErrorKind,
 };
 
 /// Binary search to fit input value in one of the 4 boundaries.
-pub(crate) fn binary_search_left_insertion_point(bounds: &[u64; 4], val: u64) -> usize {
+pub(crate) fn binary_search_left_insertion_point<T: PartialOrd + Copy>(
+    bounds: &[T; 4],
+    val: T,
+) -> usize {
     // Bounds has always length 4
     let mut l: i8 = 0;
     let max_i: i8 = 3;
@@ -47,12 +53,14 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
     mut base_x: CoordinateT,
     mut base_y: CoordinateT,
     configuration: &BTreeMap<String, FieldConfiguration>,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
     fill_override: &BTreeMap<ElementIDT, String>,
     target: &T,
     group: &mut ProcessingInformation,
     text_anchor: &'static str,
-    css: &mut String,
     processed_base_configuration: &ProcessedBaseConfiguration,
+    element: ElementKind,
+    warnings: &mut Vec<Warning>,
 ) -> Result<(), SVGError> {
     // Start by adding some padding between text and element border
     base_x = base_x.saturating_add(OFFSET_FROM_BORDER);
@@ -60,7 +68,11 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
     // ID value is outside of attributes map
     if let Some(configuration) = configuration.get(ID_KEY) {
         match configuration {
-            FieldConfiguration::Text { display, colour } => {
+            FieldConfiguration::Text {
+                display,
+                colour,
+                format,
+            } => {
                 group.information.push(TextInformation::new(
                     base_x,
                     base_y,
@@ -69,7 +81,7 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                     "text-before-edge",
                     colour.as_ref(),
                     None,
-                    format!("{}: {}", display, target.id()),
+                    format!("{}: {}", display, format.apply(&target.id().to_string())),
                 ));
                 base_y = base_y
                     .saturating_add(*processed_base_configuration.attribute_font_size_coordinate());
@@ -78,12 +90,15 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
             fc => Err(generation_error(format!(
                 "Unsupported configuration for ID: {}",
                 fc.type_str()
-            ))),
+            ))
+            .with_code("unsupported_id_configuration")
+            .with_element(element, target.id())
+            .with_key(ID_KEY)),
         }?
     }
 
     // Can we even do this? i.e. does the element have attributes?
-    if let Some(map) = target.other_attributes() {
+    if target.other_attributes().is_some() {
         // Iterate through the requested attributes.
         for k in configuration.keys() {
             match k.as_str() {
@@ -92,11 +107,16 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                 }
                 valid_key => {
                     // Fetch attribute value and its requested configuration
-                    if let (Some(field_configuration), Some(value)) =
-                        (configuration.get(valid_key), map.get(k))
-                    {
+                    if let (Some(field_configuration), Some(value)) = (
+                        configuration.get(valid_key),
+                        attribute_value(target, k, computed_attributes),
+                    ) {
                         match field_configuration {
-                            FieldConfiguration::Text { display, colour } => {
+                            FieldConfiguration::Text {
+                                display,
+                                colour,
+                                format,
+                            } => {
                                 // Simple Text
                                 group.information.push(TextInformation::new(
                                     base_x,
@@ -106,7 +126,7 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                                     "text-before-edge",
                                     colour.as_ref(),
                                     None,
-                                    format!("{}: {}", display, value),
+                                    format!("{}: {}", display, format.apply(&value)),
                                 ));
 
                                 // Increase y for next element, if any
@@ -118,37 +138,37 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                                 // Do not compute if user requested override
                                 if let None = fill_override.get(target.id()) {
                                     // Fill colour
-                                    let bounds = colour_settings.bounds();
-
                                     // If we can't parse it as a number, we can't calculate what the fill colour should be.
-                                    // TODO: Conversion error instead?
-                                    if let Ok(value_num) = value.parse::<u64>() {
-                                        let fill_idx =
-                                            binary_search_left_insertion_point(bounds, value_num);
-
-                                        // Add fill colour in the [`SVG`] CSS
-                                        css.push_str(
-                                            format!(
-                                                "\n#{}{} {{fill: {};}}",
-                                                target.variant(),
-                                                target.id(),
-                                                colour_settings.colours()[fill_idx]
-                                            )
-                                            .as_str(),
-                                        );
+                                    match parse_colour_value(&value) {
+                                        Some(value_num) => {
+                                            // Fill is applied through a class once all attributes are processed
+                                            group.fill =
+                                                Some(colour_settings.colour(value_num).clone());
+                                        }
+                                        None => warnings.push(Warning::element(
+                                            element,
+                                            target.id(),
+                                            k,
+                                            non_numeric_value(&value),
+                                        )),
                                     }
                                 }
                             }
                             FieldConfiguration::ColouredText {
                                 display: title,
                                 colour_settings,
+                                format,
                             } => {
                                 // Coloured text
-                                let fill = get_attribute_colour(
-                                    colour_settings.bounds(),
-                                    colour_settings.colours(),
-                                    value,
-                                );
+                                let fill = get_attribute_colour(colour_settings, &value);
+                                if fill.is_none() {
+                                    warnings.push(Warning::element(
+                                        element,
+                                        target.id(),
+                                        k,
+                                        non_numeric_value(&value),
+                                    ));
+                                }
 
                                 group.information.push(TextInformation::new(
                                     base_x,
@@ -158,7 +178,7 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                                     "text-before-edge",
                                     fill,
                                     None,
-                                    format!("{}: {}", title, value),
+                                    format!("{}: {}", title, format.apply(&value)),
                                 ));
 
                                 // Increase y for next element, if any
@@ -166,8 +186,14 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
                                     *processed_base_configuration.attribute_font_size_coordinate(),
                                 );
                             }
-                            _ => {
-                                // Remaining variants are handled elsewhere/for other elements
+                            unsupported => {
+                                // Special keys are removed before getting here
+                                warnings.push(Warning::element(
+                                    element,
+                                    target.id(),
+                                    k,
+                                    unsupported_field(unsupported),
+                                ));
                             }
                         }
                     } // else this element does not contain this attribute
@@ -178,27 +204,66 @@ pub(crate) fn generate_with_id<T: WithID<ElementIDT> + WithXMLAttributes>(
 
     // Did the user request to override fill colour?
     if let Some(fill) = fill_override.get(target.id()) {
-        // Add fill colour in the [`SVG`] CSS
-        css.push_str(
-            format!("\n#{}{} {{fill: {};}}", target.variant(), target.id(), fill).as_str(),
-        );
+        group.fill = Some(fill.clone());
     }
 
     Ok(())
 }
 
-/// Calculates the corresponding colour for an attribute value given some bounds.
+/// Retrieves the value of an attribute from an element, if present.
+pub(crate) fn get_attribute_value<'a, T: WithXMLAttributes>(
+    target: &'a T,
+    key: &String,
+) -> Option<&'a String> {
+    match target.other_attributes() {
+        Some(map) => map.get(key),
+        None => None,
+    }
+}
+
+/// Calculates the fill colour of an element according to the provided configuration, if any.
+/// A user requested override takes precedence over any `Fill` attribute.
+pub(crate) fn get_fill_colour<'a, T: WithID<ElementIDT> + WithXMLAttributes>(
+    configuration: &'a BTreeMap<String, FieldConfiguration>,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+    fill_override: &'a BTreeMap<ElementIDT, String>,
+    target: &T,
+) -> Option<&'a String> {
+    if let Some(fill) = fill_override.get(target.id()) {
+        return Some(fill);
+    }
+
+    let mut fill = None;
+    for (key, field_configuration) in configuration {
+        if let FieldConfiguration::Fill { colour_settings } = field_configuration {
+            if let Some(value) = attribute_value(target, key, computed_attributes) {
+                // Later attributes take precedence, just like their CSS rules would.
+                if let Some(colour) = get_attribute_colour(colour_settings, &value) {
+                    fill = Some(colour);
+                }
+            }
+        }
+    }
+
+    fill
+}
+
+/// Parses an attribute value to look up its colour. Negative and fractional values, e.g. of computed attributes,
+/// are kept as they are.
+pub(crate) fn parse_colour_value(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// Calculates the corresponding colour for an attribute value given some [`ColourSettings`].
 pub(crate) fn get_attribute_colour<'a>(
-    bounds: &'a [u64; 4],
-    colours: &'a [String; 4],
-    attribute_value: &'a String,
+    colour_settings: &'a ColourSettings,
+    attribute_value: &String,
 ) -> Option<&'a String> {
     let mut fill: Option<&String> = None;
 
-    // TODO: Conversion errorr instead?
-    if let Ok(value_num) = attribute_value.parse::<u64>() {
-        let fill_idx = binary_search_left_insertion_point(bounds, value_num);
-        fill = Some(&colours[fill_idx]);
+    // Non numeric values are reported as warnings by callers
+    if let Some(value_num) = parse_colour_value(attribute_value) {
+        fill = Some(colour_settings.colour(value_num));
     }
 
     fill
@@ -213,15 +278,24 @@ pub(crate) fn get_connection_type<'a>(
     connections_group
         .core_connections_map()
         .get(core_id)
-        .ok_or(SVGError::new(SVGErrorKind::ConnectionError(format!(
-            "Could not get connections for Core {}",
-            core_id
-        ))))?
+        .ok_or_else(|| {
+            SVGError::new(SVGErrorKind::ConnectionError(format!(
+                "Could not get connections for Core {}",
+                core_id
+            )))
+            .with_code("missing_core_connections")
+            .with_element(ElementKind::Core, core_id)
+        })?
         .get(direction_type)
-        .ok_or(SVGError::new(SVGErrorKind::ConnectionError(format!(
-            "Could not get connection {} for Core {}",
-            direction_type, core_id
-        ))))
+        .ok_or_else(|| {
+            SVGError::new(SVGErrorKind::ConnectionError(format!(
+                "Could not get connection {} for Core {}",
+                direction_type, core_id
+            )))
+            .with_code("missing_connection")
+            .with_element(ElementKind::Core, core_id)
+            .with_direction(direction_type)
+        })
 }
 
 /// Wrapper to generate error when we can't grab an SVG connection.
@@ -230,6 +304,7 @@ pub(crate) fn missing_connection(idx: &usize) -> SVGError {
         "Could not grab SVG connection path for Core {}",
         idx
     )))
+    .with_code("missing_connection_path")
 }
 
 /// Wrapper to generate error when we expected a channel and did not find one.
@@ -238,6 +313,9 @@ pub(crate) fn missing_channel(core_id: &ElementIDT, direction: &Directions) -> S
         "Could not retrieve {} channel for Core {}",
         direction, core_id
     )))
+    .with_code("missing_channel")
+    .with_element(ElementKind::Channel, core_id)
+    .with_direction(direction)
 }
 
 /// Wrapper to generate error when we expected source loads and did not find any.
@@ -246,6 +324,8 @@ pub(crate) fn missing_source_loads(core_id: &ElementIDT) -> SVGError {
         "Could not retrieve source loads for Core {}",
         core_id
     )))
+    .with_code("missing_source_loads")
+    .with_element(ElementKind::Core, core_id)
 }
 
 /// Wrapper to generate error when we expected a source channel load and did not find one.
@@ -254,4 +334,7 @@ pub(crate) fn missing_source_load(core_id: &ElementIDT, direction: &Directions)
         "Could not retrieve {} source channel load for Core {}",
         direction, core_id
     )))
+    .with_code("missing_source_load")
+    .with_element(ElementKind::Channel, core_id)
+    .with_direction(direction)
 }
@@ -2,41 +2,88 @@
 //!
 //! Provides utilities to generate and customise an SVG file rerpresenting a ManyCore system.
 
+mod animation;
+mod attribute_catalogue;
+mod auto_bounds;
+mod batch;
 mod clip_path;
+mod computed_attributes;
 mod connections_group;
 mod defs;
+mod diff;
 mod error;
+mod incremental;
 mod information_group;
 mod information_layer;
 mod marker;
+mod metadata;
+mod namespace;
 mod offsets;
+mod operations;
 mod partial_update;
 mod processing_group;
+mod provenance;
 mod render_settings;
+mod schema;
+#[cfg(feature = "server")]
+mod server;
 mod sinks_sources_layer;
+mod small_multiples;
+mod streaming;
 mod style;
 mod svg_conversions;
 mod tasks_group;
+#[cfg(test)]
+mod test_utils;
+mod validation;
 mod view_box;
-
+mod warnings;
+mod watch;
+
+use animation::AnimationGroup;
+pub use animation::AnimationSettings;
+pub use attribute_catalogue::{AttributeCatalogue, AttributeSummary, ValueType};
+use auto_bounds::resolve_auto_bounds;
+pub use auto_bounds::ResolvedBounds;
+pub use batch::{expand_inputs, render_batch, BatchFailure, BatchSettings, BatchSummary};
 pub use clip_path::*;
+use computed_attributes::validate_computed_attributes;
+pub use computed_attributes::ComputedAttribute;
 use connections_group::*;
 use defs::*;
+use diff::TaskMovesGroup;
 pub use error::*;
 use getset::{Getters, MutGetters, Setters};
+use incremental::{core_fingerprint, system_fingerprint, RenderState};
+pub use incremental::{IncrementalUpdate, IncrementalUpdateResult, InformationLayerPatch};
 use information_group::*;
 use information_layer::*;
 use marker::*;
+use metadata::Metadata;
+pub use metadata::MetadataContent;
+use namespace::IdNamespace;
 use offsets::*;
+pub use operations::UpdateOperation;
 use partial_update::PartialUpdate;
 use processing_group::*;
 pub use render_settings::*;
+pub use schema::{base_configuration_schema, configuration_schema};
+#[cfg(feature = "server")]
+pub use server::RenderServer;
 use sinks_sources_layer::SinksSourcesGroup;
+pub use small_multiples::{SmallMultiples, SmallMultiplesPanel};
 use tasks_group::{missing_task, TasksGroup};
+pub use validation::{Diagnostic, Severity};
 pub use view_box::*;
+use warnings::invalid_special_key;
+pub use warnings::{ElementKind, Warning};
+pub use watch::SVGWatcher;
+
+use std::collections::BTreeMap;
 
 use manycore_parser::{
-    ManycoreSystem, SystemDimensionsT, WithID, BORDER_ROUTERS_KEY, ROUTING_KEY, TASK_COST_KEY,
+    ManycoreSystem, RoutingMap, SystemDimensionsT, WithID, BORDER_ROUTERS_KEY, ROUTING_KEY,
+    TASK_COST_KEY,
 };
 
 use serde::Serialize;
@@ -49,13 +96,14 @@ pub type FontSizeT = f32;
 
 pub(crate) const UNSUPPORTED_PLATFORM: &'static str =
     "manycore_svg supports only 64-bit platforms.";
+pub(crate) static MAIN_GROUP_ID: &'static str = "mainGroup";
 
 /// Object representation of the [`SVG`] main group. Everything goes in here.
 /// Cores, (border) routers, channels and information are all inner groups of this group.
 #[derive(Serialize, Setters)]
 struct Root {
     #[serde(rename = "@id")]
-    id: &'static str,
+    id: String,
     #[serde(rename = "g")]
     processing_group: ProcessingParentGroup,
     #[serde(rename = "g")]
@@ -66,6 +114,10 @@ struct Root {
     sinks_sources_group: SinksSourcesGroup,
     #[serde(rename = "g")]
     tasks_group: TasksGroup,
+    #[serde(rename = "g", skip_serializing_if = "Option::is_none")]
+    task_moves_group: Option<TaskMovesGroup>,
+    #[serde(rename = "g", skip_serializing_if = "Option::is_none")]
+    animation_group: Option<AnimationGroup>,
 }
 
 /// An Object representation of the [`ViewBox`] top left coordinate.
@@ -77,46 +129,35 @@ struct TopLeft {
     y: CoordinateT,
 }
 
-/// Object representation of the generated SVGs.
-#[derive(Serialize, Getters, MutGetters)]
-#[serde(rename = "svg")]
+/// Object representation of the generated SVGs. Serialised as an `<svg>` element, see `svg_conversions.rs`.
+#[derive(Getters, MutGetters)]
 pub struct SVG {
-    #[serde(rename = "@xmlns:svg")]
     xmlns_svg: &'static str,
-    #[serde(rename = "@xmlns")]
     xmlns: &'static str,
-    #[serde(rename = "@preserveAspectRation")]
     preserve_aspect_ratio: &'static str,
-    #[serde(rename = "@class")]
     class: &'static str,
-    #[serde(rename = "@viewBox")]
     #[getset(get = "pub", get_mut = "pub")]
     view_box: ViewBox,
+    metadata: Metadata,
     defs: Defs,
     style: Style,
-    #[serde(rename = "g")]
     #[getset(get_mut = "pub")]
     root: Root,
-    #[serde(skip)]
     rows: SystemDimensionsT,
-    // #[serde(skip)]
     // columns: u8,
-    #[serde(skip)]
     #[getset(get = "pub")]
     width: CoordinateT,
-    #[serde(skip)]
     #[getset(get = "pub")]
     height: CoordinateT,
-    #[serde(skip)]
     top_left: TopLeft,
-    #[serde(skip)]
     base_view_box: ViewBox,
-    #[serde(skip)]
     borders_view_box: ViewBox,
-    #[serde(skip)]
     base_configuration: BaseConfiguration,
-    #[serde(skip)]
     processed_base_configuration: ProcessedBaseConfiguration,
+    namespace: IdNamespace,
+    render_state: Option<RenderState>,
+    /// Whether the [`SVG`] was rebuilt from a document and still needs regenerating from its [`ManycoreSystem`].
+    restored: bool,
 }
 
 /// This struct is provided as a result of requesting an [`SVG`] update based on a particular [`Configuration`].
@@ -126,30 +167,41 @@ pub struct SVG {
 #[serde(rename_all = "camelCase")]
 pub struct UpdateResult {
     style: String,
+    /// Class of every core and router whose fill changed since the previous update, keyed by element ID.
+    fill_classes: BTreeMap<String, String>,
     information_group: String,
     tasks_group: String,
     view_box: String,
+    /// The SVG `<metadata>` block, unless omitted.
+    metadata: Option<String>,
     svg: Option<String>,
+    /// Bounds derived for every field configured with [`AutoBounds`].
+    resolved_bounds: ResolvedBounds,
+    /// Everything that could not be rendered, and why.
+    warnings: Vec<Warning>,
 }
 
 /// Error thrown when we can't get to the requested processing group.
 /// Realistically, it should never happen, unless an invalid [`ManycoreSystem`] is provided.
 /// However, the manycore_parser library should guard against this.
 fn no_processing_group(index: usize) -> SVGError {
-    SVGError::new(SVGErrorKind::GenerationError(format!("Could not retrieve SVG group for core with ID {}. Something weent wrong generating the SVG, please try again.", index)))
+    SVGError::new(SVGErrorKind::GenerationError(format!("Could not retrieve SVG group for core with ID {}. Something weent wrong generating the SVG, please try again.", index))).with_code("missing_processing_group")
 }
 
 impl SVG {
     /// Creates a new [`SVG`] instance with the given parameters.
     fn new(
-        manycore: &ManycoreSystem,
+        metadata: Metadata,
         width: CoordinateT,
         height: CoordinateT,
         top_left: TopLeft,
         base_configuration: BaseConfiguration,
+        namespace: IdNamespace,
     ) -> Self {
         let view_box = ViewBox::new(width, height, &top_left);
-        let number_of_cores = manycore.cores().list().len();
+        let number_of_cores = metadata.number_of_cores();
+        let rows = metadata.rows();
+        let columns = metadata.columns();
 
         Self {
             width,
@@ -159,23 +211,29 @@ impl SVG {
             preserve_aspect_ratio: "xMidYMid meet",
             class: "mx-auto",
             view_box,
-            defs: Defs::new(&number_of_cores),
-            style: Style::default(),
+            metadata,
+            defs: Defs::new(&namespace),
+            style: Style::new(&namespace),
             root: Root {
-                id: "mainGroup",
-                processing_group: ProcessingParentGroup::new(&number_of_cores),
-                connections_group: ConnectionsParentGroup::default(),
-                information_group: InformationGroup::new(&number_of_cores),
-                sinks_sources_group: SinksSourcesGroup::new(manycore.rows(), manycore.columns()),
-                tasks_group: TasksGroup::new(),
+                id: namespace.id(MAIN_GROUP_ID),
+                processing_group: ProcessingParentGroup::new(&number_of_cores, &namespace),
+                connections_group: ConnectionsParentGroup::new(&namespace),
+                information_group: InformationGroup::new(&number_of_cores, &namespace),
+                sinks_sources_group: SinksSourcesGroup::new(&rows, &columns, &namespace),
+                tasks_group: TasksGroup::new(&namespace),
+                task_moves_group: None,
+                animation_group: None,
             },
-            rows: *manycore.rows(),
+            rows,
             // columns,
             top_left,
             base_view_box: view_box,
             borders_view_box: view_box,
             base_configuration,
             processed_base_configuration: ProcessedBaseConfiguration::from(&base_configuration),
+            namespace,
+            render_state: None,
+            restored: false,
         }
     }
 
@@ -193,6 +251,59 @@ impl SVG {
         self.height = self.height.saturating_add(bottom);
     }
 
+    /// Applies the fills requested by an [`InformationLayer`] to the matching [`ProcessingGroup`].
+    /// Fills are applied through classes shared by all elements with the same colour.
+    fn apply_fill_classes(
+        &mut self,
+        index: usize,
+        information_layer: &InformationLayer,
+        fill_classes: &mut BTreeMap<String, String>,
+    ) -> Result<(), SVGError> {
+        let core_class = information_layer
+            .core_fill()
+            .map(|fill| self.style.fill_class(fill, &self.namespace));
+        let router_class = information_layer
+            .router_fill()
+            .map(|fill| self.style.fill_class(fill, &self.namespace));
+
+        self.root
+            .processing_group
+            .g_mut()
+            .get_mut(index)
+            .ok_or(no_processing_group(index))?
+            .set_fill_classes(core_class, router_class, fill_classes);
+
+        Ok(())
+    }
+
+    /// Removes the routing request from a [`Configuration`] and computes the routing, if requested.
+    fn take_routing(
+        manycore: &mut ManycoreSystem,
+        configuration: &mut Configuration,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<(Option<RoutingMap>, Option<RoutingConfiguration>), SVGError> {
+        match configuration.channel_config_mut().remove(ROUTING_KEY) {
+            Some(configuration) => match configuration {
+                FieldConfiguration::Routing {
+                    configuration: routing_configuration,
+                } => Ok((
+                    Some(manycore.route(routing_configuration.algorithm())?),
+                    Some(routing_configuration),
+                )),
+                // Invalid configuration option
+                invalid => {
+                    warnings.push(Warning::configuration(
+                        ROUTING_KEY,
+                        invalid_special_key("Routing", &invalid),
+                    ));
+
+                    Ok((None, None))
+                }
+            },
+            None => Ok((None, None)),
+        }
+    }
+
     /// Generates an [`UpdateResult`] based on a provided [`Configuration`], a possibly updated [`BaseConfiguration`] and a reference [`ManycoreSystem`].
     pub fn update_configurable_information(
         &mut self,
@@ -200,32 +311,52 @@ impl SVG {
         configuration: &mut Configuration,
         base_configuration: &BaseConfiguration,
     ) -> Result<UpdateResult, SVGError> {
+        validate_computed_attributes(configuration, manycore)?;
+
+        // Rebuilt SVGs have no elements, regenerate them from the system they were generated from
+        if self.restored {
+            self.metadata.check_system(manycore)?;
+        }
+
         // Did the base configuration change? If so, we need to regenerate the whole SVG
         let has_new_base_config = *base_configuration != self.base_configuration;
-        if has_new_base_config {
-            *self = SVG::try_from_manycore_with_base_config(manycore, base_configuration)?;
+        let regenerated = has_new_base_config || self.restored;
+        if regenerated {
+            let metadata = self.metadata.clone();
+            *self = SVG::try_from_manycore_with_base_config(
+                manycore,
+                base_configuration,
+                self.namespace.clone(),
+            )?;
+            self.metadata.inherit(&metadata);
         }
 
+        self.metadata.provenance_mut().record(configuration)?;
+
+        // Fingerprint before any key is consumed, so incremental updates can compare against it
+        let system = system_fingerprint(manycore, configuration)?;
+
         let not_empty_configuration = !configuration.core_config().is_empty()
             || !configuration.router_config().is_empty()
             || !configuration.channel_config().is_empty()
             || !configuration.core_fills().is_empty()
             || !configuration.router_fills().is_empty();
 
+        let mut warnings = Vec::new();
+
         // Compute routing if requested
-        let (links_with_load, routing_configuration) =
-            match configuration.channel_config_mut().remove(ROUTING_KEY) {
-                Some(configuration) => match configuration {
-                    FieldConfiguration::Routing {
-                        configuration: routing_configuration,
-                    } => (
-                        Some(manycore.route(routing_configuration.algorithm())?),
-                        Some(routing_configuration),
-                    ),
-                    _ => (None, None), // Invalid configuration option
-                },
-                None => (None, None),
-            };
+        let (links_with_load, mut routing_configuration) =
+            SVG::take_routing(manycore, configuration, &mut warnings)?;
+        self.metadata
+            .provenance_mut()
+            .record_routing(routing_configuration.as_ref());
+
+        let resolved_bounds = resolve_auto_bounds(
+            manycore,
+            configuration,
+            routing_configuration.as_mut(),
+            links_with_load.as_ref(),
+        );
 
         // Clear information groups. Clear will keep memory allocated, hopefully less heap allocation penalties.
         self.root.information_group.groups_mut().clear();
@@ -243,7 +374,7 @@ impl SVG {
                     value: show_border_routers,
                 } => {
                     if show_border_routers {
-                        self.style = Style::base(); // CSS
+                        self.style = Style::base(&self.namespace); // CSS
 
                         // Expand viewBox for edges
                         let ViewBox {
@@ -254,61 +385,72 @@ impl SVG {
                         } = self.borders_view_box;
                         self.view_box.swap(x, y, width, height);
                     } else {
-                        self.style = Style::default(); // CSS
+                        self.style = Style::new(&self.namespace); // CSS
                     }
                 }
-                _ => {
-                    self.style = Style::default(); // CSS
+                invalid => {
+                    warnings.push(Warning::configuration(
+                        BORDER_ROUTERS_KEY,
+                        invalid_special_key("Boolean", &invalid),
+                    ));
+                    self.style = Style::new(&self.namespace); // CSS
                 }
             }
         } else {
-            self.style = Style::default(); // CSS
+            self.style = Style::new(&self.namespace); // CSS
         }
 
         let mut offsets = Offsets::default();
+        // Offsets required by each information layer, kept for incremental updates
+        let mut layer_offsets = vec![Offsets::default(); manycore.cores().list().len()];
 
         // Should we update tasks too?
-        let toggle_task = configuration
-            .core_config_mut()
-            .remove(TASK_COST_KEY)
-            .map_or(
-                !self.root.tasks_group.is_base(),
-                |field_config| match field_config {
-                    FieldConfiguration::Boolean { value } => {
-                        value == self.root.tasks_group.is_base()
-                    }
-                    _ => !self.root.tasks_group.is_base(),
-                },
-            );
+        let toggle_task = self.root.tasks_group.should_toggle(
+            configuration.core_config_mut().remove(TASK_COST_KEY),
+            &mut warnings,
+        );
+
+        // Elements whose fill class changed, element ID -> class
+        let mut fill_classes = BTreeMap::new();
 
         // If there is something to do...
         if not_empty_configuration || toggle_task {
             // Go through everything and apply changes
             for (i, core) in manycore.cores().list().iter().enumerate() {
-                let processing_group = self
-                    .root
-                    .processing_group
-                    .g()
-                    .get(i)
-                    .ok_or(no_processing_group(i))?;
-
                 // Compute all requested attributes at information layer
                 if not_empty_configuration {
+                    let processing_group = self
+                        .root
+                        .processing_group
+                        .g()
+                        .get(i)
+                        .ok_or(no_processing_group(i))?;
+
+                    let mut core_offsets = Offsets::default();
+                    let information_layer = InformationLayer::new(
+                        self.rows,
+                        configuration,
+                        core,
+                        links_with_load.as_ref(),
+                        processing_group,
+                        &self.root.connections_group,
+                        routing_configuration.as_ref(),
+                        &mut core_offsets,
+                        &self.namespace,
+                        &self.processed_base_configuration,
+                        &mut warnings,
+                    )?;
+                    offsets.update(core_offsets);
+                    if let Some(layer_offsets) = layer_offsets.get_mut(i) {
+                        *layer_offsets = core_offsets;
+                    }
+
+                    self.apply_fill_classes(i, &information_layer, &mut fill_classes)?;
+
                     self.root
                         .information_group
                         .groups_mut()
-                        .push(InformationLayer::new(
-                            self.rows,
-                            configuration,
-                            core,
-                            links_with_load.as_ref(),
-                            self.style.css_mut(),
-                            processing_group,
-                            &self.root.connections_group,
-                            routing_configuration.as_ref(),
-                            &mut offsets,
-                            &self.processed_base_configuration,
-                        )?);
+                        .push(information_layer);
                 }
                 // Recalculate tasks
                 if toggle_task {
@@ -320,6 +462,13 @@ impl SVG {
                                 .get(task_id)
                                 .ok_or_else(|| missing_task(core.id(), task_id))?;
 
+                            let processing_group = self
+                                .root
+                                .processing_group
+                                .g()
+                                .get(i)
+                                .ok_or(no_processing_group(i))?;
+
                             let update_task = self.root.tasks_group.toggle_task(
                                 allocated_task.id(),
                                 processing_group,
@@ -340,6 +489,13 @@ impl SVG {
             }
         }
 
+        // Nothing is filled without information, restore the base fill
+        if !not_empty_configuration {
+            for processing_group in self.root.processing_group.g_mut() {
+                processing_group.set_fill_classes(None, None, &mut fill_classes);
+            }
+        }
+
         // We need to do this separately here because the base viewBox might still need extending.
         // E.g. Border-routers -> off but no task toggle
         if !toggle_task {
@@ -348,20 +504,37 @@ impl SVG {
             });
         }
 
+        self.render_state = Some(RenderState::new(
+            system,
+            manycore
+                .cores()
+                .list()
+                .iter()
+                .map(|core| core_fingerprint(core, configuration, links_with_load.as_ref()))
+                .collect(),
+            layer_offsets,
+            self.view_box,
+            resolved_bounds.clone(),
+        ));
+
         // Extend viewBox if required
         self.view_box.fit_offsets(&offsets);
 
         Ok(UpdateResult {
             style: self.style.css().clone(),
+            fill_classes,
             information_group: self.root.information_group.update_string()?,
             tasks_group: self.root.tasks_group.update_string()?,
             view_box: String::from(&self.view_box),
+            metadata: self.metadata.update_string()?,
             // Include whole SVG if it's been updated. It will inherrently contain the updated data above
-            svg: if has_new_base_config {
+            svg: if regenerated {
                 Some(quick_xml::se::to_string(self)?)
             } else {
                 None
             },
+            resolved_bounds,
+            warnings,
         })
     }
 
@@ -369,7 +542,7 @@ impl SVG {
     pub fn add_freeform_clip_path(&mut self, polygon_points: String) {
         self.defs
             .clip_paths_mut()
-            .push(ClipPath::new(polygon_points));
+            .push(ClipPath::freeform(polygon_points, &self.namespace));
     }
 
     /// Removes FreeForm exporting [`ClipPath`] from the [`SVG`]'s `<defs>`.
@@ -384,16 +557,21 @@ mod tests {
 
     use manycore_parser::ManycoreSystem;
 
-    use super::SVG;
+    use super::{
+        style::BASE_FILL_CLASS_NAME, test_utils::read_configuration, BaseConfiguration,
+        Configuration, MetadataContent, SVG,
+    };
 
     #[test]
     fn can_convert_from() {
         let manycore: ManycoreSystem = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
             .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
 
-        let svg: SVG = (&manycore)
+        let mut svg: SVG = (&manycore)
             .try_into()
             .expect("Could not convert Manycorer to SVG.");
+        // Provenance includes the render time
+        svg.set_metadata_content(MetadataContent::Layout);
 
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
@@ -405,4 +583,100 @@ mod tests {
         #[cfg(not(feature = "print"))]
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn can_prefix_ids() {
+        let mut manycore: ManycoreSystem =
+            ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+                .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+        let base_configuration = BaseConfiguration::default();
+        let mut svg =
+            SVG::try_from_manycore_with_id_prefix(&manycore, &base_configuration, "first-")
+                .expect("Could not convert Manycore to SVG.");
+
+        let mut configuration = read_configuration("tests/conf2.json");
+
+        let update = svg
+            .update_configurable_information(&mut manycore, &mut configuration, &base_configuration)
+            .expect("Could not update SVG.");
+        svg.add_freeform_clip_path("0 0, 1 1, 1 0".to_string());
+
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        assert_eq!(svg.id_prefix(), "first-");
+        assert_eq!(
+            res.matches(" id=\"").count(),
+            res.matches(" id=\"first-").count()
+        );
+        assert_eq!(
+            res.matches("url(#").count(),
+            res.matches("url(#first-").count()
+        );
+        assert!(res.contains("id=\"first-crop\""));
+        // Every generated CSS rule targets a prefixed ID
+        assert!(update.style.contains("\n#first-"));
+        assert_eq!(
+            update.style.matches("\n#").count(),
+            update.style.matches("\n#first-").count()
+        );
+        assert!(update.style.contains("#first-mainGroup .baseFill"));
+    }
+
+    #[test]
+    fn rejects_invalid_id_prefix() {
+        let manycore: ManycoreSystem = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+        assert!(SVG::try_from_manycore_with_id_prefix(
+            &manycore,
+            &BaseConfiguration::default(),
+            "1 a"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reports_only_changed_fill_classes() {
+        let mut manycore: ManycoreSystem =
+            ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+                .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let base_configuration = BaseConfiguration::default();
+        let mut svg: SVG = (&manycore)
+            .try_into()
+            .expect("Could not convert Manycore to SVG.");
+
+        let first = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut read_configuration("tests/conf3.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert_eq!(first.fill_classes.len(), 18);
+        assert_eq!(first.fill_classes.get("c0"), Some(&"fill0".to_string()));
+        assert!(!first.style.contains("#c0"));
+
+        let unchanged = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut read_configuration("tests/conf3.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert!(unchanged.fill_classes.is_empty());
+
+        let cleared = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut Configuration::default(),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert_eq!(cleared.fill_classes.len(), 18);
+        assert!(cleared
+            .fill_classes
+            .values()
+            .all(|class| class == BASE_FILL_CLASS_NAME));
+    }
 }
@@ -3,10 +3,10 @@
 use const_format::concatcp;
 use serde::Serialize;
 
-use crate::{CommonAttributes, CoordinateT};
+use crate::{namespace::IdNamespace, CommonAttributes, CoordinateT};
 
 pub(crate) static MARKER_PATH: &str = "M0,0 M0,0 V14 L14,7 Z";
-pub(crate) static MARKER_REFERENCE: &str = "url(#arrowHead)";
+pub(crate) static MARKER_ID: &str = "arrowHead";
 pub(crate) const MARKER_HEIGHT: CoordinateT = 14;
 static MARKER_DIMEN: &'static str = concatcp!(MARKER_HEIGHT);
 static MARKER_REF_Y: &'static str = concatcp!(MARKER_HEIGHT.saturating_div(2));
@@ -36,7 +36,7 @@ impl Default for MarkerPath {
 #[derive(Serialize)]
 pub(crate) struct Marker {
     #[serde(rename = "@id")]
-    id: &'static str,
+    id: String,
     #[serde(rename = "@orient")]
     orient: &'static str,
     #[serde(rename = "@markerWidth")]
@@ -48,10 +48,11 @@ pub(crate) struct Marker {
     path: MarkerPath,
 }
 
-impl Default for Marker {
-    fn default() -> Self {
+impl Marker {
+    /// Generates a new [`Marker`] instance whose id lives in the given [`IdNamespace`].
+    pub(crate) fn new(namespace: &IdNamespace) -> Self {
         Self {
-            id: "arrowHead",
+            id: namespace.id(MARKER_ID),
             orient: "auto",
             marker_width: MARKER_DIMEN,
             marker_height: MARKER_DIMEN,
@@ -0,0 +1,477 @@
+use std::{
+    env, fs,
+    path::Path,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use manycore_parser::{ElementIDT, ManycoreSystem, SystemDimensionsT, WithID};
+use quick_xml::DeError;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::{
+    namespace::IdNamespace, provenance::Provenance, BaseConfiguration, FontSizeT, SVGError,
+    SVGErrorKind, SVG, UNSUPPORTED_PLATFORM,
+};
+
+/// Namespace of the elements manycore_svg adds to `<metadata>`.
+static METADATA_NAMESPACE: &str = "https://github.com/ManyCore-Visualiser/manycore_svg";
+/// Makes temporary system file names unique, so concurrent rebuilds don't overwrite each other's file.
+static SYSTEM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// What an [`SVG`] needs to be rebuilt from its document, see [`SVG::from_svg_str`].
+/// * `rows`, `columns`: Grid dimensions.
+/// * `id_prefix`: Prefix of every element ID.
+/// * `attribute_font_size`, `task_font_size`: The [`BaseConfiguration`] the document was generated with.
+/// * `cores`: Space separated core IDs, in grid order. Routers share their core's ID.
+/// * `source_system`: XML of the [`ManycoreSystem`] on a single line, if its file was recorded with
+///   [`SVG::set_source`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct SystemMetadata {
+    #[serde(rename = "@xmlns", default)]
+    xmlns: String,
+    #[serde(rename = "@rows")]
+    rows: SystemDimensionsT,
+    #[serde(rename = "@columns")]
+    columns: SystemDimensionsT,
+    #[serde(rename = "@idPrefix", default)]
+    id_prefix: String,
+    #[serde(rename = "@attributeFontSize")]
+    attribute_font_size: FontSizeT,
+    #[serde(rename = "@taskFontSize")]
+    task_font_size: FontSizeT,
+    #[serde(rename = "@cores")]
+    cores: String,
+    #[serde(rename = "system", default, skip_serializing_if = "Option::is_none")]
+    source_system: Option<String>,
+}
+
+/// What the `<metadata>` block of a generated document includes.
+/// * [`Full`][`MetadataContent::Full`]: Layout and provenance: manycore_svg version, render time, source file,
+///   configurations and routing algorithm, as RDF with Dublin Core terms. This is the default.
+/// * [`Reproducible`][`MetadataContent::Reproducible`]: [`Full`][`MetadataContent::Full`] without the render time
+///   (`<dc:date>`), so that the document only changes with its inputs.
+/// * [`Layout`][`MetadataContent::Layout`]: Only what [`SVG::from_svg_str`] needs. Output is reproducible.
+/// * [`Omitted`][`MetadataContent::Omitted`]: No `<metadata>` block, for minimal output. The document can't be
+///   rebuilt with [`SVG::from_svg_str`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataContent {
+    #[default]
+    Full,
+    Reproducible,
+    Layout,
+    Omitted,
+}
+
+/// Object representation of the SVG `<metadata>` block.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Metadata {
+    content: MetadataContent,
+    system: SystemMetadata,
+    provenance: Provenance,
+}
+
+/// The `<metadata>` block of a generated document, as read back.
+#[derive(Deserialize)]
+struct StoredMetadata {
+    #[serde(rename = "manycore")]
+    system: SystemMetadata,
+}
+
+/// The parts of a generated document needed to rebuild an [`SVG`]. Everything else is ignored.
+#[derive(Deserialize)]
+struct Document {
+    metadata: Option<StoredMetadata>,
+}
+
+impl Serialize for Metadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("metadata", 2)?;
+        state.serialize_field("manycore", &self.system)?;
+        match self.content {
+            MetadataContent::Full => state.serialize_field("rdf:RDF", &self.provenance)?,
+            MetadataContent::Reproducible => {
+                state.serialize_field("rdf:RDF", &self.provenance.undated())?
+            }
+            MetadataContent::Layout | MetadataContent::Omitted => {}
+        }
+        state.end()
+    }
+}
+
+impl Metadata {
+    /// Describes the [`SVG`] generated from the given [`ManycoreSystem`].
+    pub(crate) fn new(
+        manycore: &ManycoreSystem,
+        base_configuration: &BaseConfiguration,
+        namespace: &IdNamespace,
+    ) -> Self {
+        Self {
+            content: MetadataContent::default(),
+            provenance: Provenance::new(base_configuration),
+            system: SystemMetadata {
+                xmlns: METADATA_NAMESPACE.to_string(),
+                rows: *manycore.rows(),
+                columns: *manycore.columns(),
+                id_prefix: namespace.prefix().to_string(),
+                attribute_font_size: *base_configuration.attribute_font_size(),
+                task_font_size: *base_configuration.task_font_size(),
+                cores: manycore
+                    .cores()
+                    .list()
+                    .iter()
+                    .map(|core| core.id().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                source_system: None,
+            },
+        }
+    }
+
+    /// Metadata read back from a document. Provenance starts afresh.
+    fn restored(system: SystemMetadata) -> Self {
+        Self {
+            content: MetadataContent::default(),
+            provenance: Provenance::new(&BaseConfiguration::new(
+                system.attribute_font_size,
+                system.task_font_size,
+            )),
+            system,
+        }
+    }
+
+    /// Keeps the settings and source of the metadata of a previous generation.
+    pub(crate) fn inherit(&mut self, previous: &Metadata) {
+        self.content = previous.content;
+        self.system.source_system = previous.system.source_system.clone();
+        self.provenance.inherit_source(&previous.provenance);
+    }
+
+    /// Whether the `<metadata>` block is left out of the document.
+    pub(crate) fn is_omitted(&self) -> bool {
+        self.content == MetadataContent::Omitted
+    }
+
+    /// Serialises the `<metadata>` block for partial updates. Omitted blocks are not sent.
+    pub(crate) fn update_string(&self) -> Result<Option<String>, DeError> {
+        if self.is_omitted() {
+            return Ok(None);
+        }
+
+        Ok(Some(quick_xml::se::to_string_with_root("metadata", self)?))
+    }
+
+    pub(crate) fn provenance_mut(&mut self) -> &mut Provenance {
+        &mut self.provenance
+    }
+
+    pub(crate) fn rows(&self) -> SystemDimensionsT {
+        self.system.rows
+    }
+
+    pub(crate) fn columns(&self) -> SystemDimensionsT {
+        self.system.columns
+    }
+
+    pub(crate) fn number_of_cores(&self) -> usize {
+        self.system.cores.split_whitespace().count()
+    }
+
+    fn core_ids(&self) -> Result<Vec<ElementIDT>, SVGError> {
+        self.system
+            .cores
+            .split_whitespace()
+            .map(|id| {
+                id.parse().map_err(|_| {
+                    SVGError::new(SVGErrorKind::DataConversionError(format!(
+                        "Invalid core ID \"{id}\" in the document's metadata."
+                    )))
+                    .with_code("invalid_metadata_core_id")
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that a [`ManycoreSystem`] is the one the document was generated from: same grid and core IDs.
+    pub(crate) fn check_system(&self, manycore: &ManycoreSystem) -> Result<(), SVGError> {
+        let expected = Metadata::new(
+            manycore,
+            &BaseConfiguration::default(),
+            &IdNamespace::default(),
+        );
+
+        if self.system.rows != expected.system.rows
+            || self.system.columns != expected.system.columns
+            || self.core_ids()? != expected.core_ids()?
+        {
+            return Err(SVGError::new(SVGErrorKind::ManycoreMismatch(format!(
+                "The document was generated from a {}x{} system with cores [{}], but a {}x{} system with cores [{}] was provided.",
+                self.system.rows,
+                self.system.columns,
+                self.system.cores,
+                expected.system.rows,
+                expected.system.columns,
+                expected.system.cores
+            )))
+            .with_code("system_mismatch"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the [`ManycoreSystem`] embedded in a document. The parser only reads files, so the XML goes through a
+/// temporary one.
+fn parse_embedded_system(xml: &str) -> Result<ManycoreSystem, SVGError> {
+    let path = env::temp_dir().join(format!(
+        "manycore_svg_system_{}_{}.xml",
+        process::id(),
+        SYSTEM_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&path, xml)?;
+
+    let manycore = path
+        .to_str()
+        .ok_or_else(|| {
+            SVGError::new(SVGErrorKind::IoError(format!(
+                "Invalid temporary path {}.",
+                path.display()
+            )))
+            .with_code("invalid_temporary_path")
+        })
+        .and_then(|path| Ok(ManycoreSystem::parse_file(path)?));
+    let _ = fs::remove_file(&path);
+
+    manycore
+}
+
+impl SVG {
+    /// Sets what the document's `<metadata>` block includes, see [`MetadataContent`].
+    /// The setting is kept when the [`SVG`] is regenerated, e.g. because of a new [`BaseConfiguration`].
+    pub fn set_metadata_content(&mut self, content: MetadataContent) {
+        self.metadata.content = content;
+    }
+
+    /// Records the [`ManycoreSystem`] XML file the [`SVG`] was generated from in its provenance: the file name and a
+    /// hash of its contents. The XML itself is embedded in the document's `<metadata>`, so that
+    /// [`SVG::from_svg_str`] can rebuild the system without the file.
+    pub fn set_source(&mut self, path: &Path) -> Result<(), SVGError> {
+        let contents = fs::read_to_string(path)?;
+
+        self.metadata
+            .provenance
+            .set_source(path, contents.as_bytes());
+        // Line breaks are only whitespace in XML, the streamed document keeps the text on one line
+        self.metadata.system.source_system = Some(contents.replace(['\r', '\n'], " "));
+
+        Ok(())
+    }
+
+    /// Rebuilds an [`SVG`] from a document it generated, using the document's `<metadata>`.
+    ///
+    /// Documents whose source was recorded with [`SVG::set_source`] embed their [`ManycoreSystem`], which is
+    /// returned alongside the [`SVG`]. Otherwise, `None` is returned and the original [`ManycoreSystem`] is required.
+    ///
+    /// The rebuilt [`SVG`] only holds the document's size, element IDs and [`BaseConfiguration`], it has no elements
+    /// and must not be serialised. The first call to
+    /// [`update_configurable_information`](SVG::update_configurable_information) checks the provided
+    /// [`ManycoreSystem`] against the document's grid and core IDs, regenerates every element from it and returns
+    /// the whole document in its [`UpdateResult`](crate::UpdateResult). Later updates are applied as usual.
+    pub fn from_svg_str(svg: &str) -> Result<(SVG, Option<ManycoreSystem>), SVGError> {
+        let document: Document = quick_xml::de::from_str(svg)?;
+        let metadata = document
+            .metadata
+            .map(|stored| Metadata::restored(stored.system))
+            .ok_or_else(|| {
+                SVGError::new(SVGErrorKind::DataConversionError(
+                    "The document has no manycore_svg metadata. Was it generated by manycore_svg?"
+                        .to_string(),
+                ))
+                .with_code("missing_metadata")
+            })?;
+
+        let core_ids = metadata.core_ids()?;
+        let columns = usize::try_from(metadata.columns()).expect(UNSUPPORTED_PLATFORM);
+        let rows = usize::try_from(metadata.rows()).expect(UNSUPPORTED_PLATFORM);
+        if columns == 0 || core_ids.len() != columns * rows {
+            return Err(SVGError::new(SVGErrorKind::DataConversionError(format!(
+                "The document's metadata lists {} cores for a {}x{} system.",
+                core_ids.len(),
+                rows,
+                columns
+            )))
+            .with_code("inconsistent_metadata"));
+        }
+
+        let base_configuration = BaseConfiguration::new(
+            metadata.system.attribute_font_size,
+            metadata.system.task_font_size,
+        );
+        let namespace = IdNamespace::try_from(metadata.system.id_prefix.as_str())?;
+
+        let manycore = match &metadata.system.source_system {
+            Some(xml) => {
+                let manycore = parse_embedded_system(xml)?;
+                metadata.check_system(&manycore)?;
+
+                Some(manycore)
+            }
+            None => None,
+        };
+
+        let mut ret = SVG::frame(metadata, base_configuration, namespace);
+        ret.restored = true;
+
+        Ok((ret, manycore))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use manycore_parser::ManycoreSystem;
+
+    use super::MetadataContent;
+    use crate::{
+        test_utils::read_configuration, BaseConfiguration, Configuration, SVGErrorKind, SVG,
+    };
+
+    #[test]
+    fn rebuilds_from_generated_svg() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let base_configuration = BaseConfiguration::default();
+
+        let mut svg =
+            SVG::try_from_manycore_with_id_prefix(&manycore, &base_configuration, "archived-")
+                .expect("Could not convert Manycore to SVG.");
+        svg.set_source(Path::new("tests/VisualiserOutput1.xml"))
+            .expect("Could not read source");
+        let document = String::try_from(&svg).expect("Could not convert from SVG to string");
+
+        // The document embeds its system, the original file is not needed
+        let (mut restored, archived) = SVG::from_svg_str(&document).expect("Could not rebuild SVG");
+        let mut archived = archived.expect("The document did not embed its system");
+        // Provenance differs between renders, the setting is kept when the SVG is completed
+        svg.set_metadata_content(MetadataContent::Layout);
+        restored.set_metadata_content(MetadataContent::Layout);
+        assert_eq!(restored.id_prefix(), "archived-");
+        assert_eq!(restored.width(), svg.width());
+        assert_eq!(restored.height(), svg.height());
+
+        let update = restored
+            .update_configurable_information(
+                &mut archived,
+                &mut read_configuration("tests/conf2.json"),
+                &base_configuration,
+            )
+            .expect("Could not update rebuilt SVG.");
+
+        let expected = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut read_configuration("tests/conf2.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        // The rebuilt SVG had no elements, the whole document is returned
+        assert!(expected.svg.is_none());
+        assert_eq!(
+            update.svg,
+            Some(String::try_from(&svg).expect("Could not convert from SVG to string"))
+        );
+        assert_eq!(
+            String::try_from(&restored).expect("Could not convert from SVG to string"),
+            String::try_from(&svg).expect("Could not convert from SVG to string")
+        );
+    }
+
+    #[test]
+    fn requires_system_without_source() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let document = String::try_from(
+            &SVG::try_from(&manycore).expect("Could not convert Manycore to SVG."),
+        )
+        .expect("Could not convert from SVG to string");
+
+        let (_, archived) = SVG::from_svg_str(&document).expect("Could not rebuild SVG");
+        assert!(archived.is_none());
+    }
+
+    #[test]
+    fn records_provenance() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration = read_configuration("tests/conf3.json");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert Manycore to SVG.");
+        svg.set_source(Path::new("tests/VisualiserOutput1.xml"))
+            .expect("Could not read source");
+        svg.update_configurable_information(
+            &mut manycore,
+            &mut configuration,
+            &BaseConfiguration::default(),
+        )
+        .expect("Could not update SVG.");
+
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert!(res.contains(&format!(
+            "<dc:creator>manycore_svg {}</dc:creator>",
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(res.contains("<dc:source>VisualiserOutput1.xml</dc:source>"));
+        assert!(res.contains("<mp:sourceHash>fnv1a64:"));
+        assert!(res.contains("<mp:routingAlgorithm>RowFirst</mp:routingAlgorithm>"));
+        assert!(res.contains("<mp:configuration>{\"version\":"));
+        assert!(res.contains("<mp:baseConfiguration>{\"attribute_font_size\":16"));
+        assert!(res.contains("<dc:date>"));
+
+        svg.set_metadata_content(MetadataContent::Reproducible);
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert!(!res.contains("<dc:date>") && res.contains("<dc:source>VisualiserOutput1.xml"));
+
+        svg.set_metadata_content(MetadataContent::Layout);
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert!(res.contains("<metadata>") && !res.contains("rdf:RDF"));
+
+        svg.set_metadata_content(MetadataContent::Omitted);
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert!(!res.contains("<metadata>"));
+    }
+
+    #[test]
+    fn rejects_mismatched_documents() {
+        assert!(matches!(
+            SVG::from_svg_str("<svg><g/></svg>")
+                .expect_err("Document without metadata was accepted")
+                .kind(),
+            SVGErrorKind::DataConversionError(_)
+        ));
+
+        let document = String::try_from(
+            &SVG::try_from(
+                &ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+                    .expect("Could not read input test file \"tests/VisualiserOutput1.xml\""),
+            )
+            .expect("Could not convert Manycore to SVG."),
+        )
+        .expect("Could not convert from SVG to string");
+        let (mut restored, _) = SVG::from_svg_str(
+            &document.replace("cores=\"0 1 2 3 4 5 6 7 8\"", "cores=\"0 1 2 3 4 5 6 7 9\""),
+        )
+        .expect("Could not rebuild SVG");
+
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let error = restored
+            .update_configurable_information(
+                &mut manycore,
+                &mut Configuration::default(),
+                &BaseConfiguration::default(),
+            )
+            .expect_err("Mismatched system was accepted");
+        assert!(matches!(error.kind(), SVGErrorKind::ManycoreMismatch(_)));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{generation_error, SVGError};
+
+/// Helper to namespace SVG element IDs and any reference to them (`url(#...)`, `href`, CSS selectors).
+/// This allows multiple [`SVG`](crate::SVG)s to live in the same document. An empty prefix leaves IDs untouched.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct IdNamespace {
+    prefix: String,
+}
+
+impl IdNamespace {
+    /// Generates a new [`IdNamespace`] instance with the given prefix.
+    pub(crate) fn new(prefix: String) -> Self {
+        Self { prefix }
+    }
+
+    /// Returns the prefix applied to every ID.
+    pub(crate) fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns whether the [`IdNamespace`] leaves IDs untouched.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.prefix.is_empty()
+    }
+
+    /// Namespaces an element ID.
+    pub(crate) fn id(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
+    /// Generates a fragment identifier (`#id`) for an element ID. Also valid as CSS ID selector.
+    pub(crate) fn href(&self, id: &str) -> String {
+        format!("#{}{}", self.prefix, id)
+    }
+
+    /// Generates a `url(#id)` reference for an element ID.
+    pub(crate) fn url(&self, id: &str) -> String {
+        format!("url(#{}{})", self.prefix, id)
+    }
+}
+
+impl TryFrom<&str> for IdNamespace {
+    type Error = SVGError;
+
+    /// Validates a user provided prefix. Prefixed IDs must still be valid XML IDs and CSS identifiers,
+    /// hence the prefix must start with a letter (or `_`) and contain only ASCII alphanumerics, `-` or `_`.
+    fn try_from(prefix: &str) -> Result<Self, Self::Error> {
+        let valid_start = prefix
+            .chars()
+            .next()
+            .is_none_or(|c| c.is_ascii_alphabetic() || c == '_');
+        let valid_chars = prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if valid_start && valid_chars {
+            Ok(Self::new(prefix.to_string()))
+        } else {
+            Err(generation_error(format!(
+                "Invalid ID prefix \"{prefix}\": it must start with a letter or '_' and only contain ASCII letters, digits, '-' or '_'."
+            ))
+            .with_code("invalid_id_prefix"))
+        }
+    }
+}
@@ -101,6 +101,16 @@ impl Offsets {
         }
     }
 
+    /// Generates a copy of the [`Offsets`] instance moved by the given deltas.
+    pub(crate) fn translate(&self, delta_x: CoordinateT, delta_y: CoordinateT) -> Self {
+        Self {
+            left: self.left.saturating_add(delta_x),
+            top: self.top.saturating_add(delta_y),
+            right: self.right.saturating_add(delta_x),
+            bottom: self.bottom.saturating_add(delta_y),
+        }
+    }
+
     /// Utility to generate viewBox offset from a [`Task`] instance.
     pub(crate) fn from_task(value: &Task) -> Self {
         let left = value.rect().x();
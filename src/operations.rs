@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+
+use manycore_parser::ManycoreSystem;
+use quick_xml::DeError;
+use serde::Serialize;
+
+use crate::{BaseConfiguration, Configuration, SVGError, SVG};
+
+/// A single DOM mutation required to bring a rendered [`SVG`] up to date.
+/// Elements are identified by their ID, children by their index within the parent.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum UpdateOperation {
+    /// Sets attribute `name` of the element with the given `id` to `value`.
+    SetAttribute {
+        id: String,
+        name: &'static str,
+        value: String,
+    },
+    /// Inserts `content` as the `index`-th child of the element with ID `parent`.
+    AddNode {
+        parent: String,
+        index: usize,
+        content: String,
+    },
+    /// Removes the `index`-th child of the element with ID `parent`.
+    RemoveNode { parent: String, index: usize },
+    /// Replaces the `index`-th child of the element with ID `parent` with `content`.
+    ReplaceNode {
+        parent: String,
+        index: usize,
+        content: String,
+    },
+    /// Sets the text content of the element with the given `id`.
+    SetText { id: String, text: String },
+    /// Replaces the text of the SVG `<style>` element.
+    ReplaceStyle { css: String },
+    /// Replaces the SVG `<metadata>` element with `content`.
+    ReplaceMetadata { content: String },
+    /// Sets the viewBox of the SVG.
+    SetViewBox { view_box: String },
+    /// The whole SVG was regenerated and must be replaced.
+    ReplaceDocument { svg: String },
+}
+
+/// Serialises every child of a group so that it can be compared against a later state.
+fn serialise_children<'a, T: Serialize + 'a>(
+    children: impl IntoIterator<Item = &'a T>,
+) -> Result<Vec<String>, DeError> {
+    children
+        .into_iter()
+        .map(|child| quick_xml::se::to_string_with_root("g", child))
+        .collect()
+}
+
+/// Generates the operations that turn the `old` children of `parent` into the `new` ones.
+/// Removals are listed last to first so that indices stay valid while applying them in order.
+fn diff_children(
+    parent: &String,
+    old: Vec<String>,
+    new: Vec<String>,
+    operations: &mut Vec<UpdateOperation>,
+) {
+    let common = old.len().min(new.len());
+
+    for (index, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+        if old != new {
+            operations.push(UpdateOperation::ReplaceNode {
+                parent: parent.clone(),
+                index,
+                content: new.clone(),
+            });
+        }
+    }
+
+    match old.len().cmp(&new.len()) {
+        Ordering::Less => {
+            for (index, content) in new.into_iter().enumerate().skip(common) {
+                operations.push(UpdateOperation::AddNode {
+                    parent: parent.clone(),
+                    index,
+                    content,
+                });
+            }
+        }
+        Ordering::Greater => {
+            for index in (common..old.len()).rev() {
+                operations.push(UpdateOperation::RemoveNode {
+                    parent: parent.clone(),
+                    index,
+                });
+            }
+        }
+        Ordering::Equal => {}
+    }
+}
+
+impl SVG {
+    /// Performs the same update as [`SVG::update_configurable_information`], but describes the result as a list of
+    /// [`UpdateOperation`]s instead of serialised groups. Only elements that actually changed are included.
+    pub fn update_operations(
+        &mut self,
+        manycore: &mut ManycoreSystem,
+        configuration: &mut Configuration,
+        base_configuration: &BaseConfiguration,
+    ) -> Result<Vec<UpdateOperation>, SVGError> {
+        // Current state
+        let old_information = self.root.information_group.groups().clone();
+        let old_layers = serialise_children(&old_information)?;
+        let old_tasks = serialise_children(&self.root.tasks_group)?;
+        let old_style = self.style.css().clone();
+        let old_view_box = String::from(&self.view_box);
+        let old_metadata = self.metadata.update_string()?;
+
+        let update =
+            self.update_configurable_information(manycore, configuration, base_configuration)?;
+
+        // A new base configuration regenerates everything, there's nothing to compare against.
+        if let Some(svg) = update.svg {
+            return Ok(vec![UpdateOperation::ReplaceDocument { svg }]);
+        }
+
+        let mut operations = Vec::new();
+
+        if update.style != old_style {
+            operations.push(UpdateOperation::ReplaceStyle { css: update.style });
+        }
+
+        for (id, class) in update.fill_classes {
+            operations.push(UpdateOperation::SetAttribute {
+                id,
+                name: "class",
+                value: class,
+            });
+        }
+
+        // Layers where only labels changed are updated in place, the others are replaced.
+        let new_information = self.root.information_group.groups();
+        let mut new_layers = serialise_children(new_information)?;
+        for (index, (old, new)) in old_information.iter().zip(new_information).enumerate() {
+            if old_layers[index] == new_layers[index] {
+                continue;
+            }
+
+            if let Some(changes) = old.text_changes(new)? {
+                for (id, text) in changes {
+                    operations.push(UpdateOperation::SetText { id, text });
+                }
+                new_layers[index].clone_from(&old_layers[index]);
+            }
+        }
+
+        diff_children(
+            self.root.information_group.id(),
+            old_layers,
+            new_layers,
+            &mut operations,
+        );
+
+        diff_children(
+            self.root.tasks_group.id(),
+            old_tasks,
+            serialise_children(&self.root.tasks_group)?,
+            &mut operations,
+        );
+
+        if update.view_box != old_view_box {
+            operations.push(UpdateOperation::SetViewBox {
+                view_box: update.view_box,
+            });
+        }
+
+        if update.metadata != old_metadata {
+            if let Some(content) = update.metadata {
+                operations.push(UpdateOperation::ReplaceMetadata { content });
+            }
+        }
+
+        Ok(operations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::UpdateOperation;
+    use crate::{
+        test_utils::read_configuration, BaseConfiguration, Configuration, MetadataContent, SVG,
+    };
+
+    #[test]
+    fn describes_updates_as_operations() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let base_configuration = BaseConfiguration::default();
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        // The render time would change the metadata between updates
+        svg.set_metadata_content(MetadataContent::Reproducible);
+
+        let operations = svg
+            .update_operations(
+                &mut manycore,
+                &mut read_configuration("tests/conf7.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+
+        let number_of_cores = manycore.cores().list().len();
+        let added_layers = operations
+            .iter()
+            .filter(|operation| {
+                matches!(operation, UpdateOperation::AddNode { parent, .. } if parent == "information")
+            })
+            .count();
+        assert_eq!(added_layers, number_of_cores);
+        assert!(operations.contains(&UpdateOperation::SetAttribute {
+            id: "c3".to_string(),
+            name: "class",
+            value: "fill1".to_string(),
+        }));
+        assert!(operations.iter().any(|operation| matches!(
+            operation,
+            UpdateOperation::ReplaceMetadata { content } if content.contains("mp:configuration")
+        )));
+
+        // Same configuration, nothing to do
+        let operations = svg
+            .update_operations(
+                &mut manycore,
+                &mut read_configuration("tests/conf7.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        assert!(operations.is_empty());
+
+        // Back to the base render
+        let operations = svg
+            .update_operations(
+                &mut manycore,
+                &mut Configuration::default(),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+        let removed_layers: Vec<usize> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                UpdateOperation::RemoveNode { index, .. } => Some(*index),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            removed_layers,
+            (0..number_of_cores).rev().collect::<Vec<usize>>()
+        );
+    }
+
+    #[test]
+    fn updates_labels_in_place() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        // Core 0 is one degree warmer, same temperature bounds so only the label changes
+        let mut warmer = ManycoreSystem::parse_file("tests/VisualiserOutput1_warmer.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1_warmer.xml\"");
+
+        let base_configuration = BaseConfiguration::default();
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        svg.update_operations(
+            &mut manycore,
+            &mut read_configuration("tests/conf2.json"),
+            &base_configuration,
+        )
+        .expect("Could not update SVG.");
+
+        let operations = svg
+            .update_operations(
+                &mut warmer,
+                &mut read_configuration("tests/conf2.json"),
+                &base_configuration,
+            )
+            .expect("Could not update SVG.");
+
+        assert_eq!(operations.len(), 1, "{operations:?}");
+        match &operations[0] {
+            UpdateOperation::SetText { id, text } => {
+                assert!(id.starts_with("c0-label"), "{id}");
+                assert_eq!(text, "Temp: 46");
+            }
+            operation => panic!("Expected SetText, got {operation:?}"),
+        }
+    }
+}
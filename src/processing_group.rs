@@ -1,11 +1,13 @@
+use std::collections::BTreeMap;
+
 use const_format::concatcp;
 use getset::{Getters, MutGetters, Setters};
 use manycore_parser::ElementIDT;
 use serde::Serialize;
 
 use crate::{
-    style::BASE_FILL_CLASS_NAME, ClipPath, CoordinateT, SVGError, TopLeft, CONNECTION_LENGTH,
-    MARKER_HEIGHT, USE_FREEFORM_CLIP_PATH,
+    namespace::IdNamespace, style::BASE_FILL_CLASS_NAME, ClipPath, CoordinateT, SVGError, TopLeft,
+    CONNECTION_LENGTH, FREEFORM_CLIP_PATH_ID, MARKER_HEIGHT,
 };
 
 pub(crate) const SIDE_LENGTH: CoordinateT = 100;
@@ -56,7 +58,7 @@ pub(crate) static CORE_ROUTER_STROKE_WIDTH_STR: &'static str = concatcp!(CORE_RO
 #[derive(Serialize, Setters, Debug)]
 pub(crate) struct CommonAttributes {
     #[serde(rename = "@class", skip_serializing_if = "Option::is_none")]
-    class: Option<&'static str>,
+    class: Option<String>,
     #[serde(rename = "@fill-rule")]
     fill_rule: &'static str,
     #[serde(rename = "@stroke")]
@@ -70,7 +72,7 @@ pub(crate) struct CommonAttributes {
 impl Default for CommonAttributes {
     fn default() -> Self {
         Self {
-            class: Some(BASE_FILL_CLASS_NAME),
+            class: Some(BASE_FILL_CLASS_NAME.to_string()),
             fill_rule: "evenodd",
             stroke: "black",
             stroke_linecap: "butt",
@@ -90,6 +92,19 @@ impl CommonAttributes {
             stroke_width: CORE_ROUTER_STROKE_WIDTH_STR,
         }
     }
+
+    /// Sets the element's fill class, falling back to [`BASE_FILL_CLASS_NAME`].
+    /// Returns the new class if it changed.
+    pub(crate) fn set_fill_class(&mut self, class: Option<String>) -> Option<&String> {
+        let class = class.unwrap_or_else(|| BASE_FILL_CLASS_NAME.to_string());
+
+        if self.class.as_ref() == Some(&class) {
+            return None;
+        }
+
+        self.class = Some(class);
+        self.class.as_ref()
+    }
 }
 
 /// Object representattion of the SVG `<path>` that makes up a router.
@@ -100,6 +115,7 @@ pub(crate) struct Router {
     #[getset(get = "pub")]
     move_coordinates: (CoordinateT, CoordinateT),
     #[serde(rename = "@id")]
+    #[getset(get = "pub")]
     id: String,
     #[serde(rename = "@d")]
     d: String,
@@ -115,12 +131,13 @@ impl Router {
         column: &CoordinateT,
         id: &ElementIDT,
         top_left: &TopLeft,
+        namespace: &IdNamespace,
     ) -> Self {
         let (move_x, move_y) = Self::get_move_coordinates(row, column, top_left);
 
         Self {
             move_coordinates: (move_x, move_y),
-            id: format!("r{}", id),
+            id: namespace.id(&format!("r{}", id)),
             d: format!("M{},{} {}", move_x, move_y, ROUTER_PATH),
             attributes: CommonAttributes::default(),
         }
@@ -155,6 +172,7 @@ pub struct Core {
     #[getset(get = "pub")]
     move_coordinates: (CoordinateT, CoordinateT),
     #[serde(rename = "@id")]
+    #[getset(get = "pub")]
     id: String,
     #[serde(rename = "@d")]
     d: String,
@@ -184,12 +202,18 @@ impl Core {
     }
 
     /// Generates a new [`Core`] instance from the given parameters.
-    fn new(row: &CoordinateT, column: &CoordinateT, id: &ElementIDT, top_left: &TopLeft) -> Self {
+    fn new(
+        row: &CoordinateT,
+        column: &CoordinateT,
+        id: &ElementIDT,
+        top_left: &TopLeft,
+        namespace: &IdNamespace,
+    ) -> Self {
         let (move_x, move_y) = Self::get_move_coordinates(row, column, top_left);
 
         Self {
             move_coordinates: (move_x, move_y),
-            id: format!("c{}", id),
+            id: namespace.id(&format!("c{}", id)),
             d: format!("M{},{} {}", move_x, move_y, PROCESSOR_PATH),
             attributes: CommonAttributes::default(),
         }
@@ -203,8 +227,10 @@ pub(crate) struct ProcessingGroup {
     #[getset(get = "pub")]
     /// Coordinates (row, column)
     coordinates: (CoordinateT, CoordinateT),
+    #[serde(skip)]
+    core_id: ElementIDT,
     #[serde(rename = "@id")]
-    id: ElementIDT,
+    id: String,
     #[serde(rename = "path")]
     #[getset(get = "pub")]
     core: Core,
@@ -220,29 +246,50 @@ impl ProcessingGroup {
         column: &CoordinateT,
         id: &ElementIDT,
         top_left: &TopLeft,
-        clip_paths: &mut Vec<ClipPath>,
+        namespace: &IdNamespace,
     ) -> Result<Self, SVGError> {
         // Core
-        let core = Core::new(row, column, id, top_left);
-        let (core_x, core_y) = core.move_coordinates;
-        // Core clip path
-        let core_clip = ClipPath::for_core(*id, core_x, core_y);
-        clip_paths.push(core_clip);
+        let core = Core::new(row, column, id, top_left, namespace);
 
         // Router
-        let router = Router::new(row, column, id, top_left);
-        let (router_x, router_y) = router.move_coordinates;
-        // Router clip path
-        let router_clip = ClipPath::for_router(*id, router_x, router_y);
-        clip_paths.push(router_clip);
+        let router = Router::new(row, column, id, top_left, namespace);
 
         Ok(Self {
             coordinates: (*row, *column),
-            id: *id,
+            core_id: *id,
+            id: namespace.id(&id.to_string()),
             core,
             router,
         })
     }
+
+    /// Generates the [`ClipPath`]s of the [`Core`] and [`Router`], used to clip their information layer.
+    pub(crate) fn clip_paths(&self, namespace: &IdNamespace) -> [ClipPath; 2] {
+        let (core_x, core_y) = self.core.move_coordinates;
+        let (router_x, router_y) = self.router.move_coordinates;
+
+        [
+            ClipPath::for_core(self.core_id, core_x, core_y, namespace),
+            ClipPath::for_router(self.core_id, router_x, router_y, namespace),
+        ]
+    }
+
+    /// Sets the fill classes of the [`Core`] and [`Router`]. `None` restores the base fill.
+    /// Elements whose class changed are recorded in `class_updates` (element ID -> class).
+    pub(crate) fn set_fill_classes(
+        &mut self,
+        core_class: Option<String>,
+        router_class: Option<String>,
+        class_updates: &mut BTreeMap<String, String>,
+    ) {
+        if let Some(class) = self.core.attributes.set_fill_class(core_class) {
+            class_updates.insert(self.core.id.clone(), class.clone());
+        }
+
+        if let Some(class) = self.router.attributes.set_fill_class(router_class) {
+            class_updates.insert(self.router.id.clone(), class.clone());
+        }
+    }
 }
 
 /// An SVG `<g>` that wraps all [`ProcessingGroup`] instances.
@@ -250,19 +297,29 @@ impl ProcessingGroup {
 #[getset(get_mut = "pub", get = "pub")]
 pub(crate) struct ProcessingParentGroup {
     #[serde(rename = "@id")]
-    id: &'static str,
+    id: String,
     #[serde(rename = "@clip-path")]
-    clip_path: &'static str,
+    clip_path: String,
     g: Vec<ProcessingGroup>,
 }
 
 impl ProcessingParentGroup {
     /// Generates a new [`ProcessingParentGroup`] with capacity for `number_of_cores` instances of [`ProcessingGroup`]s.
-    pub(crate) fn new(number_of_cores: &usize) -> Self {
+    pub(crate) fn new(number_of_cores: &usize, namespace: &IdNamespace) -> Self {
         Self {
-            id: "processingGroup",
+            id: namespace.id("processingGroup"),
             g: Vec::with_capacity(*number_of_cores),
-            clip_path: USE_FREEFORM_CLIP_PATH,
+            clip_path: namespace.url(FREEFORM_CLIP_PATH_ID),
         }
     }
+
+    /// Generates the [`ClipPath`]s of every [`ProcessingGroup`], one at a time.
+    pub(crate) fn clip_paths<'a>(
+        &'a self,
+        namespace: &'a IdNamespace,
+    ) -> impl Iterator<Item = ClipPath> + 'a {
+        self.g
+            .iter()
+            .flat_map(move |group| group.clip_paths(namespace))
+    }
 }
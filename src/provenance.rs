@@ -0,0 +1,179 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{BaseConfiguration, Configuration, RoutingConfiguration, SVGError};
+
+static RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+static DUBLIN_CORE_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+/// Namespace of the provenance terms Dublin Core has no equivalent for.
+static PROVENANCE_NAMESPACE: &str =
+    "https://github.com/ManyCore-Visualiser/manycore_svg/provenance#";
+
+/// Formats a point in time as a UTC W3C date-time, e.g. `2024-02-29T13:45:00Z`.
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// 64-bit FNV-1a hash. Unlike [`DefaultHasher`](std::hash::DefaultHasher), it is stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Provenance terms of a generated document.
+/// * `creator`: manycore_svg and its version.
+/// * `date`: When the document was last rendered. Left out of
+///   [`MetadataContent::Reproducible`](crate::MetadataContent::Reproducible) documents.
+/// * `source`, `source_hash`: File name and FNV-1a hash of the system's XML, if known.
+/// * `base_configuration`, `configuration`: The [`BaseConfiguration`] and last [`Configuration`] used, as JSON.
+/// * `routing_algorithm`: The routing algorithm channel loads were computed with, if any.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct ProvenanceDescription {
+    #[serde(rename = "dc:creator")]
+    creator: String,
+    #[serde(rename = "dc:date", skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    #[serde(rename = "dc:format")]
+    format: &'static str,
+    #[serde(rename = "dc:source", skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(rename = "mp:sourceHash", skip_serializing_if = "Option::is_none")]
+    source_hash: Option<String>,
+    #[serde(rename = "mp:baseConfiguration")]
+    base_configuration: String,
+    #[serde(rename = "mp:configuration", skip_serializing_if = "Option::is_none")]
+    configuration: Option<String>,
+    #[serde(
+        rename = "mp:routingAlgorithm",
+        skip_serializing_if = "Option::is_none"
+    )]
+    routing_algorithm: Option<String>,
+}
+
+/// Object representation of the provenance of a generated document, as RDF with Dublin Core terms.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub(crate) struct Provenance {
+    #[serde(rename = "@xmlns:rdf")]
+    xmlns_rdf: &'static str,
+    #[serde(rename = "@xmlns:dc")]
+    xmlns_dc: &'static str,
+    #[serde(rename = "@xmlns:mp")]
+    xmlns_mp: &'static str,
+    #[serde(rename = "rdf:Description")]
+    description: ProvenanceDescription,
+}
+
+impl Provenance {
+    /// Generates a new [`Provenance`] instance for a document generated now.
+    pub(crate) fn new(base_configuration: &BaseConfiguration) -> Self {
+        Self {
+            xmlns_rdf: RDF_NAMESPACE,
+            xmlns_dc: DUBLIN_CORE_NAMESPACE,
+            xmlns_mp: PROVENANCE_NAMESPACE,
+            description: ProvenanceDescription {
+                creator: format!("manycore_svg {}", env!("CARGO_PKG_VERSION")),
+                date: Some(timestamp(SystemTime::now())),
+                format: "image/svg+xml",
+                source: None,
+                source_hash: None,
+                // Plain numbers, serialising can't fail
+                base_configuration: serde_json::to_string(base_configuration).unwrap_or_default(),
+                configuration: None,
+                routing_algorithm: None,
+            },
+        }
+    }
+
+    /// The same [`Provenance`] without the render time, so that the document only changes with its inputs.
+    pub(crate) fn undated(&self) -> Self {
+        let mut ret = self.clone();
+        ret.description.date = None;
+
+        ret
+    }
+
+    /// Records the file the document was generated from, given its contents.
+    pub(crate) fn set_source(&mut self, path: &Path, contents: &[u8]) {
+        self.description.source = Some(
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+        );
+        self.description.source_hash = Some(format!("fnv1a64:{:016x}", fnv1a(contents)));
+    }
+
+    /// Keeps the source of a previous [`Provenance`], e.g. when the document is regenerated.
+    pub(crate) fn inherit_source(&mut self, previous: &Provenance) {
+        self.description.source = previous.description.source.clone();
+        self.description.source_hash = previous.description.source_hash.clone();
+    }
+
+    /// Records a render. Must be called before any key is removed from the [`Configuration`].
+    pub(crate) fn record(&mut self, configuration: &Configuration) -> Result<(), SVGError> {
+        self.description.date = Some(timestamp(SystemTime::now()));
+        self.description.configuration = Some(serde_json::to_string(configuration)?);
+        self.description.routing_algorithm = None;
+
+        Ok(())
+    }
+
+    /// Records the routing algorithm of the current render.
+    pub(crate) fn record_routing(&mut self, routing_configuration: Option<&RoutingConfiguration>) {
+        self.description.routing_algorithm = routing_configuration
+            .map(|routing_configuration| format!("{:?}", routing_configuration.algorithm()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{fnv1a, timestamp};
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
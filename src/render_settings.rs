@@ -5,37 +5,94 @@ use std::{
 
 use getset::{Getters, MutGetters};
 use manycore_parser::ElementIDT;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
 use crate::{
-    tasks_group::DEFAULT_TASK_FONT_SIZE, CoordinateT, FontSizeT, CHAR_V_PADDING,
-    DEFAULT_ATTRIBUTE_FONT_SIZE,
+    tasks_group::DEFAULT_TASK_FONT_SIZE, ComputedAttribute, CoordinateT, FontSizeT, SVGError,
+    CHAR_V_PADDING, DEFAULT_ATTRIBUTE_FONT_SIZE,
 };
 
 mod configurable_base_configuration;
 mod field_configuration;
+mod file_formats;
+mod layering;
+mod migration;
 
 pub use configurable_base_configuration::*;
 pub use field_configuration::*;
+pub use file_formats::*;
+pub use layering::*;
+pub use migration::*;
 
 #[cfg(doc)]
 use manycore_parser::{Channel, Core, Router};
 
 /// Object representation of user-defined configuration.
+/// * `version`: Version of the configuration format, see [`CONFIGURATION_VERSION`]. Older documents are migrated when
+///   deserialised, see [`migrate_configuration`].
 /// * `core_config`: A [`BTreeMap`] with [`String`] attribute key and [`FieldConfiguration`] value. Controls what [`Core`] information to display and how.
 /// * `router_config`: A [`BTreeMap`] with [`String`] attribute key and [`FieldConfiguration`] value. Controls what [`Router`] information to display and how.
 /// * `channel_config`: A [`BTreeMap`] with [`String`] attribute key and [`FieldConfiguration`] value. Controls what [`Channel`] information to display and how.
-#[derive(Serialize, Deserialize, Getters, MutGetters, Default, PartialEq, Debug)]
-#[serde(rename_all = "camelCase")]
+/// * `computed_attributes`: A [`BTreeMap`] with [`String`] attribute key and [`ComputedAttribute`] value. Computed attribute keys can be used in any of the above.
+#[derive(Serialize, Deserialize, Getters, MutGetters, PartialEq, Debug)]
+#[serde(rename_all = "camelCase", remote = "Self")]
 #[getset(get = "pub", get_mut = "pub")]
 pub struct Configuration {
+    #[getset(skip)]
+    version: u64,
     core_config: BTreeMap<String, FieldConfiguration>,
     router_config: BTreeMap<String, FieldConfiguration>,
     channel_config: BTreeMap<String, FieldConfiguration>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    computed_attributes: BTreeMap<String, ComputedAttribute>,
     core_fills: BTreeMap<ElementIDT, String>,
     router_fills: BTreeMap<ElementIDT, String>,
 }
 
+impl Configuration {
+    /// Version of the configuration format. Always [`CONFIGURATION_VERSION`] once deserialised.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Copies the configuration through its serialised form, so that it can be modified without affecting the
+    /// caller's, e.g. to resolve [`AutoBounds`].
+    pub(crate) fn duplicate(&self) -> Result<Configuration, SVGError> {
+        Ok(serde_json::from_value(serde_json::to_value(self)?)?)
+    }
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self {
+            version: CONFIGURATION_VERSION,
+            core_config: BTreeMap::new(),
+            router_config: BTreeMap::new(),
+            channel_config: BTreeMap::new(),
+            computed_attributes: BTreeMap::new(),
+            core_fills: BTreeMap::new(),
+            router_fills: BTreeMap::new(),
+        }
+    }
+}
+
+impl Serialize for Configuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Configuration::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Configuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Older documents are upgraded before being deserialised
+        let document =
+            migrate_configuration(Value::deserialize(deserializer)?).map_err(D::Error::custom)?;
+
+        Configuration::deserialize(document).map_err(D::Error::custom)
+    }
+}
+
 /// Object representation of user-defined base configuration.
 /// This configuration contains fundamental details of the SVG structure that would require
 /// a full re-generation upon change.
@@ -47,7 +104,6 @@ pub struct BaseConfiguration {
 }
 
 impl BaseConfiguration {
-    #[cfg(test)]
     pub(crate) fn new(attribute_font_size: FontSizeT, task_font_size: FontSizeT) -> Self {
         Self {
             attribute_font_size,
@@ -133,8 +189,9 @@ mod tests {
     };
 
     use crate::{
-        tasks_group::MAXIMUM_TASK_FONT_SIZE, BaseConfiguration, ColourSettings, Configuration,
-        CoordinatesOrientation, FieldConfiguration, LoadConfiguration, RoutingConfiguration,
+        parse_colour_value, tasks_group::MAXIMUM_TASK_FONT_SIZE, BaseConfiguration, ColourSettings,
+        Configuration, CoordinatesOrientation, FieldConfiguration, LoadConfiguration,
+        MetadataContent, NumberFormat, RoutingConfiguration, ScaleTransform, CONFIGURATION_VERSION,
         MAXIMUM_ATTRIBUTE_FONT_SIZE, SVG,
     };
 
@@ -143,12 +200,14 @@ mod tests {
     #[test]
     fn can_parse_configuration() {
         let expected_configuration = Configuration {
+            version: CONFIGURATION_VERSION,
             core_config: BTreeMap::from([
                 (
                     "@id".to_string(),
                     FieldConfiguration::Text {
                         display: "ID".to_string(),
                         colour: None,
+                        format: NumberFormat::default(),
                     },
                 ),
                 (
@@ -161,7 +220,7 @@ mod tests {
                     "@age".to_string(),
                     FieldConfiguration::Fill {
                         colour_settings: ColourSettings::new(
-                            [30, 100, 200, 300],
+                            [30.0, 100.0, 200.0, 300.0],
                             [
                                 "#22c55e".to_string(),
                                 "#eab308".to_string(),
@@ -176,7 +235,7 @@ mod tests {
                     FieldConfiguration::ColouredText {
                         display: "Temp".to_string(),
                         colour_settings: ColourSettings::new(
-                            [30, 31, 50, 75],
+                            [30.0, 31.0, 50.0, 75.0],
                             [
                                 "#22c55e".to_string(),
                                 "#eab308".to_string(),
@@ -184,6 +243,7 @@ mod tests {
                                 "#dc2626".to_string(),
                             ],
                         ),
+                        format: NumberFormat::default(),
                     },
                 ),
             ]),
@@ -192,7 +252,7 @@ mod tests {
                     "@age".to_string(),
                     FieldConfiguration::Fill {
                         colour_settings: ColourSettings::new(
-                            [30, 100, 200, 300],
+                            [30.0, 100.0, 200.0, 300.0],
                             [
                                 "#22c55e".to_string(),
                                 "#eab308".to_string(),
@@ -207,7 +267,7 @@ mod tests {
                     FieldConfiguration::ColouredText {
                         display: "Temp".to_string(),
                         colour_settings: ColourSettings::new(
-                            [30, 31, 50, 75],
+                            [30.0, 31.0, 50.0, 75.0],
                             [
                                 "#22c55e".to_string(),
                                 "#eab308".to_string(),
@@ -215,6 +275,7 @@ mod tests {
                                 "#dc2626".to_string(),
                             ],
                         ),
+                        format: NumberFormat::default(),
                     },
                 ),
             ]),
@@ -224,7 +285,7 @@ mod tests {
                     FieldConfiguration::ColouredText {
                         display: "Age".to_string(),
                         colour_settings: ColourSettings::new(
-                            [30, 100, 200, 300],
+                            [30.0, 100.0, 200.0, 300.0],
                             [
                                 "#22c55e".to_string(),
                                 "#eab308".to_string(),
@@ -232,6 +293,7 @@ mod tests {
                                 "#dc2626".to_string(),
                             ],
                         ),
+                        format: NumberFormat::default(),
                     },
                 ),
                 (
@@ -245,7 +307,7 @@ mod tests {
                             RoutingAlgorithms::RowFirst,
                             LoadConfiguration::Percentage,
                             ColourSettings::new(
-                                [20, 50, 70, 90],
+                                [20.0, 50.0, 70.0, 90.0],
                                 [
                                     "#1a5fb4".to_string(),
                                     "#26a269".to_string(),
@@ -258,6 +320,7 @@ mod tests {
                     },
                 ),
             ]),
+            computed_attributes: BTreeMap::new(),
             core_fills: BTreeMap::new(),
             router_fills: BTreeMap::new(),
         };
@@ -297,6 +360,7 @@ mod tests {
             )
             .expect("Could not generate SVG update.");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG2.svg")
@@ -363,6 +427,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &BASE_CONFIG)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG3.svg")
@@ -392,6 +457,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &BASE_CONFIG)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG4.svg")
@@ -422,6 +488,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &base_configuration)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG5.svg")
@@ -449,6 +516,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &BASE_CONFIG)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG6.svg")
@@ -460,6 +528,114 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    #[test]
+    fn applies_scale_transforms() {
+        assert_eq!(ScaleTransform::Log10.apply(0.0), 0.0);
+        assert_eq!(ScaleTransform::Sqrt.apply(400.0), 20.0);
+        assert_eq!(ScaleTransform::Sqrt.apply(-4.0), 0.0);
+
+        let piecewise: ScaleTransform =
+            serde_json::from_str(r#"{"Piecewise": [[1000, 100], [0, 0], [10, 50]]}"#)
+                .expect("Could not parse scale transform");
+        assert_eq!(
+            piecewise,
+            ScaleTransform::Piecewise(vec![[0.0, 0.0], [10.0, 50.0], [1000.0, 100.0]])
+        );
+        assert_eq!(piecewise.apply(5.0), 25.0);
+        assert_eq!(piecewise.apply(505.0), 75.0);
+        assert_eq!(piecewise.apply(2000.0), 100.0);
+
+        let mut field_configuration: FieldConfiguration = serde_json::from_str(
+            r##"{
+                "type": "Fill",
+                "bounds": [1, 1.5, 3, 4],
+                "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"],
+                "scale": "Log10"
+            }"##,
+        )
+        .expect("Could not parse field configuration");
+        let colour_settings = field_configuration
+            .colour_settings_mut()
+            .expect("Fill has colour settings");
+
+        assert_eq!(colour_settings.colour_index(5.0), 0);
+        assert_eq!(colour_settings.colour_index(20.0), 0);
+        assert_eq!(colour_settings.colour_index(50.0), 1);
+        assert_eq!(colour_settings.colour_index(5000.0), 2);
+        assert_eq!(colour_settings.colour_index(50000.0), 3);
+    }
+
+    #[test]
+    fn supports_signed_values() {
+        assert_eq!(parse_colour_value("-12.5"), Some(-12.5));
+        assert_eq!(parse_colour_value("NaN"), None);
+
+        let colour_settings = ColourSettings::new(
+            [-20.0, -5.0, 0.0, 10.0],
+            [
+                "#22c55e".to_string(),
+                "#eab308".to_string(),
+                "#f97316".to_string(),
+                "#dc2626".to_string(),
+            ],
+        );
+
+        assert_eq!(colour_settings.colour_index(-30.0), 0);
+        assert_eq!(colour_settings.colour_index(-12.5), 0);
+        assert_eq!(colour_settings.colour_index(-3.0), 1);
+        assert_eq!(colour_settings.colour_index(5.0), 2);
+        assert_eq!(colour_settings.colour_index(15.0), 3);
+    }
+
+    #[test]
+    fn requires_bounds_or_auto_bounds() {
+        let parse = |json: &str| serde_json::from_str::<FieldConfiguration>(json);
+
+        assert!(parse(
+            r##"{
+                "type": "Fill",
+                "autoBounds": "Quantiles",
+                "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+            }"##
+        )
+        .is_ok());
+
+        let error = parse(
+            r##"{
+                "type": "Fill",
+                "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+            }"##,
+        )
+        .expect_err("Colour settings without bounds were accepted");
+        assert!(error.to_string().contains("autoBounds"));
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let format = |json: &str| -> NumberFormat {
+            serde_json::from_str(json).expect("Could not parse number format")
+        };
+
+        assert_eq!(format(r#"{"unit": "°C"}"#).apply("45"), "45 °C");
+        assert_eq!(
+            format(r#"{"siPrefix": true, "decimals": 1}"#).apply("1234"),
+            "1.2k"
+        );
+        assert_eq!(
+            format(r#"{"siPrefix": true, "decimals": 2, "unit": "Hz"}"#).apply("3400000000"),
+            "3.40 GHz"
+        );
+        assert_eq!(
+            format(r#"{"thousandsSeparator": ","}"#).apply("-1234567.5"),
+            "-1,234,567.5"
+        );
+        assert_eq!(
+            format(r#"{"template": "~%s (100%%)", "decimals": 0}"#).apply("41.6"),
+            "~42 (100%)"
+        );
+        assert_eq!(format(r#"{"unit": "°C"}"#).apply("N/A"), "N/A");
+    }
+
     #[test]
     fn can_override_fill() {
         let conf_file: fs::File =
@@ -476,6 +652,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &BASE_CONFIG)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         let expected = read_to_string("tests/SVG7.svg")
@@ -505,6 +682,7 @@ mod tests {
             .update_configurable_information(&mut manycore, &mut configuration, &base_config)
             .expect("Could not generate SVG update");
 
+        svg.set_metadata_content(MetadataContent::Layout);
         let res = String::try_from(&svg).expect("Could not convert from SVG to string");
 
         #[cfg(feature = "print")]
@@ -1,6 +1,8 @@
 use getset::Getters;
 use manycore_parser::RoutingAlgorithms;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::binary_search_left_insertion_point;
 
 /// Configuration colour settings
 /// * `bounds`: Numerical boundaries. Used to determine colour.
@@ -23,18 +25,156 @@ use serde::{Deserialize, Serialize};
 /// | `11`            | `#eab308` |
 /// | `35`            | `#f97316` |
 /// | `50`            | `#dc2626` |
-#[derive(Serialize, Deserialize, Getters, PartialEq, Debug, PartialOrd, Eq, Ord)]
+///
+/// Bounds can be derived from the rendered values instead, by setting `auto_bounds`. `bounds` can then be omitted,
+/// but one of the two is required.
+///
+/// Values can be transformed before being matched against `bounds` by setting `scale`, see [`ScaleTransform`].
+/// `bounds` are then expressed in the transformed scale, e.g. `[1, 2, 3, 4]` with [`Log10`][`ScaleTransform::Log10`]
+/// is equivalent to `[10, 100, 1000, 10000]`, and `[1.5, 2, 2.5, 3]` splits each decade in two.
+#[derive(Serialize, Deserialize, Getters, PartialEq, Debug, PartialOrd)]
+#[serde(try_from = "ColourSettingsFields")]
 #[getset(get = "pub")]
 pub struct ColourSettings {
-    bounds: [u64; 4],
+    bounds: [f64; 4],
     colours: [String; 4],
+    #[serde(
+        rename = "autoBounds",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    auto_bounds: Option<AutoBounds>,
+    #[serde(default, skip_serializing_if = "ScaleTransform::is_linear")]
+    scale: ScaleTransform,
+}
+
+/// [`ColourSettings`] as written in a configuration, where `bounds` are optional if `auto_bounds` is set.
+#[derive(Deserialize)]
+struct ColourSettingsFields {
+    bounds: Option<[f64; 4]>,
+    colours: [String; 4],
+    #[serde(rename = "autoBounds", default)]
+    auto_bounds: Option<AutoBounds>,
+    #[serde(default)]
+    scale: ScaleTransform,
+}
+
+impl TryFrom<ColourSettingsFields> for ColourSettings {
+    type Error = &'static str;
+
+    fn try_from(fields: ColourSettingsFields) -> Result<Self, Self::Error> {
+        let bounds = match (fields.bounds, fields.auto_bounds) {
+            (Some(bounds), _) => bounds,
+            // Resolved from the rendered values
+            (None, Some(_)) => [0.0; 4],
+            (None, None) => return Err("colour settings require either `bounds` or `autoBounds`"),
+        };
+
+        Ok(Self {
+            bounds,
+            colours: fields.colours,
+            auto_bounds: fields.auto_bounds,
+            scale: fields.scale,
+        })
+    }
+}
+
+/// Methods to derive [`ColourSettings`] bounds from the values of all cores, routers or channels of a system.
+/// * [`Quantiles`][`AutoBounds::Quantiles`]: Quartiles, each colour covers a quarter of the values.
+/// * [`EqualWidth`][`AutoBounds::EqualWidth`]: Four equally wide bins between the smallest and largest value.
+/// * [`StandardDeviations`][`AutoBounds::StandardDeviations`]: One standard deviation below the mean, the mean, one
+///   and two standard deviations above the mean.
+#[derive(Serialize, Deserialize, PartialEq, Debug, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum AutoBounds {
+    Quantiles,
+    EqualWidth,
+    StandardDeviations,
+}
+
+/// Transforms applied to values before they are matched against [`ColourSettings`] bounds.
+/// * [`Linear`][`ScaleTransform::Linear`]: Values are used as they are. This is the default.
+/// * [`Log10`][`ScaleTransform::Log10`]: Base 10 logarithm. Values below 1 are treated as 1.
+/// * [`Sqrt`][`ScaleTransform::Sqrt`]: Square root. Values below 0 are treated as 0.
+/// * [`Piecewise`][`ScaleTransform::Piecewise`]: User defined `[input, output]` points, linearly interpolated.
+///   Values outside the points take the output of the closest point. Points are sorted by input when deserialised.
+#[derive(Serialize, Deserialize, PartialEq, Debug, PartialOrd, Clone, Default)]
+pub enum ScaleTransform {
+    #[default]
+    Linear,
+    Log10,
+    Sqrt,
+    Piecewise(#[serde(deserialize_with = "sorted_points")] Vec<[f64; 2]>),
+}
+
+/// Deserialises [`ScaleTransform::Piecewise`] points, sorted by input so they can be searched when applied.
+fn sorted_points<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[f64; 2]>, D::Error> {
+    let mut points = Vec::<[f64; 2]>::deserialize(deserializer)?;
+    points.sort_unstable_by(|[a, _], [b, _]| a.total_cmp(b));
+
+    Ok(points)
+}
+
+impl ScaleTransform {
+    fn is_linear(&self) -> bool {
+        matches!(self, ScaleTransform::Linear)
+    }
+
+    /// Transforms the given value.
+    pub(crate) fn apply(&self, value: f64) -> f64 {
+        match self {
+            ScaleTransform::Linear => value,
+            ScaleTransform::Log10 => value.max(1.0).log10(),
+            ScaleTransform::Sqrt => value.max(0.0).sqrt(),
+            ScaleTransform::Piecewise(points) => {
+                // Index of the first point past value
+                let i = points.partition_point(|[input, _]| *input <= value);
+
+                match (i.checked_sub(1).map(|j| points[j]), points.get(i).copied()) {
+                    (Some([x0, y0]), Some([x1, y1])) => {
+                        let t = (value - x0) / (x1 - x0);
+
+                        y0 + t * (y1 - y0)
+                    }
+                    (Some([_, y]), None) | (None, Some([_, y])) => y,
+                    // No points, nothing to map with
+                    (None, None) => value,
+                }
+            }
+        }
+    }
 }
 
 impl ColourSettings {
     #[cfg(test)]
     /// Generates a new [`ColourSettings`] from the given parameters.
-    pub(crate) fn new(bounds: [u64; 4], colours: [String; 4]) -> Self {
-        Self { bounds, colours }
+    pub(crate) fn new(bounds: [f64; 4], colours: [String; 4]) -> Self {
+        Self {
+            bounds,
+            colours,
+            auto_bounds: None,
+            scale: ScaleTransform::Linear,
+        }
+    }
+
+    /// Index of the colour a value maps to, after applying the scale transform.
+    pub(crate) fn colour_index(&self, value: f64) -> usize {
+        binary_search_left_insertion_point(&self.bounds, self.scale.apply(value))
+    }
+
+    /// Colour a value maps to, after applying the scale transform.
+    pub(crate) fn colour(&self, value: f64) -> &String {
+        &self.colours[self.colour_index(value)]
+    }
+
+    /// Replaces the bounds, e.g. with ones derived from the rendered values.
+    pub(crate) fn set_bounds(&mut self, bounds: [f64; 4]) {
+        self.bounds = bounds;
+    }
+
+    /// Replaces the bounds and stops deriving them automatically.
+    pub(crate) fn fix_bounds(&mut self, bounds: [f64; 4]) {
+        self.bounds = bounds;
+        self.auto_bounds = None;
     }
 }
 
@@ -52,6 +192,7 @@ pub enum CoordinatesOrientation {
 /// * `load_configuration`: [`LoadConfiguration`]
 /// * `load_colours`: [`ColourSettings`]
 /// * `display`: [`String`], the display key of channel loads.
+/// * `format`: [`NumberFormat`] of channel loads.
 #[derive(Serialize, Deserialize, Getters, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
@@ -61,9 +202,16 @@ pub struct RoutingConfiguration {
     #[serde(flatten)]
     load_colours: ColourSettings,
     display: String,
+    #[serde(flatten)]
+    format: NumberFormat,
 }
 
 impl RoutingConfiguration {
+    /// Returns the load [`ColourSettings`] mutably, so that automatic bounds can be resolved.
+    pub(crate) fn load_colours_mut(&mut self) -> &mut ColourSettings {
+        &mut self.load_colours
+    }
+
     #[cfg(test)]
     pub(crate) fn new(
         algorithm: RoutingAlgorithms,
@@ -76,6 +224,7 @@ impl RoutingConfiguration {
             load_configuration,
             load_colours,
             display,
+            format: NumberFormat::default(),
         }
     }
 }
@@ -89,6 +238,132 @@ pub enum LoadConfiguration {
     Fraction,
 }
 
+/// SI prefixes used by [`NumberFormat`], with their scale.
+static SI_PREFIXES: [(f64, &str); 9] = [
+    (1e15, "P"),
+    (1e12, "T"),
+    (1e9, "G"),
+    (1e6, "M"),
+    (1e3, "k"),
+    (1.0, ""),
+    (1e-3, "m"),
+    (1e-6, "µ"),
+    (1e-9, "n"),
+];
+
+/// Formatting of displayed numeric values. Values that are not numbers are displayed as they are.
+/// * `unit`: Unit suffix, separated from the value by a space, e.g. `°C` displays `45` as `45 °C`.
+/// * `decimals`: Number of decimal places. If not provided, values are displayed as they are, or with up to two
+///   decimal places when scaled by an SI prefix.
+/// * `si_prefix`: Scales values by SI prefixes, e.g. `1200` is displayed as `1.2k`.
+/// * `thousands_separator`: Separator between groups of thousands, e.g. `,` displays `1234567` as `1,234,567`.
+/// * `template`: Printf-like template. `%s` is replaced with the formatted value and `%%` with `%`, e.g. `~%s`.
+#[derive(Serialize, Deserialize, Getters, PartialEq, Debug, PartialOrd, Eq, Ord, Default)]
+#[serde(rename_all = "camelCase", default)]
+#[getset(get = "pub")]
+pub struct NumberFormat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decimals: Option<usize>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    si_prefix: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thousands_separator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+}
+
+impl NumberFormat {
+    /// Formats the given value for display.
+    pub(crate) fn apply(&self, value: &str) -> String {
+        let formatted = match value.trim().parse::<f64>() {
+            Ok(number) if number.is_finite() => self.format_number(value.trim(), number),
+            _ => value.to_string(),
+        };
+
+        match &self.template {
+            Some(template) => {
+                let mut ret = String::with_capacity(template.len() + formatted.len());
+                let mut chars = template.chars();
+
+                while let Some(c) = chars.next() {
+                    match (c, chars.clone().next()) {
+                        ('%', Some('s')) => {
+                            ret.push_str(&formatted);
+                            chars.next();
+                        }
+                        ('%', Some('%')) => {
+                            ret.push('%');
+                            chars.next();
+                        }
+                        _ => ret.push(c),
+                    }
+                }
+
+                ret
+            }
+            None => formatted,
+        }
+    }
+
+    /// Formats a numeric value. `raw` is the value as provided, used when no rounding is requested.
+    fn format_number(&self, raw: &str, number: f64) -> String {
+        let (scaled, prefix) = if self.si_prefix && number != 0.0 {
+            // Values smaller than the smallest prefix still use it
+            let (scale, prefix) = SI_PREFIXES
+                .iter()
+                .find(|(scale, _)| number.abs() >= *scale)
+                .unwrap_or(&SI_PREFIXES[SI_PREFIXES.len() - 1]);
+
+            (number / scale, *prefix)
+        } else {
+            (number, "")
+        };
+
+        let digits = match self.decimals {
+            Some(decimals) => format!("{scaled:.decimals$}"),
+            None if !prefix.is_empty() => {
+                let rounded = format!("{scaled:.2}");
+                rounded
+                    .trim_end_matches('0')
+                    .trim_end_matches('.')
+                    .to_string()
+            }
+            None => raw.to_string(),
+        };
+
+        let digits = match &self.thousands_separator {
+            Some(separator) => {
+                let (sign, unsigned) = match digits.strip_prefix('-') {
+                    Some(unsigned) => ("-", unsigned),
+                    None => ("", digits.as_str()),
+                };
+                let (integer, fraction) = match unsigned.find('.') {
+                    Some(i) => unsigned.split_at(i),
+                    None => (unsigned, ""),
+                };
+
+                let mut grouped = String::new();
+                for (i, digit) in integer.chars().enumerate() {
+                    if i > 0 && (integer.len() - i) % 3 == 0 {
+                        grouped.push_str(separator);
+                    }
+                    grouped.push(digit);
+                }
+
+                format!("{sign}{grouped}{fraction}")
+            }
+            None => digits,
+        };
+
+        match &self.unit {
+            Some(unit) => format!("{digits} {prefix}{unit}"),
+            None => format!("{digits}{prefix}"),
+        }
+    }
+}
+
 /// Possible ways a field can be configured.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type")]
@@ -96,13 +371,18 @@ pub enum FieldConfiguration {
     /// Text only.
     Text {
         display: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
         colour: Option<String>,
+        #[serde(flatten)]
+        format: NumberFormat,
     },
     /// Coloured Text according to provided [`ColourSettings`].
     ColouredText {
         display: String,
         #[serde(rename = "colourSettings", flatten)]
         colour_settings: ColourSettings,
+        #[serde(flatten)]
+        format: NumberFormat,
     },
     /// Fill colour of associated element, according to provided [`ColourSettings`].
     Fill {
@@ -121,6 +401,17 @@ pub enum FieldConfiguration {
 }
 
 impl FieldConfiguration {
+    /// Returns the [`ColourSettings`] of variants that map attribute values to colours.
+    pub(crate) fn colour_settings_mut(&mut self) -> Option<&mut ColourSettings> {
+        match self {
+            FieldConfiguration::ColouredText {
+                colour_settings, ..
+            }
+            | FieldConfiguration::Fill { colour_settings } => Some(colour_settings),
+            _ => None,
+        }
+    }
+
     pub(crate) fn type_str(&self) -> &'static str {
         match self {
             FieldConfiguration::Boolean { .. } => "Boolean",
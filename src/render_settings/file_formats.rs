@@ -0,0 +1,321 @@
+use std::{fs, path::Path};
+
+use manycore_parser::ElementIDT;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    migrate_document, watch::write_atomically, FieldConfiguration, SVGError, SVGErrorKind,
+    FIELD_SECTIONS,
+};
+
+#[cfg(doc)]
+use crate::{BaseConfiguration, Configuration};
+
+/// File formats a [`Configuration`] or [`BaseConfiguration`] can be read from and written to.
+/// * [`Json`][`ConfigurationFormat::Json`]: Always available.
+/// * [`Toml`][`ConfigurationFormat::Toml`]: Requires the `toml` feature.
+/// * [`Yaml`][`ConfigurationFormat::Yaml`]: Requires the `yaml` feature.
+///
+/// Parse errors carry the line and column of the problem in their [`ErrorContext`](crate::ErrorContext). Files are
+/// converted to JSON before being deserialised, so a [`Configuration`] entry that cannot be deserialised is reported
+/// with its `key`, e.g. `coreConfig.@age`, and the position of that key in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationFormat {
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+/// Error thrown when a file has an extension whose format is not enabled.
+#[cfg(not(all(feature = "toml", feature = "yaml")))]
+fn unsupported_format(extension: &str, feature: &str) -> SVGError {
+    SVGError::new(SVGErrorKind::DataConversionError(format!(
+        "Reading .{extension} files requires the \"{feature}\" feature."
+    )))
+    .with_code("unsupported_format")
+}
+
+/// Sections of a [`Configuration`] holding fill overrides.
+static FILL_SECTIONS: [&str; 2] = ["coreFills", "routerFills"];
+
+/// Line and column, starting from 1, of a byte offset in `contents`.
+fn location(contents: &str, offset: usize) -> (usize, usize) {
+    let before = contents.get(..offset).unwrap_or(contents);
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+#[cfg(feature = "toml")]
+fn toml_error(contents: &str, error: toml::de::Error) -> SVGError {
+    let ret = SVGError::new(SVGErrorKind::SerialisationError(
+        error.message().to_string(),
+    ))
+    .with_code("invalid_toml");
+
+    match error.span() {
+        Some(span) => {
+            let (line, column) = location(contents, span.start);
+            ret.with_location(line, column)
+        }
+        None => ret,
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_error(error: serde_yaml::Error) -> SVGError {
+    let ret = SVGError::new(SVGErrorKind::SerialisationError(error.to_string()))
+        .with_code("invalid_yaml");
+
+    match error.location() {
+        Some(location) => ret.with_location(location.line(), location.column()),
+        None => ret,
+    }
+}
+
+/// Section and key of the first entry of an upgraded [`Configuration`] document that cannot be deserialised.
+fn invalid_entry(document: &Value) -> Option<(&'static str, &String)> {
+    let entries = move |sections: &'static [&'static str]| {
+        sections.iter().flat_map(|section| {
+            document
+                .get(section)
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(move |(key, value)| (*section, key, value))
+        })
+    };
+
+    entries(&FIELD_SECTIONS)
+        .find(|(_, _, value)| FieldConfiguration::deserialize(*value).is_err())
+        .or_else(|| {
+            entries(&FILL_SECTIONS)
+                .find(|(_, key, value)| key.parse::<ElementIDT>().is_err() || !value.is_string())
+        })
+        .map(|(section, key, _)| (section, key))
+}
+
+/// Position of `key` after the first mention of `section` in `contents`. Keys are looked up quoted, as keys
+/// starting with `@` must be in every format, or followed by a key-value separator.
+fn entry_location(contents: &str, section: &str, key: &str) -> Option<(usize, usize)> {
+    let section_start = contents.find(section)?;
+    let offset = [
+        format!("\"{key}\""),
+        format!("'{key}'"),
+        format!("{key}:"),
+        format!("{key} ="),
+    ]
+    .iter()
+    .filter_map(|pattern| contents[section_start..].find(pattern.as_str()))
+    .min()?;
+
+    Some(location(contents, section_start + offset))
+}
+
+/// Maps an error deserialising the parsed `document` back to the offending [`Configuration`] entry in `contents`.
+fn locate_entry(error: serde_json::Error, document: Value, contents: &str) -> SVGError {
+    let ret = SVGError::from(error);
+    // Entries are checked in their upgraded form, like they are deserialised
+    let Ok(document) = migrate_document(document).map(Value::Object) else {
+        return ret;
+    };
+
+    match invalid_entry(&document) {
+        Some((section, key)) => {
+            let ret = ret.with_key(&format!("{section}.{key}"));
+
+            match entry_location(contents, section, key) {
+                Some((line, column)) => ret.with_location(line, column),
+                None => ret,
+            }
+        }
+        None => ret,
+    }
+}
+
+/// TOML has no null, optional values are left out instead.
+#[cfg(feature = "toml")]
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(array) => array.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+impl ConfigurationFormat {
+    /// Determines the format of a file from its extension: `.toml`, `.yaml`/`.yml` or JSON for anything else.
+    pub fn from_path(path: &Path) -> Result<Self, SVGError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Ok(ConfigurationFormat::Toml),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(unsupported_format("toml", "toml")),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Ok(ConfigurationFormat::Yaml),
+            #[cfg(not(feature = "yaml"))]
+            Some(extension @ ("yaml" | "yml")) => Err(unsupported_format(extension, "yaml")),
+            _ => Ok(ConfigurationFormat::Json),
+        }
+    }
+
+    /// Deserialises `contents`.
+    pub fn parse<T: DeserializeOwned>(&self, contents: &str) -> Result<T, SVGError> {
+        let document = self.parse_value(contents)?;
+
+        T::deserialize(&document).map_err(|error| locate_entry(error, document, contents))
+    }
+
+    /// Parses `contents` into a JSON value, e.g. to layer it. Map keys are always strings.
+    pub(crate) fn parse_value(&self, contents: &str) -> Result<Value, SVGError> {
+        match self {
+            ConfigurationFormat::Json => Ok(serde_json::from_str(contents)?),
+            #[cfg(feature = "toml")]
+            ConfigurationFormat::Toml => {
+                let value: toml::Value =
+                    toml::from_str(contents).map_err(|error| toml_error(contents, error))?;
+
+                Ok(serde_json::to_value(value)?)
+            }
+            #[cfg(feature = "yaml")]
+            ConfigurationFormat::Yaml => {
+                // YAML keys can be numbers, JSON serialisation turns them into strings
+                let value: serde_yaml::Value =
+                    serde_yaml::from_str(contents).map_err(yaml_error)?;
+
+                Ok(serde_json::to_value(value)?)
+            }
+        }
+    }
+
+    /// Serialises `value`.
+    pub fn serialise<T: Serialize>(&self, value: &T) -> Result<String, SVGError> {
+        match self {
+            ConfigurationFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+            #[cfg(feature = "toml")]
+            ConfigurationFormat::Toml => {
+                let mut value = serde_json::to_value(value)?;
+                remove_nulls(&mut value);
+
+                toml::to_string_pretty(&value).map_err(|error| {
+                    SVGError::new(SVGErrorKind::SerialisationError(error.to_string()))
+                        .with_code("toml_serialisation_failed")
+                })
+            }
+            #[cfg(feature = "yaml")]
+            ConfigurationFormat::Yaml => serde_yaml::to_string(value).map_err(yaml_error),
+        }
+    }
+}
+
+/// Reads a [`Configuration`] or [`BaseConfiguration`] file, in the format given by its extension.
+/// See [`ConfigurationFormat::from_path`].
+pub fn read_configuration_file<T: DeserializeOwned>(path: &Path) -> Result<T, SVGError> {
+    ConfigurationFormat::from_path(path)?.parse(&fs::read_to_string(path)?)
+}
+
+/// Writes a [`Configuration`] or [`BaseConfiguration`] file, in the format given by its extension.
+/// See [`ConfigurationFormat::from_path`].
+pub fn write_configuration_file<T: Serialize>(value: &T, path: &Path) -> Result<(), SVGError> {
+    let contents = ConfigurationFormat::from_path(path)?.serialise(value)?;
+
+    write_atomically(path, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{read_configuration_file, ConfigurationFormat};
+    use crate::{BaseConfiguration, Configuration};
+
+    fn round_trip(format: ConfigurationFormat) {
+        let configuration: Configuration = read_configuration_file(Path::new("tests/conf7.json"))
+            .expect("Could not read \"tests/conf7.json\"");
+
+        let serialised = format
+            .serialise(&configuration)
+            .expect("Could not serialise configuration");
+        let parsed: Configuration = format
+            .parse(&serialised)
+            .expect("Could not parse serialised configuration");
+        assert_eq!(parsed, configuration);
+
+        let base_configuration = BaseConfiguration::default();
+        let serialised = format
+            .serialise(&base_configuration)
+            .expect("Could not serialise base configuration");
+        let parsed: BaseConfiguration = format
+            .parse(&serialised)
+            .expect("Could not parse serialised base configuration");
+        assert_eq!(parsed, base_configuration);
+    }
+
+    #[test]
+    fn round_trips_json() {
+        round_trip(ConfigurationFormat::Json);
+
+        let error = ConfigurationFormat::Json
+            .parse::<Configuration>("{\n  \"coreConfig\": [\n}")
+            .expect_err("Invalid JSON was accepted");
+        assert_eq!(*error.context().line(), Some(3));
+
+        let error = ConfigurationFormat::Json
+            .parse::<Configuration>(
+                r#"{
+  "coreConfig": {},
+  "routerConfig": {
+    "@temperature": { "type": "Text", "display": "Temp" },
+    "@age": { "type": "Fil" }
+  },
+  "channelConfig": {},
+  "coreFills": {},
+  "routerFills": {}
+}"#,
+            )
+            .expect_err("Invalid field configuration was accepted");
+        assert_eq!(error.context().key().as_deref(), Some("routerConfig.@age"));
+        assert_eq!(*error.context().line(), Some(5));
+        assert_eq!(*error.context().column(), Some(5));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn round_trips_toml() {
+        round_trip(ConfigurationFormat::Toml);
+
+        let error = ConfigurationFormat::Toml
+            .parse::<Configuration>("[coreConfig]\n\"@age\" = {")
+            .expect_err("Invalid TOML was accepted");
+        assert_eq!(*error.context().line(), Some(2));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn round_trips_yaml() {
+        round_trip(ConfigurationFormat::Yaml);
+
+        let error = ConfigurationFormat::Yaml
+            .parse::<Configuration>("coreConfig: {}\nrouterConfig: [\n")
+            .expect_err("Invalid YAML was accepted");
+        assert!(error.context().line().is_some());
+
+        let error = ConfigurationFormat::Yaml
+            .parse::<Configuration>(
+                "coreConfig:\n  \"@age\":\n    type: Fill\n    bounds: [0, 1]\nrouterConfig: {}\n\
+                channelConfig: {}\ncoreFills: {}\nrouterFills: {}\n",
+            )
+            .expect_err("Invalid field configuration was accepted");
+        assert_eq!(error.context().key().as_deref(), Some("coreConfig.@age"));
+        assert_eq!(*error.context().line(), Some(2));
+    }
+}
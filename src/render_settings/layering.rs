@@ -0,0 +1,243 @@
+use std::{fs, path::PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::{migrate_document, Configuration, ConfigurationFormat, SVGError, SVGErrorKind};
+
+/// Built-in presets, by name.
+/// * `thermal`: Core and router temperatures.
+/// * `ageing`: Core and router ages, coloured by quartile.
+/// * `routing-load`: Channel loads of row first routing.
+static PRESETS: [(&str, &str); 3] = [
+    (
+        "thermal",
+        r##"{
+            "coreConfig": {
+                "@temperature": {
+                    "type": "Fill",
+                    "bounds": [0, 50, 70, 85],
+                    "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                }
+            },
+            "routerConfig": {
+                "@temperature": {
+                    "type": "ColouredText",
+                    "display": "Temp",
+                    "bounds": [0, 50, 70, 85],
+                    "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"],
+                    "unit": "°C"
+                }
+            }
+        }"##,
+    ),
+    (
+        "ageing",
+        r##"{
+            "coreConfig": {
+                "@age": {
+                    "type": "Fill",
+                    "autoBounds": "Quantiles",
+                    "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                }
+            },
+            "routerConfig": {
+                "@age": {
+                    "type": "Fill",
+                    "autoBounds": "Quantiles",
+                    "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                }
+            }
+        }"##,
+    ),
+    (
+        "routing-load",
+        r##"{
+            "channelConfig": {
+                "@routingAlgorithm": {
+                    "type": "Routing",
+                    "algorithm": "RowFirst",
+                    "loadConfiguration": "Percentage",
+                    "bounds": [25, 50, 75, 100],
+                    "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"],
+                    "display": "Load"
+                }
+            }
+        }"##,
+    ),
+];
+
+/// Required sections of a [`Configuration`].
+static SECTIONS: [&str; 5] = [
+    "coreConfig",
+    "routerConfig",
+    "channelConfig",
+    "coreFills",
+    "routerFills",
+];
+
+/// A source of a layered [`Configuration`].
+/// * [`Preset`][`ConfigurationSource::Preset`]: A built-in preset, see [`ConfigurationSource::presets`].
+/// * [`File`][`ConfigurationSource::File`]: A configuration file, in the format given by its extension. See
+///   [`ConfigurationFormat::from_path`].
+/// * [`Value`][`ConfigurationSource::Value`]: An already parsed JSON configuration.
+///
+/// Layers do not need to be complete configurations: missing sections are left untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigurationSource {
+    Preset(String),
+    File(PathBuf),
+    Value(Value),
+}
+
+impl ConfigurationSource {
+    /// Names of the built-in presets.
+    pub fn presets() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Parses a command line source: `preset:<name>` for presets, a file path otherwise.
+    pub fn parse(source: &str) -> Self {
+        match source.strip_prefix("preset:") {
+            Some(name) => ConfigurationSource::Preset(name.to_string()),
+            None => ConfigurationSource::File(PathBuf::from(source)),
+        }
+    }
+
+    fn read(&self) -> Result<Value, SVGError> {
+        match self {
+            ConfigurationSource::Preset(name) => {
+                let (_, preset) = PRESETS
+                    .iter()
+                    .find(|(preset_name, _)| *preset_name == name.as_str())
+                    .ok_or_else(|| {
+                        SVGError::new(SVGErrorKind::ConfigurationError(format!(
+                            "Unknown preset \"{name}\". Available presets: {}.",
+                            ConfigurationSource::presets()
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )))
+                        .with_code("unknown_preset")
+                    })?;
+
+                Ok(serde_json::from_str(preset)?)
+            }
+            ConfigurationSource::File(path) => {
+                ConfigurationFormat::from_path(path)?.parse_value(&fs::read_to_string(path)?)
+            }
+            ConfigurationSource::Value(value) => Ok(value.clone()),
+        }
+    }
+}
+
+/// Merges `layer` into `base`. Sections are merged key by key: `null` removes a key and anything else replaces the
+/// previous value, e.g. a whole field configuration. Other values, e.g. `version`, are replaced.
+fn merge(base: &mut Map<String, Value>, layer: Map<String, Value>) {
+    for (section, value) in layer {
+        match (base.get_mut(&section), value) {
+            (Some(Value::Object(base_section)), Value::Object(layer_section)) => {
+                for (key, value) in layer_section {
+                    match value {
+                        Value::Null => {
+                            base_section.remove(&key);
+                        }
+                        value => {
+                            base_section.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (_, value) => {
+                base.insert(section, value);
+            }
+        }
+    }
+}
+
+impl Configuration {
+    /// Builds a [`Configuration`] by layering `sources` in order, later sources take precedence.
+    ///
+    /// `coreConfig`, `routerConfig`, `channelConfig`, `computedAttributes`, `coreFills` and `routerFills` are merged
+    /// key by key: a layer only needs to provide the keys it changes. Each field configuration is replaced as a
+    /// whole, and keys set to `null` are removed, e.g. `{"coreConfig": {"@age": null}}` stops displaying core ages.
+    pub fn layered(sources: &[ConfigurationSource]) -> Result<Configuration, SVGError> {
+        // Every section starts empty, layers only provide what they change
+        let mut ret = Map::new();
+        for section in SECTIONS {
+            ret.insert(section.to_string(), Value::Object(Map::new()));
+        }
+
+        for source in sources {
+            // Layers can be of different versions, they are upgraded before being merged
+            merge(&mut ret, migrate_document(source.read()?)?);
+        }
+
+        Configuration::from_value(Value::Object(ret))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ROUTING_KEY;
+    use serde_json::json;
+
+    use super::ConfigurationSource;
+    use crate::{Configuration, FieldConfiguration, SVGErrorKind};
+
+    #[test]
+    fn parses_presets() {
+        for preset in ConfigurationSource::presets() {
+            Configuration::layered(&[ConfigurationSource::Preset(preset.to_string())])
+                .unwrap_or_else(|error| panic!("Invalid preset {preset}: {error}"));
+        }
+
+        let error = Configuration::layered(&[ConfigurationSource::parse("preset:unknown")])
+            .expect_err("Unknown preset was accepted");
+        assert!(matches!(error.kind(), SVGErrorKind::ConfigurationError(_)));
+    }
+
+    #[test]
+    fn layers_configurations() {
+        let configuration = Configuration::layered(&[
+            ConfigurationSource::parse("preset:ageing"),
+            ConfigurationSource::parse("preset:routing-load"),
+            ConfigurationSource::parse("tests/conf7.json"),
+            ConfigurationSource::Value(json!({
+                "routerConfig": { "@age": null },
+                "channelConfig": {
+                    "@routingAlgorithm": {
+                        "type": "Routing",
+                        "algorithm": "ColumnFirst",
+                        "loadConfiguration": "Fraction",
+                        "autoBounds": "EqualWidth",
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"],
+                        "display": "Channel load"
+                    }
+                },
+                "coreFills": { "3": null }
+            })),
+        ])
+        .expect("Could not layer configurations");
+
+        // conf7 replaces the preset's field configuration as a whole, auto bounds included
+        match configuration.core_config().get("@age") {
+            Some(FieldConfiguration::Fill { colour_settings }) => {
+                assert!(colour_settings.auto_bounds().is_none());
+                assert_eq!(*colour_settings.bounds(), [0.0; 4]);
+                assert_eq!(colour_settings.colours()[3], "#22c55e");
+            }
+            other => panic!("Unexpected core age configuration {other:?}"),
+        }
+
+        assert!(configuration.router_config().is_empty());
+        assert!(configuration.core_fills().is_empty());
+        assert_eq!(configuration.router_fills().len(), 1);
+
+        match configuration.channel_config().get(ROUTING_KEY) {
+            Some(FieldConfiguration::Routing { configuration }) => {
+                assert_eq!(configuration.display(), "Channel load");
+                assert!(configuration.load_colours().auto_bounds().is_some());
+            }
+            other => panic!("Unexpected routing configuration {other:?}"),
+        }
+    }
+}
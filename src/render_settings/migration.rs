@@ -0,0 +1,243 @@
+use serde_json::{Map, Value};
+
+use crate::{Configuration, SVGError, SVGErrorKind};
+
+/// Current version of the [`Configuration`] format.
+///
+/// Version history:
+/// * `1`: [`ColourSettings`](crate::ColourSettings) are nested under `colourSettings` in `Fill` and `ColouredText`
+///   field configurations, and under `loadColours` in `Routing` ones. Documents without a `version` are version 1.
+/// * `2`: Colour settings are flattened into the field configuration, e.g. `{"type": "Fill", "bounds": [...],
+///   "colours": [...]}`. Documents carry their `version`.
+pub const CONFIGURATION_VERSION: u64 = 2;
+
+/// Sections of a [`Configuration`] holding field configurations.
+pub(crate) static FIELD_SECTIONS: [&str; 3] = ["coreConfig", "routerConfig", "channelConfig"];
+
+/// Upgrades a document from the version matching its index, plus one, to the next version.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), SVGError>;
+
+static MIGRATIONS: [Migration; 1] = [flatten_colour_settings];
+
+fn migration_error(code: &'static str, reason: String) -> SVGError {
+    SVGError::new(SVGErrorKind::MigrationError(reason)).with_code(code)
+}
+
+/// Moves the content of a nested object into its parent field configuration.
+fn flatten_nested(
+    field: &mut Map<String, Value>,
+    nested_key: &str,
+    path: &str,
+) -> Result<(), SVGError> {
+    let nested = match field.remove(nested_key) {
+        Some(Value::Object(nested)) => nested,
+        Some(_) => {
+            return Err(migration_error(
+                "invalid_nested_settings",
+                format!("\"{nested_key}\" must be an object in version 1 configurations."),
+            )
+            .with_key(path))
+        }
+        None => return Ok(()),
+    };
+
+    for (key, value) in nested {
+        if field.contains_key(&key) {
+            return Err(migration_error(
+                "duplicate_nested_setting",
+                format!("\"{key}\" is provided both inside and outside \"{nested_key}\"."),
+            )
+            .with_key(path));
+        }
+
+        field.insert(key, value);
+    }
+
+    Ok(())
+}
+
+/// Version 1 to 2: colour settings are no longer nested.
+fn flatten_colour_settings(document: &mut Map<String, Value>) -> Result<(), SVGError> {
+    for section in FIELD_SECTIONS {
+        let fields = match document.get_mut(section) {
+            Some(Value::Object(fields)) => fields,
+            // Partial documents, e.g. layers, can leave sections out
+            None => continue,
+            Some(_) => {
+                return Err(migration_error(
+                    "invalid_section",
+                    format!("\"{section}\" must be an object."),
+                )
+                .with_key(section))
+            }
+        };
+
+        for (key, field) in fields.iter_mut() {
+            let path = format!("{section}.{key}");
+
+            if let Value::Object(field) = field {
+                match field.get("type").and_then(Value::as_str) {
+                    Some("Fill" | "ColouredText") => {
+                        flatten_nested(field, "colourSettings", &path)?
+                    }
+                    Some("Routing") => flatten_nested(field, "loadColours", &path)?,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Upgrades a [`Configuration`] document to [`CONFIGURATION_VERSION`]. Documents can be partial, e.g. layers.
+///
+/// Fails with [`MigrationError`][`SVGErrorKind::MigrationError`] if the document is not an object, its version is
+/// not supported or it cannot be upgraded.
+pub fn migrate_configuration(document: Value) -> Result<Value, SVGError> {
+    migrate_document(document).map(Value::Object)
+}
+
+/// Upgrades a [`Configuration`] document, see [`migrate_configuration`], returning its fields.
+pub(crate) fn migrate_document(document: Value) -> Result<Map<String, Value>, SVGError> {
+    let mut document = match document {
+        Value::Object(document) => document,
+        _ => {
+            return Err(migration_error(
+                "invalid_document",
+                "A configuration must be a JSON object.".to_string(),
+            ))
+        }
+    };
+
+    let version = match document.get("version") {
+        None => 1,
+        Some(version) => match version.as_u64() {
+            Some(version) if version >= 1 => version,
+            _ => {
+                return Err(migration_error(
+                    "invalid_version",
+                    format!(
+                        "Invalid configuration version {version}, expected a positive integer."
+                    ),
+                )
+                .with_key("version"))
+            }
+        },
+    };
+
+    if version > CONFIGURATION_VERSION {
+        return Err(migration_error(
+            "unsupported_version",
+            format!(
+                "Configuration version {version} is newer than the latest supported version {CONFIGURATION_VERSION}. \
+                Please update manycore_svg."
+            ),
+        )
+        .with_key("version"));
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(&mut document)?;
+    }
+
+    document.insert("version".to_string(), Value::from(CONFIGURATION_VERSION));
+
+    Ok(document)
+}
+
+impl Configuration {
+    /// Deserialises a [`Configuration`] document of any supported version, see [`migrate_configuration`].
+    pub fn from_value(document: Value) -> Result<Configuration, SVGError> {
+        Ok(Configuration::deserialize(migrate_configuration(
+            document,
+        )?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{migrate_configuration, CONFIGURATION_VERSION};
+    use crate::{Configuration, FieldConfiguration, SVGErrorKind};
+
+    #[test]
+    fn migrates_configurations() {
+        let configuration = Configuration::from_value(json!({
+            "coreConfig": {
+                "@age": {
+                    "type": "Fill",
+                    "colourSettings": {
+                        "bounds": [30, 100, 200, 300],
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    }
+                }
+            },
+            "routerConfig": {},
+            "channelConfig": {
+                "@routingAlgorithm": {
+                    "type": "Routing",
+                    "algorithm": "RowFirst",
+                    "loadConfiguration": "Percentage",
+                    "loadColours": {
+                        "bounds": [25, 50, 75, 100],
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    },
+                    "display": "Load"
+                }
+            },
+            "coreFills": {},
+            "routerFills": {}
+        }))
+        .expect("Could not migrate version 1 configuration");
+
+        assert_eq!(configuration.version(), CONFIGURATION_VERSION);
+        match configuration.core_config().get("@age") {
+            Some(FieldConfiguration::Fill { colour_settings }) => {
+                assert_eq!(*colour_settings.bounds(), [30.0, 100.0, 200.0, 300.0])
+            }
+            other => panic!("Unexpected core age configuration {other:?}"),
+        }
+
+        // Current documents are left as they are, and round trip
+        let serialised = serde_json::to_value(&configuration).expect("Could not serialise");
+        assert_eq!(serialised["version"], json!(CONFIGURATION_VERSION));
+        assert_eq!(
+            migrate_configuration(serialised.clone()).expect("Could not migrate"),
+            serialised
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        for (document, key) in [
+            (json!({ "version": CONFIGURATION_VERSION + 1 }), "version"),
+            (json!({ "version": "two" }), "version"),
+            (
+                json!({
+                    "coreConfig": {
+                        "@age": {
+                            "type": "Fill",
+                            "bounds": [0, 0, 0, 0],
+                            "colourSettings": { "bounds": [0, 0, 0, 0] }
+                        }
+                    }
+                }),
+                "coreConfig.@age",
+            ),
+        ] {
+            let error = migrate_configuration(document).expect_err("Migration did not fail");
+
+            assert!(matches!(error.kind(), SVGErrorKind::MigrationError(_)));
+            assert_eq!(error.context().key().as_deref(), Some(key));
+        }
+
+        // Migration errors are not hidden behind serde
+        let error = serde_json::from_str::<Configuration>("{\"version\": 99}")
+            .expect_err("Unsupported version was accepted");
+        assert!(error
+            .to_string()
+            .contains("newer than the latest supported version"));
+    }
+}
@@ -0,0 +1,407 @@
+use serde_json::{json, Map, Value};
+
+use crate::{
+    tasks_group::{DEFAULT_TASK_FONT_SIZE, MAXIMUM_TASK_FONT_SIZE, MINIMUM_TASK_FONT_SIZE},
+    validation::CSS_COLOUR_NAMES,
+    FontSizeT, CONFIGURATION_VERSION, DEFAULT_ATTRIBUTE_FONT_SIZE, MAXIMUM_ATTRIBUTE_FONT_SIZE,
+    MINIMUM_ATTRIBUTE_FONT_SIZE,
+};
+
+#[cfg(doc)]
+use crate::{
+    BaseConfiguration, ColourSettings, Configuration, FieldConfiguration, RoutingConfiguration,
+};
+
+static SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/// Hexadecimal colours, as accepted by [`Configuration::validate`](crate::Configuration::validate).
+static HEX_COLOUR_PATTERN: &str = "^#([0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$";
+/// CSS colour functions, as accepted by [`Configuration::validate`](crate::Configuration::validate).
+static FUNCTIONAL_COLOUR_PATTERN: &str =
+    r"^\s*([rR][gG][bB][aA]?|[hH][sS][lL][aA]?)\s*\(.*\S.*\)\s*$";
+/// Routing algorithms supported by manycore_parser, as named in configurations.
+static ROUTING_ALGORITHMS: [&str; 3] = ["Observed", "RowFirst", "ColumnFirst"];
+
+/// Builds an object schema from its properties and required property names.
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Merges the properties and required names of object schemas, like `#[serde(flatten)]` does with fields.
+/// Their `anyOf` constraints are kept under `allOf`.
+fn flatten(schemas: &[Value]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut constraints = Vec::new();
+
+    for schema in schemas {
+        if let Some(schema_properties) = schema["properties"].as_object() {
+            properties.extend(schema_properties.clone());
+        }
+        if let Some(schema_required) = schema["required"].as_array() {
+            required.extend(schema_required.iter().cloned());
+        }
+        if let Some(any_of) = schema.get("anyOf") {
+            constraints.push(json!({ "anyOf": any_of }));
+        }
+    }
+
+    let mut ret = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    if !constraints.is_empty() {
+        ret["allOf"] = Value::Array(constraints);
+    }
+
+    ret
+}
+
+/// CSS colours: hexadecimal, functional or named. Named colours are listed in lowercase.
+fn colour() -> Value {
+    json!({
+        "type": "string",
+        "anyOf": [
+            { "pattern": HEX_COLOUR_PATTERN },
+            { "pattern": FUNCTIONAL_COLOUR_PATTERN },
+            { "enum": CSS_COLOUR_NAMES },
+        ],
+    })
+}
+
+fn font_size(default: FontSizeT, min: FontSizeT, max: FontSizeT) -> Value {
+    json!({
+        "type": "number",
+        "default": default,
+        "minimum": min,
+        "maximum": max,
+    })
+}
+
+/// Properties of [`ColourSettings`], flattened in the configurations that use it. Either `bounds` or `autoBounds`
+/// is required.
+fn colour_settings() -> Value {
+    let mut ret = object(
+        json!({
+            "bounds": {
+                "type": "array",
+                "items": { "type": "number" },
+                "minItems": 4,
+                "maxItems": 4,
+                "description": "Lower bound of each colour, in ascending order. Can be omitted with autoBounds.",
+            },
+            "colours": {
+                "type": "array",
+                "items": colour(),
+                "minItems": 4,
+                "maxItems": 4,
+            },
+            "autoBounds": {
+                "enum": ["Quantiles", "EqualWidth", "StandardDeviations"],
+            },
+            "scale": {
+                "oneOf": [
+                    { "enum": ["Linear", "Log10", "Sqrt"] },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "Piecewise": {
+                                "type": "array",
+                                "items": {
+                                    "type": "array",
+                                    "items": { "type": "number" },
+                                    "minItems": 2,
+                                    "maxItems": 2,
+                                },
+                            },
+                        },
+                        "required": ["Piecewise"],
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+        }),
+        &["colours"],
+    );
+    ret["anyOf"] = json!([{ "required": ["bounds"] }, { "required": ["autoBounds"] }]);
+
+    ret
+}
+
+/// Properties of [`NumberFormat`](crate::NumberFormat), flattened in the configurations that use it.
+fn number_format() -> Value {
+    object(
+        json!({
+            "unit": { "type": "string" },
+            "decimals": { "type": "integer", "minimum": 0 },
+            "siPrefix": { "type": "boolean" },
+            "thousandsSeparator": { "type": "string" },
+            "template": {
+                "type": "string",
+                "description": "%s is replaced with the formatted value, %% with %.",
+            },
+        }),
+        &[],
+    )
+}
+
+/// Schema of [`RoutingConfiguration`].
+fn routing_configuration() -> Value {
+    flatten(&[
+        object(
+            json!({
+                "algorithm": { "enum": ROUTING_ALGORITHMS },
+                "loadConfiguration": { "enum": ["Percentage", "Fraction"] },
+                "display": { "type": "string" },
+            }),
+            &["algorithm", "loadConfiguration", "display"],
+        ),
+        colour_settings(),
+        number_format(),
+    ])
+}
+
+/// Schema of a [`FieldConfiguration`] variant, tagged by `type`.
+fn variant(type_name: &str, schemas: &[Value]) -> Value {
+    let mut parts = vec![object(json!({ "type": { "const": type_name } }), &["type"])];
+    parts.extend_from_slice(schemas);
+
+    let mut ret = flatten(&parts);
+    ret["title"] = json!(type_name);
+
+    ret
+}
+
+/// Schema of [`FieldConfiguration`].
+fn field_configuration() -> Value {
+    let display = object(json!({ "display": { "type": "string" } }), &["display"]);
+
+    json!({
+        "oneOf": [
+            variant(
+                "Text",
+                &[
+                    display.clone(),
+                    object(json!({ "colour": colour() }), &[]),
+                    number_format(),
+                ],
+            ),
+            variant(
+                "ColouredText",
+                &[display, colour_settings(), number_format()],
+            ),
+            variant("Fill", &[colour_settings()]),
+            variant(
+                "Coordinates",
+                &[object(
+                    json!({ "orientation": { "enum": ["T", "B"] } }),
+                    &["orientation"],
+                )],
+            ),
+            variant("Routing", &[routing_configuration()]),
+            variant(
+                "Boolean",
+                &[object(json!({ "value": { "type": "boolean" } }), &["value"])],
+            ),
+        ],
+    })
+}
+
+/// Generates the JSON Schema of [`Configuration`] files.
+///
+/// [`FieldConfiguration`], [`ColourSettings`] and [`RoutingConfiguration`] are provided under `$defs`.
+pub fn configuration_schema() -> Value {
+    let field_configurations = json!({
+        "type": "object",
+        "additionalProperties": { "$ref": "#/$defs/FieldConfiguration" },
+    });
+    let fills = json!({
+        "type": "object",
+        "propertyNames": { "pattern": "^[0-9]+$" },
+        "additionalProperties": colour(),
+    });
+
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "Configuration",
+        "type": "object",
+        "properties": {
+            "version": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": CONFIGURATION_VERSION,
+                "default": CONFIGURATION_VERSION,
+                "description": "Format version. Older configurations are migrated, unversioned ones are version 1.",
+            },
+            "coreConfig": field_configurations,
+            "routerConfig": field_configurations,
+            "channelConfig": field_configurations,
+            "computedAttributes": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "string",
+                    "description": "Arithmetic expression over the element's attributes, e.g. @temperature - @ambient.",
+                },
+            },
+            "coreFills": fills,
+            "routerFills": fills,
+        },
+        "required": ["coreConfig", "routerConfig", "channelConfig", "coreFills", "routerFills"],
+        "$defs": {
+            "FieldConfiguration": field_configuration(),
+            "ColourSettings": colour_settings(),
+            "RoutingConfiguration": routing_configuration(),
+        },
+    })
+}
+
+/// Generates the JSON Schema of [`BaseConfiguration`] files, including font size limits.
+pub fn base_configuration_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "BaseConfiguration",
+        "type": "object",
+        "properties": {
+            "attribute_font_size": font_size(
+                DEFAULT_ATTRIBUTE_FONT_SIZE,
+                MINIMUM_ATTRIBUTE_FONT_SIZE,
+                MAXIMUM_ATTRIBUTE_FONT_SIZE,
+            ),
+            "task_font_size": font_size(
+                DEFAULT_TASK_FONT_SIZE,
+                MINIMUM_TASK_FONT_SIZE,
+                MAXIMUM_TASK_FONT_SIZE,
+            ),
+        },
+        "required": ["attribute_font_size", "task_font_size"],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use manycore_parser::RoutingAlgorithms;
+    use serde_json::{json, Value};
+
+    use super::{base_configuration_schema, configuration_schema, ROUTING_ALGORITHMS};
+    use crate::{tasks_group::MAXIMUM_TASK_FONT_SIZE, Configuration, MINIMUM_ATTRIBUTE_FONT_SIZE};
+
+    #[test]
+    fn describes_configurations() {
+        let schema = configuration_schema();
+
+        let variants = schema["$defs"]["FieldConfiguration"]["oneOf"]
+            .as_array()
+            .expect("FieldConfiguration has no variants");
+        let variant = |name: &str| {
+            variants
+                .iter()
+                .find(|variant| variant["title"] == name)
+                .unwrap_or_else(|| panic!("No {name} variant"))
+        };
+
+        assert_eq!(variants.len(), 6);
+        assert_eq!(
+            variant("Coordinates")["properties"]["orientation"]["enum"],
+            json!(["T", "B"])
+        );
+        assert_eq!(
+            variant("Routing")["properties"]["loadConfiguration"]["enum"],
+            json!(["Percentage", "Fraction"])
+        );
+        assert_eq!(variant("Fill")["required"], json!(["type", "colours"]));
+        assert!(schema["required"]
+            .as_array()
+            .expect("Configuration has no required fields")
+            .contains(&json!("coreFills")));
+
+        let base_schema = base_configuration_schema();
+        assert_eq!(
+            base_schema["properties"]["attribute_font_size"]["minimum"],
+            json!(MINIMUM_ATTRIBUTE_FONT_SIZE)
+        );
+        assert_eq!(
+            base_schema["properties"]["task_font_size"]["maximum"],
+            json!(MAXIMUM_TASK_FONT_SIZE)
+        );
+    }
+
+    #[test]
+    fn accepts_configuration_fixtures() {
+        let validator = jsonschema::validator_for(&configuration_schema())
+            .expect("Invalid configuration schema");
+
+        for entry in fs::read_dir("tests").expect("Could not read tests directory") {
+            let path = entry.expect("Could not read tests directory entry").path();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            if !(name.starts_with("conf") && name.ends_with(".json")) {
+                continue;
+            }
+
+            let contents = fs::read_to_string(&path)
+                .unwrap_or_else(|_| panic!("Could not read \"{}\"", path.display()));
+            let document: Value = serde_json::from_str(&contents)
+                .unwrap_or_else(|_| panic!("Could not parse \"{}\"", path.display()));
+            let configuration: Configuration = serde_json::from_value(document.clone())
+                .unwrap_or_else(|_| panic!("Could not deserialise \"{}\"", path.display()));
+            let serialised =
+                serde_json::to_value(&configuration).expect("Could not serialise configuration");
+
+            for instance in [&document, &serialised] {
+                let errors: Vec<_> = validator
+                    .iter_errors(instance)
+                    .map(|error| error.to_string())
+                    .collect();
+
+                assert!(errors.is_empty(), "{}: {errors:?}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn describes_colour_settings() {
+        let validator = jsonschema::validator_for(&configuration_schema())
+            .expect("Invalid configuration schema");
+        let configuration = |fill: Value| {
+            json!({
+                "coreConfig": { "@age": fill },
+                "routerConfig": {},
+                "channelConfig": {},
+                "coreFills": {},
+                "routerFills": {}
+            })
+        };
+
+        assert!(validator.is_valid(&configuration(json!({
+            "type": "Fill",
+            "autoBounds": "Quantiles",
+            "colours": ["green", "rgb(234, 179, 8)", "hsla(25 95% 53% / 1)", "#dc2626"]
+        }))));
+        // Unknown colour
+        assert!(!validator.is_valid(&configuration(json!({
+            "type": "Fill",
+            "bounds": [0, 1, 2, 3],
+            "colours": ["greenish", "#eab308", "#f97316", "#dc2626"]
+        }))));
+        // Neither bounds nor autoBounds
+        assert!(!validator.is_valid(&configuration(json!({
+            "type": "Fill",
+            "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+        }))));
+
+        for algorithm in ROUTING_ALGORITHMS {
+            assert!(
+                serde_json::from_value::<RoutingAlgorithms>(json!(algorithm)).is_ok(),
+                "{algorithm} is not a routing algorithm"
+            );
+        }
+    }
+}
@@ -0,0 +1,566 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+use manycore_parser::ManycoreSystem;
+
+use crate::{
+    watch::write_atomically, BaseConfiguration, Configuration, SVGError, SVGErrorKind, SVG,
+};
+
+/// Largest request body the server accepts, in bytes.
+static MAX_BODY_LENGTH: usize = 64 * 1024 * 1024;
+/// How long writing to a client may block before the client is considered gone.
+static WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Makes every upload file name unique, so concurrent uploads to the same session don't overwrite each other.
+static UPLOAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// State kept for each client session.
+struct Session {
+    manycore: ManycoreSystem,
+    svg: SVG,
+    base_configuration: BaseConfiguration,
+}
+
+/// An open Server-Sent Events stream.
+type Subscriber = Arc<Mutex<TcpStream>>;
+
+/// A session and its subscribers. Each is locked on its own, so rendering a session neither blocks other sessions
+/// nor waits on slow subscribers.
+#[derive(Clone)]
+struct SessionHandle {
+    session: Arc<Mutex<Session>>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+type Sessions = Arc<Mutex<BTreeMap<String, SessionHandle>>>;
+
+/// Minimal HTTP/1.1 server that keeps an [`SVG`] per session. Routes:
+/// * `PUT /sessions/{id}/system`: Uploads a [`ManycoreSystem`] XML. Responds with the base [`SVG`].
+/// * `PUT /sessions/{id}/base-configuration`: Sets the session's [`BaseConfiguration`] JSON, used from the next
+///   render on. Responds with the stored [`BaseConfiguration`].
+/// * `POST /sessions/{id}/configuration`: Renders a [`Configuration`] JSON. Responds with the [`UpdateResult`](crate::UpdateResult)
+///   JSON, which is also pushed to every subscriber.
+/// * `GET /sessions/{id}/events`: Server-Sent Events stream of [`UpdateResult`](crate::UpdateResult)s.
+/// * `GET /sessions/{id}/svg`: The session's current [`SVG`].
+pub struct RenderServer {
+    listener: TcpListener,
+    sessions: Sessions,
+    uploads: PathBuf,
+}
+
+/// A parsed HTTP request.
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+/// An HTTP response, always closing the connection.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: String) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: message,
+        }
+    }
+
+    /// Describes an [`SVGError`] as JSON, so clients can act on its code and context.
+    fn svg_error(status: &'static str, error: SVGError) -> Self {
+        match serde_json::to_string(&error) {
+            Ok(body) => Self {
+                status,
+                content_type: "application/json",
+                body,
+            },
+            Err(_) => Self::error(status, error.to_string()),
+        }
+    }
+
+    fn bad_request(error: SVGError) -> Self {
+        Self::svg_error("400 Bad Request", error)
+    }
+
+    fn internal_error(error: SVGError) -> Self {
+        Self::svg_error("500 Internal Server Error", error)
+    }
+
+    fn not_found() -> Self {
+        Self::error("404 Not Found", "Not Found".to_string())
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> Result<(), SVGError> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+
+        Ok(stream.flush()?)
+    }
+}
+
+/// Error thrown when a request can't be understood.
+fn malformed_request(reason: &str) -> SVGError {
+    SVGError::new(SVGErrorKind::DataConversionError(format!(
+        "Malformed HTTP request: {reason}"
+    )))
+    .with_code("malformed_request")
+}
+
+/// Reads a request line, its headers and body from the stream.
+fn read_request(stream: &TcpStream) -> Result<Request, SVGError> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| malformed_request("missing method"))?
+        .to_string();
+    let path = parts
+        .next()
+        .ok_or_else(|| malformed_request("missing path"))?
+        .to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| malformed_request("invalid Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_LENGTH {
+        return Err(malformed_request("body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request { method, path, body })
+}
+
+/// Session IDs end up in file names, only allow a safe subset of characters.
+fn valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Locks a mutex. A panicking handler doesn't invalidate other sessions.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Looks up a session. The sessions map is only locked for the lookup.
+fn session(id: &str, sessions: &Sessions) -> Option<SessionHandle> {
+    lock(sessions).get(id).cloned()
+}
+
+/// Pushes an event to every subscriber, dropping those that went away.
+/// Subscribers are written to without holding the sessions map or the session.
+fn publish(subscribers: &Mutex<Vec<Subscriber>>, event: &str) {
+    let current = lock(subscribers).clone();
+
+    let gone: Vec<Subscriber> = current
+        .into_iter()
+        .filter(|subscriber| {
+            let mut stream = lock(subscriber);
+            stream
+                .write_all(event.as_bytes())
+                .and_then(|_| stream.flush())
+                .is_err()
+        })
+        .collect();
+
+    if !gone.is_empty() {
+        lock(subscribers)
+            .retain(|subscriber| !gone.iter().any(|gone| Arc::ptr_eq(subscriber, gone)));
+    }
+}
+
+/// Handles `PUT /sessions/{id}/system`.
+fn upload_system(id: &str, body: Vec<u8>, sessions: &Sessions, uploads: &Path) -> Response {
+    let result = String::from_utf8(body)
+        .map_err(|error| {
+            SVGError::new(SVGErrorKind::DataConversionError(error.to_string()))
+                .with_code("invalid_utf8_upload")
+        })
+        .and_then(|xml| {
+            let path = uploads.join(format!(
+                "{id}-{}.xml",
+                UPLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            write_atomically(&path, &xml)?;
+
+            // The upload is only needed for parsing
+            let manycore = path
+                .to_str()
+                .ok_or_else(|| malformed_request("invalid upload path"))
+                .and_then(|path| Ok(ManycoreSystem::parse_file(path)?));
+            let _ = fs::remove_file(&path);
+            let manycore = manycore?;
+            let svg = SVG::try_from(&manycore)?;
+
+            Ok((manycore, svg))
+        });
+
+    let (manycore, svg) = match result {
+        Ok(session) => session,
+        Err(error) => return Response::bad_request(error),
+    };
+
+    let svg_string = match String::try_from(&svg) {
+        Ok(svg_string) => svg_string,
+        Err(error) => return Response::internal_error(error.into()),
+    };
+
+    let session = Arc::new(Mutex::new(Session {
+        manycore,
+        svg,
+        base_configuration: BaseConfiguration::default(),
+    }));
+
+    let mut sessions = lock(sessions);
+    match sessions.get_mut(id) {
+        // Keep subscribers of a previous upload, they'll receive updates for the new system.
+        Some(handle) => handle.session = session,
+        None => {
+            sessions.insert(
+                id.to_string(),
+                SessionHandle {
+                    session,
+                    subscribers: Arc::default(),
+                },
+            );
+        }
+    }
+
+    Response::ok("image/svg+xml", svg_string)
+}
+
+/// Handles `PUT /sessions/{id}/base-configuration`.
+fn set_base_configuration(id: &str, body: Vec<u8>, sessions: &Sessions) -> Response {
+    let base_configuration = match serde_json::from_slice::<BaseConfiguration>(&body) {
+        Ok(base_configuration) => base_configuration,
+        Err(error) => return Response::bad_request(error.into()),
+    };
+
+    let Some(handle) = session(id, sessions) else {
+        return Response::not_found();
+    };
+    lock(&handle.session).base_configuration = base_configuration;
+
+    match serde_json::to_string(&base_configuration) {
+        Ok(json) => Response::ok("application/json", json),
+        Err(error) => Response::internal_error(error.into()),
+    }
+}
+
+/// Handles `POST /sessions/{id}/configuration`.
+fn render(id: &str, body: Vec<u8>, sessions: &Sessions) -> Response {
+    // Parsed as a value first, so that migration errors are reported as such
+    let configuration = serde_json::from_slice(&body)
+        .map_err(SVGError::from)
+        .and_then(Configuration::from_value);
+    let mut configuration = match configuration {
+        Ok(configuration) => configuration,
+        Err(error) => return Response::bad_request(error),
+    };
+
+    let Some(handle) = session(id, sessions) else {
+        return Response::not_found();
+    };
+
+    let update = {
+        let mut session = lock(&handle.session);
+        let session = &mut *session;
+
+        session
+            .svg
+            .update_configurable_information(
+                &mut session.manycore,
+                &mut configuration,
+                &session.base_configuration,
+            )
+            .and_then(|update| Ok(serde_json::to_string(&update)?))
+    };
+
+    match update {
+        Ok(json) => {
+            publish(&handle.subscribers, &format!("data: {json}\n\n"));
+
+            Response::ok("application/json", json)
+        }
+        Err(error) => Response::bad_request(error),
+    }
+}
+
+/// Handles `GET /sessions/{id}/events`. The stream is kept open and registered as a subscriber.
+fn subscribe(id: &str, mut stream: TcpStream, sessions: &Sessions) -> Result<(), SVGError> {
+    let Some(handle) = session(id, sessions) else {
+        return Response::not_found().write_to(&mut stream);
+    };
+
+    let subscriber = Arc::new(Mutex::new(stream));
+    // Register before writing the headers, so no update is missed once the client sees them.
+    // Events wait for the stream's lock, so they can't get ahead of the headers.
+    let mut stream = lock(&subscriber);
+    lock(&handle.subscribers).push(Arc::clone(&subscriber));
+
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+
+    Ok(stream.flush()?)
+}
+
+/// Handles `GET /sessions/{id}/svg`.
+fn current_svg(id: &str, sessions: &Sessions) -> Response {
+    match session(id, sessions).map(|handle| String::try_from(&lock(&handle.session).svg)) {
+        Some(Ok(svg)) => Response::ok("image/svg+xml", svg),
+        Some(Err(error)) => Response::internal_error(error.into()),
+        None => Response::not_found(),
+    }
+}
+
+/// Routes a single connection.
+fn handle_connection(
+    mut stream: TcpStream,
+    sessions: &Sessions,
+    uploads: &Path,
+) -> Result<(), SVGError> {
+    // Don't let a client that stopped reading hold a thread, or a session's subscribers, forever
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(error) => return Response::bad_request(error).write_to(&mut stream),
+    };
+
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let response = match (request.method.as_str(), segments.as_slice()) {
+        (_, ["sessions", id, _]) if !valid_session_id(id) => Response::not_found(),
+        ("PUT", ["sessions", id, "system"]) => upload_system(id, request.body, sessions, uploads),
+        ("PUT", ["sessions", id, "base-configuration"]) => {
+            set_base_configuration(id, request.body, sessions)
+        }
+        ("POST", ["sessions", id, "configuration"]) => render(id, request.body, sessions),
+        ("GET", ["sessions", id, "events"]) => return subscribe(id, stream, sessions),
+        ("GET", ["sessions", id, "svg"]) => current_svg(id, sessions),
+        _ => Response::not_found(),
+    };
+
+    response.write_to(&mut stream)
+}
+
+impl RenderServer {
+    /// Binds a new [`RenderServer`] to the given address. Use port 0 to let the OS pick one.
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, SVGError> {
+        let listener = TcpListener::bind(address)?;
+
+        // Uploaded systems are parsed from disk
+        let uploads = std::env::temp_dir().join(format!(
+            "manycore_svg_server_{}_{}",
+            std::process::id(),
+            listener.local_addr()?.port()
+        ));
+        fs::create_dir_all(&uploads)?;
+
+        Ok(Self {
+            listener,
+            sessions: Arc::new(Mutex::new(BTreeMap::new())),
+            uploads,
+        })
+    }
+
+    /// Returns the address the [`RenderServer`] is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, SVGError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accepts connections until the listener fails. Every connection is handled on its own thread.
+    pub fn serve(&self) -> Result<(), SVGError> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let sessions = Arc::clone(&self.sessions);
+            let uploads = self.uploads.clone();
+
+            thread::spawn(move || {
+                // Nobody to report to, the client is gone
+                let _ = handle_connection(stream, &sessions, &uploads);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for RenderServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.uploads);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use super::RenderServer;
+
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(address).expect("Could not connect to server");
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .expect("Could not send request");
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Could not read response");
+
+        response
+    }
+
+    #[test]
+    fn pushes_updates_to_subscribers() {
+        let server = RenderServer::bind("127.0.0.1:0").expect("Could not bind server");
+        let address = server.local_addr().expect("Could not get server address");
+        thread::spawn(move || server.serve());
+
+        let xml = std::fs::read_to_string("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let configuration = std::fs::read_to_string("tests/conf2.json")
+            .expect("Could not read \"tests/conf2.json\"");
+
+        assert!(
+            request(address, "POST", "/sessions/a/configuration", &configuration)
+                .starts_with("HTTP/1.1 404")
+        );
+
+        let upload = request(address, "PUT", "/sessions/a/system", &xml);
+        assert!(upload.starts_with("HTTP/1.1 200"));
+        assert!(upload.contains("<svg"));
+
+        // Subscribe
+        let events = TcpStream::connect(address).expect("Could not connect to server");
+        events
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .expect("Could not set timeout");
+        let mut events = BufReader::new(events);
+        events
+            .get_mut()
+            .write_all(b"GET /sessions/a/events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .expect("Could not subscribe");
+        loop {
+            let mut line = String::new();
+            events.read_line(&mut line).expect("Could not read headers");
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        let render = request(address, "POST", "/sessions/a/configuration", &configuration);
+        assert!(render.starts_with("HTTP/1.1 200"));
+        assert!(render.contains("\"informationGroup\""));
+
+        let mut event = String::new();
+        events.read_line(&mut event).expect("Could not read event");
+        assert!(event.starts_with("data: {"));
+        assert!(event.contains("\"viewBox\""));
+    }
+
+    #[test]
+    fn sets_base_configuration() {
+        let server = RenderServer::bind("127.0.0.1:0").expect("Could not bind server");
+        let address = server.local_addr().expect("Could not get server address");
+        thread::spawn(move || server.serve());
+
+        let xml = std::fs::read_to_string("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let configuration = std::fs::read_to_string("tests/conf2.json")
+            .expect("Could not read \"tests/conf2.json\"");
+        let base_configuration = r#"{"attribute_font_size":20.0,"task_font_size":30.0}"#;
+
+        assert!(request(
+            address,
+            "PUT",
+            "/sessions/b/base-configuration",
+            base_configuration
+        )
+        .starts_with("HTTP/1.1 404"));
+        assert!(request(address, "PUT", "/sessions/b/system", &xml).starts_with("HTTP/1.1 200"));
+        assert!(
+            request(address, "PUT", "/sessions/b/base-configuration", "{}")
+                .starts_with("HTTP/1.1 400")
+        );
+
+        let update = request(
+            address,
+            "PUT",
+            "/sessions/b/base-configuration",
+            base_configuration,
+        );
+        assert!(update.starts_with("HTTP/1.1 200"));
+        assert!(update.ends_with(base_configuration));
+
+        // A different base configuration regenerates the whole document
+        let render = request(address, "POST", "/sessions/b/configuration", &configuration);
+        assert!(render.starts_with("HTTP/1.1 200"));
+        assert!(render.contains("\"svg\":\"<svg"));
+        assert!(render.contains("font-size=\\\"20px\\\""));
+    }
+}
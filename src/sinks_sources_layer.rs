@@ -6,7 +6,11 @@ use manycore_parser::{BorderEntry, EdgePosition, SinkSourceDirection, SystemDime
 use serde::Serialize;
 
 use crate::{
-    style::{DEFAULT_FILL, EDGE_DATA_CLASS_NAME}, CoordinateT, Offsets, ProcessedBaseConfiguration, TextInformation, CHAR_H_PADDING, HALF_ROUTER_OFFSET, MARKER_HEIGHT, ROUTER_OFFSET, SIDE_LENGTH, UNSUPPORTED_PLATFORM, USE_FREEFORM_CLIP_PATH
+    namespace::IdNamespace,
+    style::{DEFAULT_FILL, EDGE_DATA_CLASS_NAME},
+    CoordinateT, Offsets, ProcessedBaseConfiguration, TextInformation, CHAR_H_PADDING,
+    FREEFORM_CLIP_PATH_ID, HALF_ROUTER_OFFSET, MARKER_HEIGHT, ROUTER_OFFSET, SIDE_LENGTH,
+    UNSUPPORTED_PLATFORM,
 };
 
 // Side lengths
@@ -241,9 +245,9 @@ impl SinkSource {
 #[derive(Serialize)]
 pub(crate) struct SinksSourcesGroup {
     #[serde(rename = "@id")]
-    id: &'static str,
+    id: String,
     #[serde(rename = "@clip-path")]
-    clip_path: &'static str,
+    clip_path: String,
     #[serde(rename = "@class")]
     class: &'static str,
     g: Vec<SinkSource>,
@@ -252,13 +256,19 @@ pub(crate) struct SinksSourcesGroup {
 impl SinksSourcesGroup {
     /// Generates a new [`SinksSourcesGroup`] instance with capacity for all border router,
     /// calculated fom the number of rows and columns.
-    pub(crate) fn new(rows: &SystemDimensionsT, columns: &SystemDimensionsT) -> Self {
+    pub(crate) fn new(
+        rows: &SystemDimensionsT,
+        columns: &SystemDimensionsT,
+        namespace: &IdNamespace,
+    ) -> Self {
         Self {
-            id: SINK_SOURCES_ID,
+            id: namespace.id(SINK_SOURCES_ID),
             class: EDGE_DATA_CLASS_NAME,
             // Formula worksout because we ignore the corners. Obv, only on 2D matrix.
-            g: Vec::with_capacity(usize::try_from((rows + columns) * 2).expect(UNSUPPORTED_PLATFORM)),
-            clip_path: USE_FREEFORM_CLIP_PATH,
+            g: Vec::with_capacity(
+                usize::try_from((rows + columns) * 2).expect(UNSUPPORTED_PLATFORM),
+            ),
+            clip_path: namespace.url(FREEFORM_CLIP_PATH_ID),
         }
     }
 
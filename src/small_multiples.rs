@@ -0,0 +1,295 @@
+use std::cmp::{max, min};
+
+use manycore_parser::ManycoreSystem;
+use quick_xml::DeError;
+use serde::Serialize;
+
+use crate::{
+    namespace::IdNamespace, style::Style, BaseConfiguration, Configuration, CoordinateT,
+    DefsContent, FontSizeT, Root, SVGError, SVGErrorKind, TextInformation, ViewBox, SVG,
+};
+
+static PANEL_GAP: CoordinateT = 100;
+static TITLE_FONT_SIZE: FontSizeT = 48.0;
+static TITLE_HEIGHT: CoordinateT = 80;
+
+/// A panel of a [`SmallMultiples`] render.
+/// * `title`: Displayed above the panel.
+/// * `system`: Index of the [`ManycoreSystem`] to render. Multiple panels can share the same system.
+/// * `configuration`: The [`Configuration`] to render the system with.
+pub struct SmallMultiplesPanel {
+    title: String,
+    system: usize,
+    configuration: Configuration,
+}
+
+impl SmallMultiplesPanel {
+    /// Generates a new [`SmallMultiplesPanel`] instance from the given parameters.
+    pub fn new(title: String, system: usize, configuration: Configuration) -> Self {
+        Self {
+            title,
+            system,
+            configuration,
+        }
+    }
+}
+
+/// A rendered [`SmallMultiplesPanel`] with its position in the grid.
+struct RenderedPanel {
+    title: TextInformation,
+    transform: String,
+    svg: SVG,
+}
+
+/// Side-by-side layout of multiple [`SVG`]s in a single document.
+/// Panels are laid out in a grid, each cell having the same size so that panels are rendered at the same scale.
+/// Every panel's element IDs, clip paths and CSS rules are namespaced, so panels do not interfere with each other.
+pub struct SmallMultiples {
+    view_box: ViewBox,
+    panels: Vec<RenderedPanel>,
+}
+
+/// Object representation of the SVG `<g>` that translates a panel's content into its cell.
+#[derive(Serialize)]
+struct PanelContent<'a> {
+    #[serde(rename = "@transform")]
+    transform: &'a String,
+    defs: DefsContent<'a>,
+    style: &'a Style,
+    #[serde(rename = "g")]
+    root: &'a Root,
+}
+
+/// Object representation of the SVG `<g>` that wraps a panel and its title.
+#[derive(Serialize)]
+struct PanelGroup<'a> {
+    #[serde(rename = "text")]
+    title: &'a TextInformation,
+    #[serde(rename = "g")]
+    content: PanelContent<'a>,
+}
+
+/// Object representation of a [`SmallMultiples`] document.
+#[derive(Serialize)]
+#[serde(rename = "svg")]
+struct SmallMultiplesDocument<'a> {
+    #[serde(rename = "@xmlns:svg")]
+    xmlns_svg: &'static str,
+    #[serde(rename = "@xmlns")]
+    xmlns: &'static str,
+    #[serde(rename = "@class")]
+    class: &'static str,
+    #[serde(rename = "@viewBox")]
+    view_box: &'a ViewBox,
+    #[serde(rename = "g")]
+    panels: Vec<PanelGroup<'a>>,
+}
+
+/// Error thrown when a [`SmallMultiplesPanel`] references a [`ManycoreSystem`] that was not provided.
+fn missing_system(panel: usize, system: usize, number_of_systems: usize) -> SVGError {
+    SVGError::new(SVGErrorKind::GenerationError(format!(
+        "Panel {panel} references system {system}, but only {number_of_systems} systems were provided."
+    )))
+    .with_code("missing_system")
+}
+
+/// Error thrown when a [`SmallMultiples`] render is requested without any [`SmallMultiplesPanel`].
+fn no_panels() -> SVGError {
+    SVGError::new(SVGErrorKind::GenerationError(
+        "At least one panel is required to generate small multiples.".to_string(),
+    ))
+    .with_code("no_panels")
+}
+
+impl SmallMultiples {
+    /// Renders the given panels and lays them out in a grid with the requested number of columns.
+    /// At least one panel is required.
+    pub fn new(
+        systems: &mut [ManycoreSystem],
+        panels: Vec<SmallMultiplesPanel>,
+        columns: usize,
+        base_configuration: &BaseConfiguration,
+    ) -> Result<Self, SVGError> {
+        if panels.is_empty() {
+            return Err(no_panels());
+        }
+
+        let number_of_systems = systems.len();
+        let mut svgs = Vec::with_capacity(panels.len());
+        let mut titles = Vec::with_capacity(panels.len());
+
+        for (i, panel) in panels.into_iter().enumerate() {
+            let SmallMultiplesPanel {
+                title,
+                system,
+                mut configuration,
+            } = panel;
+
+            let manycore = systems
+                .get_mut(system)
+                .ok_or_else(|| missing_system(i, system, number_of_systems))?;
+
+            let mut svg = SVG::try_from_manycore_with_base_config(
+                manycore,
+                base_configuration,
+                IdNamespace::new(format!("p{i}-")),
+            )?;
+            svg.update_configurable_information(manycore, &mut configuration, base_configuration)?;
+
+            svgs.push(svg);
+            titles.push(title);
+        }
+
+        // Every cell is as big as the biggest panel
+        let cell_width = svgs.iter().map(|svg| svg.view_box.width).max().unwrap_or(0);
+        let cell_height = svgs
+            .iter()
+            .map(|svg| svg.view_box.height)
+            .max()
+            .unwrap_or(0);
+
+        let columns = max(min(columns, svgs.len()), 1);
+        let rows = svgs.len().div_ceil(columns);
+
+        let mut rendered = Vec::with_capacity(svgs.len());
+        for (i, (svg, title)) in svgs.into_iter().zip(titles).enumerate() {
+            let cell_x = CoordinateT::try_from(i % columns)?
+                .saturating_mul(cell_width.saturating_add(PANEL_GAP));
+            let cell_y = CoordinateT::try_from(i / columns)?.saturating_mul(
+                cell_height
+                    .saturating_add(TITLE_HEIGHT)
+                    .saturating_add(PANEL_GAP),
+            );
+
+            // Centre the panel in its cell
+            let translate_x = cell_x
+                .saturating_add(cell_width.saturating_sub(svg.view_box.width) / 2)
+                .saturating_sub(svg.view_box.x);
+            let translate_y = cell_y
+                .saturating_add(TITLE_HEIGHT)
+                .saturating_add(cell_height.saturating_sub(svg.view_box.height) / 2)
+                .saturating_sub(svg.view_box.y);
+
+            rendered.push(RenderedPanel {
+                title: TextInformation::new(
+                    cell_x.saturating_add(cell_width / 2),
+                    cell_y,
+                    TITLE_FONT_SIZE,
+                    "middle",
+                    "text-before-edge",
+                    None,
+                    None,
+                    title,
+                ),
+                transform: format!("translate({translate_x} {translate_y})"),
+                svg,
+            });
+        }
+
+        let columns_coord = CoordinateT::try_from(columns)?;
+        let rows_coord = CoordinateT::try_from(rows)?;
+
+        Ok(Self {
+            view_box: ViewBox {
+                x: 0,
+                y: 0,
+                width: columns_coord
+                    .saturating_mul(cell_width)
+                    .saturating_add(columns_coord.saturating_sub(1).saturating_mul(PANEL_GAP)),
+                height: rows_coord
+                    .saturating_mul(cell_height.saturating_add(TITLE_HEIGHT))
+                    .saturating_add(rows_coord.saturating_sub(1).saturating_mul(PANEL_GAP)),
+            },
+            panels: rendered,
+        })
+    }
+
+    /// Returns the rendered panels, in the order they were provided.
+    pub fn panels(&self) -> impl Iterator<Item = &SVG> {
+        self.panels.iter().map(|panel| &panel.svg)
+    }
+}
+
+impl TryFrom<&SmallMultiples> for String {
+    type Error = DeError;
+
+    fn try_from(small_multiples: &SmallMultiples) -> Result<Self, Self::Error> {
+        let document = SmallMultiplesDocument {
+            xmlns_svg: "http://www.w3.org/2000/svg",
+            xmlns: "http://www.w3.org/2000/svg",
+            class: "mx-auto",
+            view_box: &small_multiples.view_box,
+            panels: small_multiples
+                .panels
+                .iter()
+                .map(|panel| PanelGroup {
+                    title: &panel.title,
+                    content: PanelContent {
+                        transform: &panel.transform,
+                        defs: panel
+                            .svg
+                            .defs
+                            .content(&panel.svg.root.processing_group, &panel.svg.namespace),
+                        style: &panel.svg.style,
+                        root: &panel.svg.root,
+                    },
+                })
+                .collect(),
+        };
+
+        let mut buf = String::new();
+        let mut serialiser = quick_xml::se::Serializer::new(&mut buf);
+        serialiser.indent(' ', 4);
+        serialiser.set_quote_level(quick_xml::se::QuoteLevel::Minimal);
+
+        document.serialize(serialiser)?;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::{SmallMultiples, SmallMultiplesPanel};
+    use crate::{test_utils::read_configuration, BaseConfiguration};
+
+    #[test]
+    fn panels_are_namespaced() {
+        let mut systems = vec![ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"")];
+
+        let panels = vec![
+            SmallMultiplesPanel::new("A".to_string(), 0, read_configuration("tests/conf2.json")),
+            SmallMultiplesPanel::new("B".to_string(), 0, read_configuration("tests/conf4.json")),
+        ];
+
+        let small_multiples =
+            SmallMultiples::new(&mut systems, panels, 2, &BaseConfiguration::default())
+                .expect("Could not generate small multiples.");
+
+        let res = String::try_from(&small_multiples).expect("Could not convert to string");
+
+        assert_eq!(small_multiples.panels().count(), 2);
+        assert!(!res.contains("id=\"mainGroup\""));
+        assert_eq!(res.matches("id=\"p0-mainGroup\"").count(), 1);
+        assert_eq!(res.matches("id=\"p1-mainGroup\"").count(), 1);
+        assert_eq!(res.matches("id=\"p0-c0\"").count(), 1);
+        assert_eq!(res.matches("id=\"p1-c0\"").count(), 1);
+        assert!(res.contains("clip-path=\"url(#p1-crop)\""));
+        assert!(res.contains("clip-path=\"url(#p1-clip-c-0)\""));
+        assert!(!res.contains("url(#arrowHead)"));
+    }
+
+    #[test]
+    fn rejects_empty_panels() {
+        let mut systems = vec![ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"")];
+
+        let error = SmallMultiples::new(&mut systems, Vec::new(), 2, &BaseConfiguration::default())
+            .err()
+            .expect("Empty panels were accepted");
+        assert_eq!(error.code(), "no_panels");
+    }
+}
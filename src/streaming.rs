@@ -0,0 +1,433 @@
+use std::io::Write;
+
+use quick_xml::se::{QuoteLevel, Serializer};
+use serde::Serialize;
+
+use crate::{Root, SVGError, ViewBox, SVG};
+
+/// Indentation of each nesting level, as in [`String::try_from`].
+static INDENT: &str = "    ";
+/// Serialised output is handed to the writer in chunks of at least this many bytes.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Attributes of the `<svg>` element, serialised without its children. Renamed like the fields of [`SVG`].
+#[derive(Serialize)]
+struct SVGShell<'a> {
+    #[serde(rename = "@xmlns:svg")]
+    xmlns_svg: &'a str,
+    #[serde(rename = "@xmlns")]
+    xmlns: &'a str,
+    #[serde(rename = "@preserveAspectRation")]
+    preserve_aspect_ratio: &'a str,
+    #[serde(rename = "@class")]
+    class: &'a str,
+    #[serde(rename = "@viewBox")]
+    view_box: &'a ViewBox,
+}
+
+/// Attributes of a group, serialised without its children.
+#[derive(Serialize, Default)]
+struct GroupShell<'a> {
+    #[serde(rename = "@id", skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    #[serde(rename = "@clip-path", skip_serializing_if = "Option::is_none")]
+    clip_path: Option<&'a str>,
+    #[serde(rename = "@class", skip_serializing_if = "Option::is_none")]
+    class: Option<&'a str>,
+}
+
+/// Writes a document one element at a time, indenting each element at its depth.
+struct StreamWriter<W: Write> {
+    writer: W,
+    /// The element being serialised.
+    element: String,
+    /// Indented output not yet handed to the writer.
+    output: String,
+    depth: usize,
+}
+
+impl<W: Write> StreamWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            element: String::new(),
+            output: String::with_capacity(CHUNK_SIZE),
+            depth: 0,
+        }
+    }
+
+    /// Serialises `value` on its own, as if it was the document root.
+    fn serialise(&mut self, root: &str, value: &impl Serialize) -> Result<(), SVGError> {
+        self.element.clear();
+
+        let mut serialiser = Serializer::with_root(&mut self.element, Some(root))?;
+        serialiser.indent(' ', 4);
+        serialiser.set_quote_level(QuoteLevel::Minimal);
+
+        value.serialize(serialiser)?;
+
+        Ok(())
+    }
+
+    /// Starts a new line at the current depth. The document itself starts without a line break.
+    fn new_line(&mut self) {
+        if self.depth > 0 {
+            self.output.push('\n');
+        }
+
+        self.indent(self.depth);
+    }
+
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), SVGError> {
+        if self.output.len() >= CHUNK_SIZE {
+            self.writer.write_all(self.output.as_bytes())?;
+            self.output.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Writes a whole element. Any text it contains must fit on one line, or it would be indented too.
+    fn element(&mut self, root: &str, value: &impl Serialize) -> Result<(), SVGError> {
+        self.serialise(root, value)?;
+        self.new_line();
+
+        let element = std::mem::take(&mut self.element);
+        for (i, line) in element.split('\n').enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+                self.indent(self.depth);
+            }
+            self.output.push_str(line);
+        }
+        self.element = element;
+
+        self.flush_chunk()
+    }
+
+    /// Writes an element whose text spans multiple lines. Only the line breaks around the text are indented.
+    fn text_element(&mut self, root: &str, value: &impl Serialize) -> Result<(), SVGError> {
+        self.serialise(root, value)?;
+        self.new_line();
+
+        let element = std::mem::take(&mut self.element);
+        match (element.find('\n'), element.rfind('\n')) {
+            (Some(first), Some(last)) if first < last => {
+                self.output.push_str(&element[..=first]);
+                self.indent(self.depth);
+                self.output.push_str(&element[first + 1..=last]);
+                self.indent(self.depth);
+                self.output.push_str(&element[last + 1..]);
+            }
+            // Empty element
+            _ => self.output.push_str(&element),
+        }
+        self.element = element;
+
+        self.flush_chunk()
+    }
+
+    /// Writes the start tag of an element, its children must follow.
+    fn open(&mut self, root: &str, shell: &impl Serialize) -> Result<(), SVGError> {
+        self.serialise(root, shell)?;
+        self.new_line();
+
+        // Without children, the shell is an empty element tag
+        let start = self
+            .element
+            .strip_suffix("/>")
+            .unwrap_or(self.element.as_str());
+        self.output.push_str(start);
+        self.output.push('>');
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Writes the end tag of the last element opened with [`StreamWriter::open`].
+    fn close(&mut self, root: &str) -> Result<(), SVGError> {
+        self.depth -= 1;
+        self.new_line();
+        self.output.push_str("</");
+        self.output.push_str(root);
+        self.output.push('>');
+
+        self.flush_chunk()
+    }
+
+    /// Writes a group one child at a time.
+    fn group<'a, T: Serialize + 'a>(
+        &mut self,
+        shell: &GroupShell,
+        child_root: &str,
+        children: impl IntoIterator<Item = &'a T>,
+    ) -> Result<(), SVGError> {
+        let mut children = children.into_iter().peekable();
+        if children.peek().is_none() {
+            return self.element("g", shell);
+        }
+
+        self.open("g", shell)?;
+        for child in children {
+            self.element(child_root, child)?;
+        }
+        self.close("g")
+    }
+
+    fn finish(mut self) -> Result<(), SVGError> {
+        self.writer.write_all(self.output.as_bytes())?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl SVG {
+    /// Writes the [`SVG`] to `writer`, producing the same document as [`String::try_from`].
+    ///
+    /// Only serialisation is streamed: the [`SVG`] itself is already in memory and grows with the number of cores.
+    /// Core and router clip paths are not kept in it, they are generated from the processing groups as they are
+    /// written. Elements are serialised one at a time rather than the whole document at once, so the memory needed on top of
+    /// the [`SVG`] is bounded by the largest core, channel, information or task group instead of the document's size.
+    pub fn write_to(&self, writer: impl Write) -> Result<(), SVGError> {
+        // Destructured exhaustively, so that fields added to SVG or Root can't be left out of the stream
+        let SVG {
+            xmlns_svg,
+            xmlns,
+            preserve_aspect_ratio,
+            class,
+            view_box,
+            metadata,
+            defs,
+            style,
+            root,
+            rows: _,
+            width: _,
+            height: _,
+            top_left: _,
+            base_view_box: _,
+            borders_view_box: _,
+            base_configuration: _,
+            processed_base_configuration: _,
+            namespace,
+            render_state: _,
+            restored: _,
+        } = self;
+        let Root {
+            id,
+            processing_group,
+            connections_group,
+            information_group,
+            sinks_sources_group,
+            tasks_group,
+            task_moves_group,
+            animation_group,
+        } = root;
+
+        let mut stream = StreamWriter::new(writer);
+
+        stream.open(
+            "svg",
+            &SVGShell {
+                xmlns_svg,
+                xmlns,
+                preserve_aspect_ratio,
+                class,
+                view_box,
+            },
+        )?;
+
+        if !metadata.is_omitted() {
+            stream.element("metadata", metadata)?;
+        }
+
+        stream.open("defs", &GroupShell::default())?;
+        stream.element("marker", defs.marker())?;
+        // Generated one at a time, as in String::try_from
+        for clip_path in processing_group.clip_paths(namespace) {
+            stream.element("clipPath", &clip_path)?;
+        }
+        for clip_path in defs.clip_paths() {
+            stream.element("clipPath", clip_path)?;
+        }
+        stream.close("defs")?;
+
+        stream.text_element("style", style)?;
+
+        stream.open(
+            "g",
+            &GroupShell {
+                id: Some(id.as_str()),
+                ..Default::default()
+            },
+        )?;
+
+        stream.group(
+            &GroupShell {
+                id: Some(processing_group.id().as_str()),
+                clip_path: Some(processing_group.clip_path().as_str()),
+                class: None,
+            },
+            "g",
+            processing_group.g(),
+        )?;
+
+        let edge_connections = connections_group.edge_connections();
+        stream.open("g", &GroupShell::default())?;
+        stream.group(
+            &GroupShell::default(),
+            "path",
+            connections_group.connections().path(),
+        )?;
+        stream.group(
+            &GroupShell {
+                id: Some(edge_connections.id().as_str()),
+                clip_path: None,
+                class: Some(*edge_connections.class()),
+            },
+            "path",
+            edge_connections
+                .source()
+                .iter()
+                .chain(edge_connections.sink()),
+        )?;
+        stream.close("g")?;
+
+        stream.group(
+            &GroupShell {
+                id: Some(information_group.id().as_str()),
+                ..Default::default()
+            },
+            "g",
+            information_group.groups(),
+        )?;
+
+        // Bounded by the matrix edge rather than the number of cores
+        stream.element("g", sinks_sources_group)?;
+
+        stream.group(
+            &GroupShell {
+                id: Some(tasks_group.id().as_str()),
+                clip_path: Some(tasks_group.clip_path().as_str()),
+                class: None,
+            },
+            "g",
+            tasks_group,
+        )?;
+
+        if let Some(task_moves_group) = task_moves_group {
+            stream.element("g", task_moves_group)?;
+        }
+        if let Some(animation_group) = animation_group {
+            stream.element("g", animation_group)?;
+        }
+
+        stream.close("g")?;
+        stream.close("svg")?;
+
+        stream.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use crate::{test_utils::read_configuration, AnimationSettings, BaseConfiguration, SVG};
+
+    fn assert_streams_same_document(svg: &SVG, case: &str) {
+        let mut streamed = Vec::new();
+        svg.write_to(&mut streamed).expect("Could not stream SVG.");
+
+        assert_eq!(
+            String::from_utf8(streamed).expect("Streamed SVG is not UTF-8"),
+            String::try_from(svg).expect("Could not convert from SVG to string"),
+            "{case}"
+        );
+    }
+
+    #[test]
+    fn streams_same_document() {
+        for conf in ["conf2", "conf3", "conf4", "conf6", "conf7", "conf8"] {
+            let mut configuration = read_configuration(&format!("tests/{conf}.json"));
+
+            let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+                .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+            let mut svg = SVG::try_from(&manycore).expect("Could not convert Manycore to SVG.");
+            svg.update_configurable_information(
+                &mut manycore,
+                &mut configuration,
+                &BaseConfiguration::default(),
+            )
+            .expect("Could not update SVG.");
+
+            assert_streams_same_document(&svg, conf);
+        }
+    }
+
+    #[test]
+    fn streams_generated_clip_paths() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert Manycore to SVG.");
+        svg.add_freeform_clip_path("0 0, 1 1, 1 0".to_string());
+        assert_streams_same_document(&svg, "freeform");
+
+        // One per core and router, then the FreeForm one
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert_eq!(
+            res.matches("<clipPath ").count(),
+            manycore.cores().list().len() * 2 + 1
+        );
+        assert!(res.contains("<clipPath id=\"clip-c-0\">"));
+        assert!(res.contains("<clipPath id=\"clip-r-8\">"));
+        assert!(res.find("id=\"clip-r-8\"") < res.find("id=\"crop\""));
+
+        // Only the FreeForm clip path is removed
+        svg.clear_freeform_clip_path();
+        svg.clear_freeform_clip_path();
+        let res = String::try_from(&svg).expect("Could not convert from SVG to string");
+        assert_eq!(
+            res.matches("<clipPath ").count(),
+            manycore.cores().list().len() * 2
+        );
+    }
+
+    #[test]
+    fn streams_diffs_and_animations() {
+        let manycore = |path: &str| {
+            ManycoreSystem::parse_file(path)
+                .unwrap_or_else(|_| panic!("Could not read input test file \"{path}\""))
+        };
+        let configuration = read_configuration("tests/conf2.json");
+
+        // Task moves group
+        let svg = SVG::diff(
+            &manycore("tests/VisualiserOutput1.xml"),
+            &manycore("tests/VisualiserOutput1_next.xml"),
+            &configuration,
+        )
+        .expect("Could not generate diff SVG.");
+        assert_streams_same_document(&svg, "diff");
+
+        // Animation group
+        let svg = SVG::animate(
+            &mut [
+                manycore("tests/VisualiserOutput1.xml"),
+                manycore("tests/VisualiserOutput1_next.xml"),
+            ],
+            &configuration,
+            &AnimationSettings::new(500, true),
+        )
+        .expect("Could not generate animated SVG.");
+        assert_streams_same_document(&svg, "animation");
+    }
+}
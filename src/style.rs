@@ -1,10 +1,13 @@
 use const_format::concatcp;
-use getset::{Getters, MutGetters};
+use getset::Getters;
 use serde::Serialize;
 
+use crate::{namespace::IdNamespace, MAIN_GROUP_ID};
+
 pub(crate) const DEFAULT_FILL: &str = "#e5e5e5";
 pub(crate) const BASE_FILL_CLASS_NAME: &'static str = "baseFill";
 pub(crate) const EDGE_DATA_CLASS_NAME: &'static str = "edgeData";
+static FILL_CLASS_PREFIX: &'static str = "fill";
 
 const FONT_FACE: &str = concatcp!(
     r#"
@@ -28,26 +31,89 @@ const BASE_STYLE: &str = concatcp!(
 static DEFAULT_STYLE: &str = concatcp!(BASE_STYLE, "\n.", EDGE_DATA_CLASS_NAME, "{display: none;}");
 
 /// Object representation of SVG `<style>`.
-#[derive(Serialize, MutGetters, Getters)]
+/// Fill colours are shared through classes, one per distinct colour, rather than one rule per element.
+#[derive(Serialize, Getters)]
 pub(crate) struct Style {
     #[serde(rename = "$text")]
-    #[getset(get_mut = "pub", get = "pub")]
+    #[getset(get = "pub")]
     css: String,
+    /// Colours that have a fill class, the class index is the colour's position.
+    #[serde(skip)]
+    fill_colours: Vec<String>,
 }
 
 impl Style {
     /// Generates a new [`Style`] instance with only [`BASE_STYLE`].
-    pub(crate) fn base() -> Self {
+    /// Class rules are scoped to the main group when the [`IdNamespace`] is not empty.
+    pub(crate) fn base(namespace: &IdNamespace) -> Self {
+        if namespace.is_empty() {
+            return Self {
+                css: BASE_STYLE.into(),
+                fill_colours: Vec::new(),
+            };
+        }
+
         Self {
-            css: BASE_STYLE.into(),
+            css: format!(
+                "{}\n{} .{}{{fill: {};}}",
+                FONT_FACE,
+                namespace.href(MAIN_GROUP_ID),
+                BASE_FILL_CLASS_NAME,
+                DEFAULT_FILL
+            ),
+            fill_colours: Vec::new(),
         }
     }
-}
 
-impl Default for Style {
-    fn default() -> Self {
-        Self {
-            css: DEFAULT_STYLE.into(),
+    /// Generates a new [`Style`] instance with [`DEFAULT_STYLE`], i.e. edge data hidden.
+    /// Class rules are scoped to the main group when the [`IdNamespace`] is not empty.
+    pub(crate) fn new(namespace: &IdNamespace) -> Self {
+        if namespace.is_empty() {
+            return Self {
+                css: DEFAULT_STYLE.into(),
+                fill_colours: Vec::new(),
+            };
         }
+
+        let mut ret = Self::base(namespace);
+        ret.css.push_str(
+            format!(
+                "\n{} .{}{{display: none;}}",
+                namespace.href(MAIN_GROUP_ID),
+                EDGE_DATA_CLASS_NAME
+            )
+            .as_str(),
+        );
+
+        ret
+    }
+
+    /// Returns the class that fills an element with the given colour.
+    /// The class rule is added to the CSS the first time a colour is requested.
+    pub(crate) fn fill_class(&mut self, colour: &String, namespace: &IdNamespace) -> String {
+        let index = match self.fill_colours.iter().position(|c| c == colour) {
+            Some(index) => index,
+            None => {
+                let index = self.fill_colours.len();
+                let selector = if namespace.is_empty() {
+                    String::new()
+                } else {
+                    format!("{} ", namespace.href(MAIN_GROUP_ID))
+                };
+
+                self.css.push_str(
+                    format!(
+                        "\n{}.{}{}{{fill: {};}}",
+                        selector, FILL_CLASS_PREFIX, index, colour
+                    )
+                    .as_str(),
+                );
+                self.fill_colours.push(colour.clone());
+
+                index
+            }
+        };
+
+        format!("{}{}", FILL_CLASS_PREFIX, index)
     }
 }
@@ -1,15 +1,40 @@
 /// [`SVG`] conversion utilities.
 use manycore_parser::{ManycoreSystem, SystemDimensionsT, WithID};
 use quick_xml::DeError;
-use serde::Serialize;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use std::cmp::min;
 
 use crate::{
+    namespace::IdNamespace,
     tasks_group::{missing_task, TASK_RECT_STROKE},
-    BaseConfiguration, CoordinateT, Offsets, ProcessingGroup, SVGError, TopLeft, ViewBox,
+    BaseConfiguration, CoordinateT, Metadata, Offsets, ProcessingGroup, SVGError, TopLeft, ViewBox,
     BLOCK_DISTANCE, BLOCK_LENGTH, CORE_ROUTER_STROKE_WIDTH, SVG, UNSUPPORTED_PLATFORM,
 };
 
+impl Serialize for SVG {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("svg", 9)?;
+        state.serialize_field("@xmlns:svg", self.xmlns_svg)?;
+        state.serialize_field("@xmlns", self.xmlns)?;
+        state.serialize_field("@preserveAspectRation", self.preserve_aspect_ratio)?;
+        state.serialize_field("@class", self.class)?;
+        state.serialize_field("@viewBox", &self.view_box)?;
+        if !self.metadata.is_omitted() {
+            state.serialize_field("metadata", &self.metadata)?;
+        }
+        // Core and router clip paths are generated as they are written rather than kept
+        state.serialize_field(
+            "defs",
+            &self
+                .defs
+                .content(&self.root.processing_group, &self.namespace),
+        )?;
+        state.serialize_field("style", &self.style)?;
+        state.serialize_field("g", &self.root)?;
+        state.end()
+    }
+}
+
 impl TryFrom<&SVG> for String {
     type Error = DeError;
 
@@ -26,15 +51,14 @@ impl TryFrom<&SVG> for String {
 }
 
 impl SVG {
-    fn shared_try_from(
-        manycore: &ManycoreSystem,
+    /// Generates an [`SVG`] sized for the system described by `metadata`, with no processing groups yet.
+    pub(crate) fn frame(
+        metadata: Metadata,
         base_configuration: BaseConfiguration,
-    ) -> Result<Self, SVGError> {
-        let columns = *manycore.columns();
-        let rows = *manycore.rows();
-
-        let columns_coord: CoordinateT = columns.into();
-        let rows_coord: CoordinateT = rows.into();
+        namespace: IdNamespace,
+    ) -> Self {
+        let columns_coord: CoordinateT = metadata.columns().into();
+        let rows_coord: CoordinateT = metadata.rows().into();
 
         // Each column * each block + the distance between blocks + the stroke
         let width = (columns_coord * BLOCK_LENGTH)
@@ -51,8 +75,31 @@ impl SVG {
             y: height.saturating_div(2).saturating_mul(-1),
         };
 
+        SVG::new(
+            metadata,
+            width,
+            height,
+            top_left,
+            base_configuration,
+            namespace,
+        )
+    }
+
+    fn shared_try_from(
+        manycore: &ManycoreSystem,
+        base_configuration: BaseConfiguration,
+        namespace: IdNamespace,
+    ) -> Result<Self, SVGError> {
+        let columns = *manycore.columns();
+        let rows = *manycore.rows();
+
         // The SVG we'll return
-        let mut ret = SVG::new(manycore, width, height, top_left, base_configuration);
+        let mut ret = SVG::frame(
+            Metadata::new(manycore, &base_configuration, &namespace),
+            base_configuration,
+            namespace,
+        );
+        let top_left = ret.top_left;
 
         // Row tracker for iteration
         let mut r: SystemDimensionsT = 0;
@@ -81,13 +128,8 @@ impl SVG {
             let c_coord: CoordinateT = c.into();
 
             // Generate processing group
-            let processing_group = ProcessingGroup::new(
-                &r_coord,
-                &c_coord,
-                core.id(),
-                &ret.top_left,
-                ret.defs.clip_paths_mut(),
-            )?;
+            let processing_group =
+                ProcessingGroup::new(&r_coord, &c_coord, core.id(), &ret.top_left, &ret.namespace)?;
 
             // Add task
             if let Some(task_id) = core.allocated_task() {
@@ -175,8 +217,32 @@ impl SVG {
     pub(crate) fn try_from_manycore_with_base_config(
         manycore: &ManycoreSystem,
         base_configuration: &BaseConfiguration,
+        namespace: IdNamespace,
     ) -> Result<Self, SVGError> {
-        Ok(SVG::shared_try_from(manycore, *base_configuration)?)
+        Ok(SVG::shared_try_from(
+            manycore,
+            *base_configuration,
+            namespace,
+        )?)
+    }
+
+    /// Generates an [`SVG`] whose element IDs, `url(#...)` references and CSS rules are prefixed with `id_prefix`.
+    /// Use distinct prefixes to embed multiple [`SVG`]s in the same page.
+    pub fn try_from_manycore_with_id_prefix(
+        manycore: &ManycoreSystem,
+        base_configuration: &BaseConfiguration,
+        id_prefix: &str,
+    ) -> Result<Self, SVGError> {
+        SVG::try_from_manycore_with_base_config(
+            manycore,
+            base_configuration,
+            IdNamespace::try_from(id_prefix)?,
+        )
+    }
+
+    /// Returns the prefix applied to the [`SVG`]'s element IDs. Empty unless requested.
+    pub fn id_prefix(&self) -> &str {
+        self.namespace.prefix()
     }
 }
 
@@ -186,6 +252,7 @@ impl TryFrom<&ManycoreSystem> for SVG {
         Ok(SVG::shared_try_from(
             manycore,
             BaseConfiguration::default(),
+            IdNamespace::default(),
         )?)
     }
 }
@@ -5,17 +5,20 @@ use std::{
 };
 
 use getset::Getters;
-use manycore_parser::ElementIDT;
+use manycore_parser::{ElementIDT, TASK_COST_KEY};
 use manycore_utils::serialise_btreemap;
 use quick_xml::DeError;
 use serde::Serialize;
 
 use crate::{
-    generation_error, partial_update::PartialUpdate, CoordinateT, FontSizeT,
-    ProcessedBaseConfiguration, ProcessingGroup, SVGError, SVGErrorKind, TaskRectConfiguration,
-    TextInformation, TopLeft, BLOCK_DISTANCE, BLOCK_LENGTH, CHAR_H_PADDING,
-    CORE_ROUTER_STROKE_WIDTH_STR, HALF_CHAR_V_PADDING, ROUTER_OFFSET, SIDE_LENGTH,
-    USE_FREEFORM_CLIP_PATH,
+    generation_error,
+    namespace::IdNamespace,
+    partial_update::PartialUpdate,
+    warnings::{invalid_special_key, ElementKind, Warning},
+    CoordinateT, FieldConfiguration, FontSizeT, ProcessedBaseConfiguration, ProcessingGroup,
+    SVGError, SVGErrorKind, TaskRectConfiguration, TextInformation, TopLeft, BLOCK_DISTANCE,
+    BLOCK_LENGTH, CHAR_H_PADDING, CORE_ROUTER_STROKE_WIDTH_STR, FREEFORM_CLIP_PATH_ID,
+    HALF_CHAR_V_PADDING, ROUTER_OFFSET, SIDE_LENGTH,
 };
 
 pub(crate) const DEFAULT_TASK_FONT_SIZE: FontSizeT = 22.0;
@@ -94,6 +97,8 @@ struct BaseVariant(bool);
 /// Helper struct to group [`TaskRect`] and its corresponding [`TextInformation`] together, forms the task bubble in the SVG.
 #[derive(Serialize, Getters)]
 pub(crate) struct Task {
+    #[serde(rename = "@id", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
     #[getset(get = "pub")]
     rect: TaskRect,
     #[serde(rename = "text")]
@@ -163,6 +168,7 @@ impl Task {
         );
 
         Ok(Self {
+            id: None,
             rect: TaskRect::new(
                 cx,
                 cy,
@@ -266,26 +272,28 @@ impl Task {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Getters)]
 pub(crate) struct TasksGroup {
     #[serde(rename = "@id")]
-    id: &'static str,
+    #[getset(get = "pub")]
+    id: String,
     #[serde(rename = "g", serialize_with = "serialise_btreemap")]
     tasks: BTreeMap<u16, Task>,
     #[serde(skip)]
     variant: BaseVariant,
     #[serde(rename = "@clip-path")]
-    clip_path: &'static str,
+    #[getset(get = "pub")]
+    clip_path: String,
 }
 
 impl TasksGroup {
     /// Creates a new [`TasksGroup`] instance with enough capacity for the provided number of tasks.
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(namespace: &IdNamespace) -> Self {
         Self {
-            id: "tasks",
+            id: namespace.id("tasks"),
             tasks: BTreeMap::new(),
             variant: BaseVariant(true),
-            clip_path: USE_FREEFORM_CLIP_PATH,
+            clip_path: namespace.url(FREEFORM_CLIP_PATH_ID),
         }
     }
 
@@ -303,6 +311,23 @@ impl TasksGroup {
         Ok(self.tasks.entry(*task.id()).or_insert(task_g))
     }
 
+    /// Gives every [`Task`] in the group an ID, so that it can be referenced (e.g. by animations).
+    pub(crate) fn set_ids(&mut self, namespace: &IdNamespace) {
+        for (task_id, task) in self.tasks.iter_mut() {
+            task.id = Some(TasksGroup::make_task_id(task_id, namespace));
+        }
+    }
+
+    /// Calculates the ID of a [`Task`].
+    pub(crate) fn make_task_id(task_id: &u16, namespace: &IdNamespace) -> String {
+        namespace.id(&format!("t{task_id}"))
+    }
+
+    /// Returns the requested [`Task`], if in the group.
+    pub(crate) fn get(&self, task_id: &u16) -> Option<&Task> {
+        self.tasks.get(task_id)
+    }
+
     /// Returns whether the [`TasksGroup`] is in the base variant.
     pub(crate) fn is_base(&self) -> bool {
         self.variant.0
@@ -313,6 +338,27 @@ impl TasksGroup {
         self.variant.0 = !self.variant.0;
     }
 
+    /// Determines whether the tasks need toggling to match the requested task cost configuration.
+    /// Without a valid request, tasks go back to the base variant.
+    pub(crate) fn should_toggle(
+        &self,
+        field_configuration: Option<FieldConfiguration>,
+        warnings: &mut Vec<Warning>,
+    ) -> bool {
+        match field_configuration {
+            Some(FieldConfiguration::Boolean { value }) => value == self.is_base(),
+            Some(invalid) => {
+                warnings.push(Warning::configuration(
+                    TASK_COST_KEY,
+                    invalid_special_key("Boolean", &invalid),
+                ));
+
+                !self.is_base()
+            }
+            None => !self.is_base(),
+        }
+    }
+
     /// Toggles the requested task.
     pub(crate) fn toggle_task(
         &mut self,
@@ -325,6 +371,8 @@ impl TasksGroup {
 
         let task = self.tasks.get_mut(task_id).ok_or_else(|| {
             generation_error(format!("Could not find Task {task_id} in TasksGroup."))
+                .with_code("missing_svg_task")
+                .with_element(ElementKind::Task, task_id)
         })?;
 
         if is_base {
@@ -361,4 +409,6 @@ pub(crate) fn missing_task(core_id: &ElementIDT, task_id: &u16) -> SVGError {
     SVGError::new(SVGErrorKind::ManycoreMismatch(format!(
         "Core {core_id} has Task {task_id} allocated but the task is not in the TaskGraph."
     )))
+    .with_code("missing_task")
+    .with_element(ElementKind::Task, task_id)
 }
@@ -0,0 +1,12 @@
+//! Helpers shared by the unit tests.
+
+use crate::Configuration;
+
+/// Reads a JSON [`Configuration`] fixture, panicking if it cannot be read.
+pub(crate) fn read_configuration(path: &str) -> Configuration {
+    let conf_file = std::fs::File::open(path)
+        .unwrap_or_else(|error| panic!("Could not open \"{path}\": {error}"));
+
+    serde_json::from_reader(conf_file)
+        .unwrap_or_else(|error| panic!("Could not parse \"{path}\": {error}"))
+}
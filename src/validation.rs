@@ -0,0 +1,572 @@
+use std::collections::BTreeMap;
+
+use getset::Getters;
+use manycore_parser::{
+    ElementIDT, ManycoreSystem, WithID, WithXMLAttributes, BORDER_ROUTERS_KEY, COORDINATES_KEY,
+    ID_KEY, ROUTING_KEY, TASK_COST_KEY,
+};
+use serde::Serialize;
+
+use crate::{
+    computed_attributes::attribute_value, parse_colour_value, ColourSettings, ComputedAttribute,
+    Configuration, FieldConfiguration,
+};
+
+/// Severity of a [`Diagnostic`].
+/// * [`Warning`][`Severity::Warning`]: The configuration can be rendered, but some of it will have no effect.
+/// * [`Error`][`Severity::Error`]: The configuration is invalid. Rendering fails or ignores the offending part.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found by [`Configuration::validate`].
+/// * `path`: Where the problem is in the configuration, e.g. `coreConfig.@age` or `coreConfig.@age.colours[2]`.
+/// * `severity`: [`Severity`] of the problem.
+/// * `message`: User friendly explanation of the problem.
+#[derive(Serialize, Getters, Debug, Clone, PartialEq)]
+#[getset(get = "pub")]
+pub struct Diagnostic {
+    path: String,
+    severity: Severity,
+    message: String,
+}
+
+impl Diagnostic {
+    fn new(path: String, severity: Severity, message: String) -> Self {
+        Self {
+            path,
+            severity,
+            message,
+        }
+    }
+}
+
+/// Field types that can display attribute values.
+pub(crate) static ATTRIBUTE_TYPES: [&str; 3] = ["Text", "ColouredText", "Fill"];
+/// Channel attributes are displayed as text only.
+pub(crate) static CHANNEL_ATTRIBUTE_TYPES: [&str; 2] = ["Text", "ColouredText"];
+
+/// CSS named colours, sorted.
+pub(crate) static CSS_COLOUR_NAMES: [&str; 149] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "transparent",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+/// CSS colour functions, e.g. `rgb(34, 197, 94)`.
+pub(crate) static CSS_COLOUR_FUNCTIONS: [&str; 4] = ["rgb", "rgba", "hsl", "hsla"];
+
+/// Whether `colour` is a CSS colour: hexadecimal, e.g. `#22c55e`, named, e.g. `green`, or functional, e.g.
+/// `rgb(34, 197, 94)`.
+fn is_colour(colour: &str) -> bool {
+    let colour = colour.trim().to_ascii_lowercase();
+
+    if let Some(hex) = colour.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+
+    if let Some((function, arguments)) = colour.split_once('(') {
+        return CSS_COLOUR_FUNCTIONS.contains(&function.trim_end())
+            && arguments.ends_with(')')
+            && !arguments[..arguments.len() - 1].trim().is_empty();
+    }
+
+    CSS_COLOUR_NAMES.binary_search(&colour.as_str()).is_ok()
+}
+
+fn check_colour(path: String, colour: &str, ret: &mut Vec<Diagnostic>) {
+    if !is_colour(colour) {
+        ret.push(Diagnostic::new(
+            path,
+            Severity::Error,
+            format!("\"{colour}\" is not a CSS colour."),
+        ));
+    }
+}
+
+fn check_colour_settings(path: &str, colour_settings: &ColourSettings, ret: &mut Vec<Diagnostic>) {
+    // Bounds are replaced when derived automatically
+    if colour_settings.auto_bounds().is_none()
+        && colour_settings
+            .bounds()
+            .windows(2)
+            .any(|pair| pair[0] > pair[1])
+    {
+        ret.push(Diagnostic::new(
+            format!("{path}.bounds"),
+            Severity::Error,
+            format!(
+                "Bounds {:?} must be in ascending order.",
+                colour_settings.bounds()
+            ),
+        ));
+    }
+
+    for (i, colour) in colour_settings.colours().iter().enumerate() {
+        check_colour(format!("{path}.colours[{i}]"), colour, ret);
+    }
+}
+
+/// Field configurations of one kind of element, with what they are validated against.
+/// * `name`: Name of the configuration section, e.g. `coreConfig`.
+/// * `element`: Name of the elements, used in messages.
+/// * `config`: Field configurations of the section.
+/// * `special_keys`: Keys that are not attributes, with the only field type they accept.
+/// * `attribute_types`: Field types accepted by attribute keys.
+/// * `targets`: Elements the attributes are read from.
+struct Section<'a, T> {
+    name: &'a str,
+    element: &'a str,
+    config: &'a BTreeMap<String, FieldConfiguration>,
+    special_keys: &'a [(&'a str, &'a str)],
+    attribute_types: &'a [&'a str],
+    targets: &'a [&'a T],
+}
+
+/// Validates the field configurations of one [`Section`].
+fn check_section<T: WithXMLAttributes>(
+    section: Section<T>,
+    computed_attributes: &BTreeMap<String, ComputedAttribute>,
+    ret: &mut Vec<Diagnostic>,
+) {
+    let Section {
+        name,
+        element,
+        config,
+        special_keys,
+        attribute_types,
+        targets,
+    } = section;
+
+    for (key, field_configuration) in config {
+        let path = format!("{name}.{key}");
+        let type_str = field_configuration.type_str();
+
+        if let Some((_, expected)) = special_keys
+            .iter()
+            .find(|(special, _)| *special == key.as_str())
+        {
+            if type_str != *expected {
+                ret.push(Diagnostic::new(
+                    path.clone(),
+                    Severity::Error,
+                    format!("{key} must be configured as {expected}, not {type_str}."),
+                ));
+            }
+        } else {
+            if !attribute_types.contains(&type_str) {
+                ret.push(Diagnostic::new(
+                    path.clone(),
+                    Severity::Error,
+                    format!(
+                        "{element} attributes can be configured as {}, not {type_str}.",
+                        attribute_types.join(", ")
+                    ),
+                ));
+            }
+
+            let values: Vec<_> = targets
+                .iter()
+                .filter_map(|target| attribute_value(*target, key, computed_attributes))
+                .collect();
+
+            if values.is_empty() && !computed_attributes.contains_key(key) {
+                ret.push(Diagnostic::new(
+                    path.clone(),
+                    Severity::Warning,
+                    format!("No {element} has attribute {key}."),
+                ));
+            }
+
+            if matches!(
+                field_configuration,
+                FieldConfiguration::Fill { .. } | FieldConfiguration::ColouredText { .. }
+            ) {
+                let non_numeric: Vec<_> = values
+                    .iter()
+                    .filter(|value| parse_colour_value(value).is_none())
+                    .collect();
+
+                if let Some(example) = non_numeric.first() {
+                    ret.push(Diagnostic::new(
+                        path.clone(),
+                        Severity::Warning,
+                        format!(
+                            "{} {element} values of {key} are not numeric, e.g. \"{example}\". They cannot be coloured.",
+                            non_numeric.len()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        match field_configuration {
+            FieldConfiguration::Text {
+                colour: Some(colour),
+                ..
+            } => check_colour(format!("{path}.colour"), colour, ret),
+            FieldConfiguration::ColouredText {
+                colour_settings, ..
+            }
+            | FieldConfiguration::Fill { colour_settings } => {
+                check_colour_settings(&path, colour_settings, ret)
+            }
+            FieldConfiguration::Routing { configuration } => {
+                check_colour_settings(&path, configuration.load_colours(), ret)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Validates fill overrides: colours must be valid and IDs must exist.
+fn check_fills(
+    section: &str,
+    element: &str,
+    fills: &BTreeMap<ElementIDT, String>,
+    ids: &[ElementIDT],
+    ret: &mut Vec<Diagnostic>,
+) {
+    for (id, colour) in fills {
+        let path = format!("{section}.{id}");
+
+        if !ids.contains(id) {
+            ret.push(Diagnostic::new(
+                path.clone(),
+                Severity::Warning,
+                format!("There is no {element} with ID {id}."),
+            ));
+        }
+
+        check_colour(path, colour, ret);
+    }
+}
+
+impl Configuration {
+    /// Validates the [`Configuration`] against a [`ManycoreSystem`], without rendering anything.
+    /// Returns every problem found, an empty list means the configuration is valid.
+    pub fn validate(&self, manycore: &ManycoreSystem) -> Vec<Diagnostic> {
+        let mut ret = Vec::new();
+        let cores: Vec<_> = manycore.cores().list().iter().collect();
+        let routers: Vec<_> = cores.iter().map(|core| core.router()).collect();
+        let channels: Vec<_> = cores
+            .iter()
+            .flat_map(|core| core.channels().channel().values())
+            .collect();
+
+        for (key, computed_attribute) in self.computed_attributes() {
+            if let Some(reason) = computed_attribute.parse_error() {
+                ret.push(Diagnostic::new(
+                    format!("computedAttributes.{key}"),
+                    Severity::Error,
+                    format!(
+                        "Invalid expression \"{}\": {reason}.",
+                        computed_attribute.source()
+                    ),
+                ));
+            }
+        }
+
+        check_section(
+            Section {
+                name: "coreConfig",
+                element: "core",
+                config: self.core_config(),
+                special_keys: &[
+                    (ID_KEY, "Text"),
+                    (COORDINATES_KEY, "Coordinates"),
+                    (TASK_COST_KEY, "Boolean"),
+                ],
+                attribute_types: &ATTRIBUTE_TYPES,
+                targets: &cores,
+            },
+            self.computed_attributes(),
+            &mut ret,
+        );
+        check_section(
+            Section {
+                name: "routerConfig",
+                element: "router",
+                config: self.router_config(),
+                special_keys: &[(ID_KEY, "Text")],
+                attribute_types: &ATTRIBUTE_TYPES,
+                targets: &routers,
+            },
+            self.computed_attributes(),
+            &mut ret,
+        );
+        check_section(
+            Section {
+                name: "channelConfig",
+                element: "channel",
+                config: self.channel_config(),
+                special_keys: &[(BORDER_ROUTERS_KEY, "Boolean"), (ROUTING_KEY, "Routing")],
+                attribute_types: &CHANNEL_ATTRIBUTE_TYPES,
+                targets: &channels,
+            },
+            self.computed_attributes(),
+            &mut ret,
+        );
+
+        check_fills(
+            "coreFills",
+            "core",
+            self.core_fills(),
+            &cores.iter().map(|core| *core.id()).collect::<Vec<_>>(),
+            &mut ret,
+        );
+        check_fills(
+            "routerFills",
+            "router",
+            self.router_fills(),
+            &routers
+                .iter()
+                .map(|router| *router.id())
+                .collect::<Vec<_>>(),
+            &mut ret,
+        );
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::ManycoreSystem;
+
+    use super::Severity;
+    use crate::{test_utils::read_configuration, Configuration};
+
+    #[test]
+    fn reports_configuration_problems() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let configuration: Configuration = serde_json::from_str(
+            r##"{
+                "coreConfig": {
+                    "@id": {
+                        "type": "Fill",
+                        "bounds": [0, 1, 2, 3],
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    },
+                    "@age": {
+                        "type": "Fill",
+                        "bounds": [300, 200, 100, 30],
+                        "colours": ["#22c55e", "greenish", "#f97316", "#dc2626"]
+                    },
+                    "@temperature": {
+                        "type": "ColouredText",
+                        "display": "Temp",
+                        "bounds": [0, 1, 2, 3],
+                        "colours": ["green", "RebeccaPurple", "rgb(249, 115, 22)", "hsl(0 72% 51%)"]
+                    },
+                    "@notAnAttribute": {
+                        "type": "Text",
+                        "display": "Nothing"
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {
+                    "@routingAlgorithm": {
+                        "type": "Boolean",
+                        "value": true
+                    }
+                },
+                "coreFills": {
+                    "200": "#000000"
+                },
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let diagnostics = configuration.validate(&manycore);
+        let find = |path: &str| {
+            diagnostics
+                .iter()
+                .find(|diagnostic| diagnostic.path() == path)
+                .unwrap_or_else(|| panic!("No diagnostic for {path}: {diagnostics:?}"))
+        };
+
+        assert_eq!(*find("coreConfig.@id").severity(), Severity::Error);
+        assert_eq!(*find("coreConfig.@age.bounds").severity(), Severity::Error);
+        assert_eq!(
+            *find("coreConfig.@age.colours[1]").severity(),
+            Severity::Error
+        );
+        assert_eq!(
+            *find("coreConfig.@notAnAttribute").severity(),
+            Severity::Warning
+        );
+        assert_eq!(
+            *find("channelConfig.@routingAlgorithm").severity(),
+            Severity::Error
+        );
+        assert_eq!(*find("coreFills.200").severity(), Severity::Warning);
+        assert!(diagnostics.iter().all(|diagnostic| !diagnostic
+            .path()
+            .starts_with("coreConfig.@temperature.colours")));
+    }
+
+    #[test]
+    fn accepts_valid_configuration() {
+        let manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let configuration = read_configuration("tests/conf7.json");
+
+        let errors: Vec<_> = configuration
+            .validate(&manycore)
+            .into_iter()
+            .filter(|diagnostic| *diagnostic.severity() == Severity::Error)
+            .collect();
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use getset::Getters;
+use manycore_parser::{Directions, ElementIDT};
+use serde::Serialize;
+
+use crate::FieldConfiguration;
+
+/// Kind of element a [`Warning`] or an [`SVGError`](crate::SVGError) refers to.
+/// [`Configuration`][`ElementKind::Configuration`] refers to the configuration as a whole.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementKind {
+    Core,
+    Router,
+    Channel,
+    Task,
+    Configuration,
+}
+
+/// A non-fatal problem encountered while generating an [`SVG`](crate::SVG). The offending element or field is
+/// not rendered, the rest of the update is.
+/// * `element`: [`ElementKind`] the warning refers to.
+/// * `id`: ID of the element, if the warning refers to one. Channels are identified by their core's ID.
+/// * `direction`: Direction of the channel, for channel warnings.
+/// * `key`: Configuration key that was being rendered, if any.
+/// * `reason`: User friendly explanation of why something did not render.
+#[derive(Serialize, Getters, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct Warning {
+    element: ElementKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    reason: String,
+}
+
+impl Warning {
+    /// Warning about a core or router attribute.
+    pub(crate) fn element(
+        element: ElementKind,
+        id: &ElementIDT,
+        key: &str,
+        reason: String,
+    ) -> Self {
+        Self {
+            element,
+            id: Some(id.to_string()),
+            direction: None,
+            key: Some(key.to_string()),
+            reason,
+        }
+    }
+
+    /// Warning about a channel attribute.
+    pub(crate) fn channel(
+        core_id: &ElementIDT,
+        direction: &Directions,
+        key: &str,
+        reason: String,
+    ) -> Self {
+        Self {
+            element: ElementKind::Channel,
+            id: Some(core_id.to_string()),
+            direction: Some(direction.to_string()),
+            key: Some(key.to_string()),
+            reason,
+        }
+    }
+
+    /// Warning about a configuration option that is ignored.
+    pub(crate) fn configuration(key: &str, reason: String) -> Self {
+        Self {
+            element: ElementKind::Configuration,
+            id: None,
+            direction: None,
+            key: Some(key.to_string()),
+            reason,
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.element)?;
+        if let Some(id) = &self.id {
+            write!(f, " {id}")?;
+        }
+        if let Some(direction) = &self.direction {
+            write!(f, " {direction}")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, " ({key})")?;
+        }
+
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Reason for an element missing a requested attribute.
+pub(crate) fn missing_attribute() -> String {
+    "The element does not have this attribute, nothing was rendered.".to_string()
+}
+
+/// Reason for a value that cannot be coloured.
+pub(crate) fn non_numeric_value(value: &str) -> String {
+    format!("Value \"{value}\" is not numeric, its colour cannot be calculated.")
+}
+
+/// Reason for a field type that cannot be used for an attribute.
+pub(crate) fn unsupported_field(field_configuration: &FieldConfiguration) -> String {
+    format!(
+        "{} is not supported for this attribute, it was ignored.",
+        field_configuration.type_str()
+    )
+}
+
+/// Reason for a special key configured with the wrong field type.
+pub(crate) fn invalid_special_key(
+    expected: &str,
+    field_configuration: &FieldConfiguration,
+) -> String {
+    format!(
+        "Expected {expected} configuration, found {}. The option was ignored.",
+        field_configuration.type_str()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use manycore_parser::{ManycoreSystem, ROUTING_KEY};
+
+    use super::ElementKind;
+    use crate::{BaseConfiguration, Configuration, SVG};
+
+    #[test]
+    fn collects_generation_warnings() {
+        let mut manycore = ManycoreSystem::parse_file("tests/VisualiserOutput1.xml")
+            .expect("Could not read input test file \"tests/VisualiserOutput1.xml\"");
+        let mut configuration: Configuration = serde_json::from_str(
+            r##"{
+                "coreConfig": {
+                    "@status": {
+                        "type": "Fill",
+                        "bounds": [0, 1, 2, 3],
+                        "colours": ["#22c55e", "#eab308", "#f97316", "#dc2626"]
+                    }
+                },
+                "routerConfig": {},
+                "channelConfig": {
+                    "@notAnAttribute": {
+                        "type": "Text",
+                        "display": "Nothing"
+                    },
+                    "@routingAlgorithm": {
+                        "type": "Boolean",
+                        "value": true
+                    }
+                },
+                "coreFills": {},
+                "routerFills": {}
+            }"##,
+        )
+        .expect("Could not parse configuration");
+
+        let mut svg = SVG::try_from(&manycore).expect("Could not convert ManycoreSystem to SVG.");
+        let update = svg
+            .update_configurable_information(
+                &mut manycore,
+                &mut configuration,
+                &BaseConfiguration::default(),
+            )
+            .expect("Could not update SVG.");
+
+        let count = |element: ElementKind, key: &str| {
+            update
+                .warnings
+                .iter()
+                .filter(|warning| {
+                    *warning.element() == element && warning.key().as_deref() == Some(key)
+                })
+                .count()
+        };
+
+        // Every core has a non numeric status
+        assert_eq!(
+            count(ElementKind::Core, "@status"),
+            manycore.cores().list().len()
+        );
+        assert!(count(ElementKind::Channel, "@notAnAttribute") > 0);
+        assert_eq!(count(ElementKind::Configuration, ROUTING_KEY), 1);
+        assert!(update
+            .warnings
+            .iter()
+            .filter(|warning| *warning.element() == ElementKind::Channel)
+            .all(|warning| warning.id().is_some() && warning.direction().is_some()));
+    }
+}
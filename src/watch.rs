@@ -0,0 +1,272 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::BufWriter,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use manycore_parser::ManycoreSystem;
+
+use crate::{
+    namespace::IdNamespace, read_configuration_file, BaseConfiguration, SVGError, SVGErrorKind, SVG,
+};
+
+#[cfg(doc)]
+use crate::Configuration;
+
+/// Re-renders an [`SVG`] whenever the watched [`ManycoreSystem`] XML or [`Configuration`] file changes.
+/// The same [`SVG`] instance is updated through [`SVG::update_configurable_information`] unless the system's structure
+/// (dimensions, task allocations and costs, borders) changed, in which case it is regenerated.
+pub struct SVGWatcher {
+    system_path: PathBuf,
+    configuration_path: PathBuf,
+    output_path: PathBuf,
+    base_configuration: BaseConfiguration,
+    manycore: ManycoreSystem,
+    svg: SVG,
+    system_modified: SystemTime,
+    configuration_modified: SystemTime,
+}
+
+/// Error thrown when a path cannot be handed to the parser.
+fn invalid_path(path: &Path) -> SVGError {
+    SVGError::new(SVGErrorKind::DataConversionError(format!(
+        "Path \"{}\" is not valid UTF-8.",
+        path.display()
+    )))
+    .with_code("invalid_path")
+}
+
+/// Retrieves the last modification time of a file.
+fn modified(path: &Path) -> Result<SystemTime, SVGError> {
+    Ok(fs::metadata(path)?.modified()?)
+}
+
+/// Parses the [`ManycoreSystem`] at the given path.
+pub(crate) fn read_system(path: &Path) -> Result<ManycoreSystem, SVGError> {
+    Ok(ManycoreSystem::parse_file(
+        path.to_str().ok_or_else(|| invalid_path(path))?,
+    )?)
+}
+
+/// Sibling of `path` that is written before being renamed into place.
+fn temporary_path(path: &Path) -> OsString {
+    let mut temporary = OsString::from(path.as_os_str());
+    temporary.push(".tmp");
+
+    temporary
+}
+
+/// Writes `contents` to a temporary sibling of `path`, then renames it into place.
+/// Readers never observe a partially written file.
+pub(crate) fn write_atomically(path: &Path, contents: &str) -> Result<(), SVGError> {
+    let temporary = temporary_path(path);
+
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+/// Like [`write_atomically`], but streams the [`SVG`] with [`SVG::write_to`] instead of serialising it whole.
+pub(crate) fn write_svg_atomically(path: &Path, svg: &SVG) -> Result<(), SVGError> {
+    let temporary = temporary_path(path);
+
+    svg.write_to(BufWriter::new(fs::File::create(&temporary)?))?;
+    fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+/// Whether an [`SVG`] generated from `old` can be updated to display `new`.
+fn same_structure(old: &ManycoreSystem, new: &ManycoreSystem) -> bool {
+    let task_cost = |manycore: &ManycoreSystem, task_id: &u16| {
+        manycore
+            .task_graph()
+            .tasks()
+            .get(task_id)
+            .map(|task| *task.computation_cost())
+    };
+    let has_border = |manycore: &ManycoreSystem, index: usize| match manycore.borders() {
+        Some(borders) => borders.core_border_map().get(&index).is_some(),
+        None => false,
+    };
+
+    old.rows() == new.rows()
+        && old.columns() == new.columns()
+        && old.cores().list().len() == new.cores().list().len()
+        && old
+            .cores()
+            .list()
+            .iter()
+            .zip(new.cores().list().iter())
+            .enumerate()
+            .all(|(i, (old_core, new_core))| {
+                old_core.allocated_task() == new_core.allocated_task()
+                    && match old_core.allocated_task() {
+                        Some(task_id) => task_cost(old, task_id) == task_cost(new, task_id),
+                        None => true,
+                    }
+                    && has_border(old, i) == has_border(new, i)
+            })
+}
+
+impl SVGWatcher {
+    /// Generates a new [`SVGWatcher`] and writes the first render to `output_path`.
+    pub fn new(
+        system_path: impl Into<PathBuf>,
+        configuration_path: impl Into<PathBuf>,
+        output_path: impl Into<PathBuf>,
+        base_configuration: BaseConfiguration,
+    ) -> Result<Self, SVGError> {
+        let system_path = system_path.into();
+        let configuration_path = configuration_path.into();
+
+        let system_modified = modified(&system_path)?;
+        let configuration_modified = modified(&configuration_path)?;
+        let manycore = read_system(&system_path)?;
+        let mut svg = SVG::try_from_manycore_with_base_config(
+            &manycore,
+            &base_configuration,
+            IdNamespace::default(),
+        )?;
+        svg.set_source(&system_path)?;
+
+        let mut ret = Self {
+            system_path,
+            configuration_path,
+            output_path: output_path.into(),
+            base_configuration,
+            manycore,
+            svg,
+            system_modified,
+            configuration_modified,
+        };
+
+        ret.render()?;
+
+        Ok(ret)
+    }
+
+    /// Returns the [`SVG`] as of the last render.
+    pub fn svg(&self) -> &SVG {
+        &self.svg
+    }
+
+    /// Updates the [`SVG`] with the current [`Configuration`] and writes it out.
+    fn render(&mut self) -> Result<(), SVGError> {
+        let mut configuration = read_configuration_file(&self.configuration_path)?;
+
+        self.svg.update_configurable_information(
+            &mut self.manycore,
+            &mut configuration,
+            &self.base_configuration,
+        )?;
+
+        write_svg_atomically(&self.output_path, &self.svg)
+    }
+
+    /// Checks the watched files once and re-renders if either changed.
+    /// Returns whether a new render was written. A failed render is retried on the next poll,
+    /// e.g. when a file was read while still being written.
+    pub fn poll(&mut self) -> Result<bool, SVGError> {
+        let system_modified = modified(&self.system_path)?;
+        let configuration_modified = modified(&self.configuration_path)?;
+
+        if system_modified == self.system_modified
+            && configuration_modified == self.configuration_modified
+        {
+            return Ok(false);
+        }
+
+        if system_modified != self.system_modified {
+            let manycore = read_system(&self.system_path)?;
+
+            if !same_structure(&self.manycore, &manycore) {
+                self.svg = SVG::try_from_manycore_with_base_config(
+                    &manycore,
+                    &self.base_configuration,
+                    self.svg.namespace.clone(),
+                )?;
+            }
+
+            self.svg.set_source(&self.system_path)?;
+            self.manycore = manycore;
+        }
+
+        self.render()?;
+
+        self.system_modified = system_modified;
+        self.configuration_modified = configuration_modified;
+
+        Ok(true)
+    }
+
+    /// Polls the watched files every `interval` until `callback` breaks.
+    /// `callback` receives the result of every poll.
+    pub fn watch<F>(&mut self, interval: Duration, mut callback: F)
+    where
+        F: FnMut(Result<bool, SVGError>) -> ControlFlow<()>,
+    {
+        loop {
+            thread::sleep(interval);
+
+            if callback(self.poll()).is_break() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use super::SVGWatcher;
+    use crate::BaseConfiguration;
+
+    #[test]
+    fn rerenders_on_configuration_change() {
+        let dir = std::env::temp_dir().join(format!("manycore_svg_watch_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("Could not create test directory");
+
+        let system_path = dir.join("system.xml");
+        let configuration_path = dir.join("configuration.json");
+        let output_path = dir.join("output.svg");
+
+        fs::copy("tests/VisualiserOutput1.xml", &system_path).expect("Could not copy system");
+        fs::copy("tests/conf2.json", &configuration_path).expect("Could not copy configuration");
+
+        let mut watcher = SVGWatcher::new(
+            &system_path,
+            &configuration_path,
+            &output_path,
+            BaseConfiguration::default(),
+        )
+        .expect("Could not start watcher");
+
+        let first = fs::read_to_string(&output_path).expect("Could not read first render");
+        assert!(!watcher.poll().expect("Could not poll"));
+
+        fs::copy("tests/conf3.json", &configuration_path).expect("Could not copy configuration");
+        fs::File::options()
+            .write(true)
+            .open(&configuration_path)
+            .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(10)))
+            .expect("Could not touch configuration");
+
+        assert!(watcher.poll().expect("Could not poll"));
+
+        let second = fs::read_to_string(&output_path).expect("Could not read second render");
+        assert_ne!(first, second);
+        assert!(!dir.join("output.svg.tmp").exists());
+
+        fs::remove_dir_all(&dir).expect("Could not clean up test directory");
+    }
+}